@@ -41,7 +41,24 @@ fn commit_date() -> Result<String, Ignore> {
     )?)
 }
 
+/// Version diesel gives to the most recent migration, to refuse a DB migrated by a newer build
+fn latest_migration_version() -> Result<String, Ignore> {
+    let mut versions = vec![];
+    for entry in std::fs::read_dir("migrations")? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if let Some(version) = name.split('_').next() {
+            versions.push(version.replace("-", ""));
+        }
+    }
+    versions.into_iter().max().ok_or(Ignore)
+}
+
 fn main() {
+    println!(
+        "cargo:rustc-env=IKRELLN_LATEST_MIGRATION={}",
+        latest_migration_version().unwrap_or_default()
+    );
+
     let gitref = match commit_hash() {
         Ok(v) => v.trim_end().to_string(),
         Err(_) => "N/A".to_string(),
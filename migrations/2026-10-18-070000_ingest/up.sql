@@ -0,0 +1,10 @@
+CREATE TABLE ingest
+(
+    id VARCHAR(36) NOT NULL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL,
+    processed_at TIMESTAMP,
+    nb_received INT NOT NULL,
+    nb_stored INT NOT NULL,
+    nb_failed INT NOT NULL,
+    nb_test_results INT NOT NULL
+);
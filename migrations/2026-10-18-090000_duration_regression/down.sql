@@ -0,0 +1 @@
+DROP TABLE duration_regression;
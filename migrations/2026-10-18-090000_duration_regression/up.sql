@@ -0,0 +1,14 @@
+CREATE TABLE duration_regression
+(
+    test_id VARCHAR NOT NULL,
+    trace_id VARCHAR(36) NOT NULL,
+    environment VARCHAR,
+    date TIMESTAMP NOT NULL,
+    duration BIGINT NOT NULL,
+    nb_results INT NOT NULL,
+    median BIGINT NOT NULL,
+    p90 BIGINT NOT NULL,
+    p95 BIGINT NOT NULL,
+    PRIMARY KEY (test_id, trace_id),
+    FOREIGN KEY (test_id) REFERENCES test_item(id)
+);
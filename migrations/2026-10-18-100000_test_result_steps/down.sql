@@ -0,0 +1,2 @@
+ALTER TABLE test_result
+DROP COLUMN steps;
@@ -0,0 +1,2 @@
+ALTER TABLE test_result
+ADD steps VARCHAR NOT NULL DEFAULT '[]';
@@ -0,0 +1,2 @@
+ALTER TABLE test_result
+ADD blamed_component VARCHAR;
@@ -0,0 +1,4 @@
+ALTER TABLE test_result
+DROP COLUMN attempt;
+ALTER TABLE test_result
+DROP COLUMN run_id;
@@ -0,0 +1,4 @@
+ALTER TABLE test_result
+ADD run_id VARCHAR;
+ALTER TABLE test_result
+ADD attempt INT NOT NULL DEFAULT 1;
@@ -0,0 +1,2 @@
+DROP INDEX idx_test_result_run_id;
+DROP TABLE test_run;
@@ -0,0 +1,11 @@
+CREATE TABLE test_run
+(
+    id VARCHAR NOT NULL PRIMARY KEY,
+    commit_sha VARCHAR,
+    branch VARCHAR,
+    ci_url VARCHAR,
+    start_date TIMESTAMP NOT NULL,
+    end_date TIMESTAMP NOT NULL
+);
+CREATE INDEX idx_test_run_branch ON test_run (branch, start_date);
+CREATE INDEX idx_test_result_run_id ON test_result (run_id);
@@ -0,0 +1,12 @@
+CREATE TABLE annotation_varchar
+(
+    annotation_id VARCHAR(36) NOT NULL PRIMARY KEY,
+    trace_id VARCHAR(36) NOT NULL,
+    span_id VARCHAR(36) NOT NULL,
+    ts TIMESTAMP NOT NULL,
+    value VARCHAR(255) NOT NULL,
+    FOREIGN KEY (trace_id, span_id) REFERENCES span (trace_id, id)
+);
+INSERT INTO annotation_varchar SELECT annotation_id, trace_id, span_id, ts, SUBSTR(value, 1, 255) FROM annotation;
+DROP TABLE annotation;
+ALTER TABLE annotation_varchar RENAME TO annotation;
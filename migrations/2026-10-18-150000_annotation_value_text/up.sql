@@ -0,0 +1,12 @@
+CREATE TABLE annotation_text
+(
+    annotation_id VARCHAR(36) NOT NULL PRIMARY KEY,
+    trace_id VARCHAR(36) NOT NULL,
+    span_id VARCHAR(36) NOT NULL,
+    ts TIMESTAMP NOT NULL,
+    value TEXT NOT NULL,
+    FOREIGN KEY (trace_id, span_id) REFERENCES span (trace_id, id)
+);
+INSERT INTO annotation_text SELECT annotation_id, trace_id, span_id, ts, value FROM annotation;
+DROP TABLE annotation;
+ALTER TABLE annotation_text RENAME TO annotation;
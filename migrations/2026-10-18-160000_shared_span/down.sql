@@ -0,0 +1,44 @@
+CREATE TABLE span_unique
+(
+    trace_id VARCHAR(36) NOT NULL,
+    id VARCHAR(36) NOT NULL UNIQUE,
+    parent_id VARCHAR(36),
+    name VARCHAR,
+    kind VARCHAR(10),
+    duration BIGINT,
+    ts TIMESTAMP,
+    debug BOOLEAN NOT NULL,
+    shared BOOLEAN NOT NULL,
+    local_endpoint_id VARCHAR(36),
+    remote_endpoint_id VARCHAR(36),
+    PRIMARY KEY (trace_id, id),
+    FOREIGN KEY (local_endpoint_id) REFERENCES endpoint (endpoint_id),
+    FOREIGN KEY (remote_endpoint_id) REFERENCES endpoint (endpoint_id)
+);
+-- the server side of a span shared with its client is dropped
+INSERT INTO span_unique SELECT trace_id, id, parent_id, name, kind, duration, ts, debug, shared, local_endpoint_id, remote_endpoint_id FROM span WHERE NOT (shared AND EXISTS (SELECT 1 FROM span AS client WHERE client.trace_id = span.trace_id AND client.id = span.id AND NOT client.shared));
+CREATE TABLE annotation_unique
+(
+    annotation_id VARCHAR(36) NOT NULL PRIMARY KEY,
+    trace_id VARCHAR(36) NOT NULL,
+    span_id VARCHAR(36) NOT NULL,
+    ts TIMESTAMP NOT NULL,
+    value TEXT NOT NULL,
+    FOREIGN KEY (trace_id, span_id) REFERENCES span_unique (trace_id, id)
+);
+INSERT INTO annotation_unique SELECT annotation.annotation_id, annotation.trace_id, annotation.span_id, annotation.ts, annotation.value FROM annotation JOIN span_unique ON span_unique.trace_id = annotation.trace_id AND span_unique.id = annotation.span_id AND span_unique.shared = annotation.shared;
+CREATE TABLE tag_unique
+(
+    span_id VARCHAR(36) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    value VARCHAR NOT NULL,
+    PRIMARY KEY (span_id, name),
+    FOREIGN KEY (span_id) REFERENCES span_unique (id)
+);
+INSERT INTO tag_unique SELECT tag.span_id, tag.name, tag.value FROM tag JOIN span_unique ON span_unique.trace_id = tag.trace_id AND span_unique.id = tag.span_id AND span_unique.shared = tag.shared;
+DROP TABLE tag;
+DROP TABLE annotation;
+DROP TABLE span;
+ALTER TABLE span_unique RENAME TO span;
+ALTER TABLE annotation_unique RENAME TO annotation;
+ALTER TABLE tag_unique RENAME TO tag;
@@ -0,0 +1,46 @@
+CREATE TABLE span_shared
+(
+    trace_id VARCHAR(36) NOT NULL,
+    id VARCHAR(36) NOT NULL,
+    parent_id VARCHAR(36),
+    name VARCHAR,
+    kind VARCHAR(10),
+    duration BIGINT,
+    ts TIMESTAMP,
+    debug BOOLEAN NOT NULL,
+    shared BOOLEAN NOT NULL,
+    local_endpoint_id VARCHAR(36),
+    remote_endpoint_id VARCHAR(36),
+    PRIMARY KEY (trace_id, id, shared),
+    FOREIGN KEY (local_endpoint_id) REFERENCES endpoint (endpoint_id),
+    FOREIGN KEY (remote_endpoint_id) REFERENCES endpoint (endpoint_id)
+);
+INSERT INTO span_shared SELECT trace_id, id, parent_id, name, kind, duration, ts, debug, shared, local_endpoint_id, remote_endpoint_id FROM span;
+CREATE TABLE annotation_shared
+(
+    annotation_id VARCHAR(36) NOT NULL PRIMARY KEY,
+    trace_id VARCHAR(36) NOT NULL,
+    span_id VARCHAR(36) NOT NULL,
+    shared BOOLEAN NOT NULL,
+    ts TIMESTAMP NOT NULL,
+    value TEXT NOT NULL,
+    FOREIGN KEY (trace_id, span_id, shared) REFERENCES span_shared (trace_id, id, shared)
+);
+INSERT INTO annotation_shared SELECT annotation.annotation_id, annotation.trace_id, annotation.span_id, span.shared, annotation.ts, annotation.value FROM annotation JOIN span ON span.trace_id = annotation.trace_id AND span.id = annotation.span_id;
+CREATE TABLE tag_shared
+(
+    trace_id VARCHAR(36) NOT NULL,
+    span_id VARCHAR(36) NOT NULL,
+    shared BOOLEAN NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    value VARCHAR NOT NULL,
+    PRIMARY KEY (trace_id, span_id, shared, name),
+    FOREIGN KEY (trace_id, span_id, shared) REFERENCES span_shared (trace_id, id, shared)
+);
+INSERT INTO tag_shared SELECT span.trace_id, tag.span_id, span.shared, tag.name, tag.value FROM tag JOIN span ON span.id = tag.span_id;
+DROP TABLE tag;
+DROP TABLE annotation;
+DROP TABLE span;
+ALTER TABLE span_shared RENAME TO span;
+ALTER TABLE annotation_shared RENAME TO annotation;
+ALTER TABLE tag_shared RENAME TO tag;
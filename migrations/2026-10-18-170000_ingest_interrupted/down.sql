@@ -0,0 +1,2 @@
+ALTER TABLE ingest
+DROP COLUMN interrupted;
@@ -0,0 +1,2 @@
+ALTER TABLE ingest
+ADD interrupted BOOLEAN NOT NULL DEFAULT FALSE;
@@ -42,6 +42,11 @@ impl From<error::JsonPayloadError> for IkError {
         }
     }
 }
+impl From<error::PayloadError> for IkError {
+    fn from(err: error::PayloadError) -> IkError {
+        IkError::BadRequest(format!("{}", err))
+    }
+}
 impl From<actix::MailboxError> for IkError {
     fn from(err: actix::MailboxError) -> IkError {
         error!("Got a {:?}", err);
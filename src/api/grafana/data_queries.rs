@@ -107,6 +107,10 @@ impl ToGrafana for crate::api::report::Report {
                 text: "Skipped",
                 column_type: "Number",
             },
+            Column {
+                text: "Passed On Retry",
+                column_type: "Number",
+            },
         ]
     }
     fn as_columns(self) -> Vec<Value> {
@@ -143,6 +147,16 @@ impl ToGrafana for crate::api::report::Report {
                 })
                 .unwrap_or(0) as i64,
         ));
+        column.push(Value::Number(
+            self.summary
+                .clone()
+                .and_then(|summary| {
+                    summary
+                        .get(&crate::engine::test_result::TestStatus::PassedOnRetry)
+                        .cloned()
+                })
+                .unwrap_or(0) as i64,
+        ));
         column
     }
 }
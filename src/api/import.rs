@@ -0,0 +1,70 @@
+use actix_web::*;
+use futures::Future;
+
+use super::span::{send_to_ingestor, INGEST_BODY_LIMIT};
+use super::{errors, AppState};
+use crate::opentracing::Span;
+
+/// Read the report sent as body, and ingest the spans built from it by `parse`
+/// with the import time
+fn import_report<F, E>(
+    req: &HttpRequest<AppState>,
+    parse: F,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>>
+where
+    F: FnOnce(&str, i64) -> Result<Vec<Span>, E> + 'static,
+    E: std::fmt::Display,
+{
+    req.body()
+        .limit(INGEST_BODY_LIMIT)
+        .from_err()
+        .and_then(move |body| {
+            let report = std::str::from_utf8(&body)
+                .map_err(|err| errors::IkError::BadRequest(format!("{}", err)))?;
+            let spans = parse(report, chrono::Utc::now().timestamp() * 1000 * 1000)
+                .map_err(|err| errors::IkError::BadRequest(format!("{}", err)))?;
+            Ok(HttpResponse::Ok().json(send_to_ingestor(spans)))
+        })
+        .responder()
+}
+
+pub fn import_junit(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    let environment = req.query().get("environment").cloned();
+    import_report(req, move |report, now| {
+        crate::import::junit::parse(report, environment.as_deref(), now)
+    })
+}
+
+pub fn import_libtest(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    let environment = req.query().get("environment").cloned();
+    let crate_name = req
+        .query()
+        .get("crate")
+        .cloned()
+        .unwrap_or_else(|| "cargo-test".to_string());
+    import_report(req, move |report, now| {
+        crate::import::libtest::parse(report, &crate_name, environment.as_deref(), now)
+    })
+}
+
+pub fn import_gotest(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    let environment = req.query().get("environment").cloned();
+    import_report(req, move |report, now| {
+        crate::import::gotest::parse(report, environment.as_deref(), now)
+    })
+}
+
+pub fn import_cucumber(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    let environment = req.query().get("environment").cloned();
+    import_report(req, move |report, now| {
+        crate::import::cucumber::parse(report, environment.as_deref(), now)
+    })
+}
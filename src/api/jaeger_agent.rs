@@ -0,0 +1,59 @@
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+
+use actix::prelude::*;
+use bytes::BytesMut;
+use tokio_codec::BytesCodec;
+use tokio_udp::{UdpFramed, UdpSocket};
+
+use crate::engine::ingestor::IngestEvents;
+
+pub struct JaegerAgent;
+
+impl Actor for JaegerAgent {
+    type Context = Context<Self>;
+}
+
+impl StreamHandler<(BytesMut, SocketAddr), io::Error> for JaegerAgent {
+    fn handle(&mut self, (datagram, from): (BytesMut, SocketAddr), _ctx: &mut Context<Self>) {
+        match crate::opentracing::jaeger_thrift::decode_emit_batch(&datagram) {
+            Ok(spans) => {
+                let ingest = IngestEvents::new(spans);
+                debug!(
+                    "ingesting {} event(s) from jaeger agent as {}",
+                    ingest.events.len(),
+                    ingest.ingest_id
+                );
+                actix::System::current()
+                    .registry()
+                    .get::<crate::engine::ingestor::Ingestor>()
+                    .do_send(ingest);
+            }
+            Err(err) => warn!("invalid jaeger batch from {}: {}", from, err),
+        }
+    }
+
+    fn error(&mut self, err: io::Error, _ctx: &mut Context<Self>) -> Running {
+        error!("error receiving jaeger batch: {}", err);
+        Running::Continue
+    }
+}
+
+pub fn serve(host: &str, port: u16) {
+    let socket = (host, port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid address"))
+        .and_then(|addr| UdpSocket::bind(&addr));
+    match socket {
+        Ok(socket) => {
+            info!("listening for jaeger batches on udp {}:{}", host, port);
+            JaegerAgent::create(|ctx| {
+                ctx.add_stream(UdpFramed::new(socket, BytesCodec::new()));
+                JaegerAgent
+            });
+        }
+        Err(err) => error!("error starting jaeger agent on {}:{}: {}", host, port, err),
+    }
+}
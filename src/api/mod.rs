@@ -11,7 +11,10 @@ use uuid;
 mod errors;
 mod grafana;
 mod healthcheck;
+mod import;
+pub mod jaeger_agent;
 pub mod report;
+pub mod run;
 mod script;
 pub mod span;
 pub mod test;
@@ -47,6 +50,9 @@ pub fn http_application() -> App<AppState> {
         r.method(http::Method::POST).f(span::ingest);
         r.method(http::Method::GET).f(span::get_spans_by_service);
     })
+    .resource("/api/v1/ingest/{ingestId}", |r| {
+        r.method(http::Method::GET).f(span::get_ingest)
+    })
     .resource("/api/v1/services", |r| {
         r.method(http::Method::GET).f(span::get_services)
     })
@@ -59,18 +65,84 @@ pub fn http_application() -> App<AppState> {
     .resource("/api/v1/dependencies", |r| {
         r.method(http::Method::GET).f(span::get_dependencies)
     })
+    .resource("/api/v2/spans", |r| {
+        r.method(http::Method::POST).f(span::ingest);
+        r.method(http::Method::GET).f(span::get_span_names_v2);
+    })
+    .resource("/api/v2/services", |r| {
+        r.method(http::Method::GET).f(span::get_services)
+    })
+    .resource("/api/v2/remoteServices", |r| {
+        r.method(http::Method::GET).f(span::get_remote_services)
+    })
+    .resource("/api/v2/trace/{traceId}", |r| {
+        r.method(http::Method::GET)
+            .f(span::get_spans_by_trace_id_v2)
+    })
+    .resource("/api/v2/traces", |r| {
+        r.method(http::Method::GET).f(span::get_traces_v2)
+    })
+    .resource("/api/v2/dependencies", |r| {
+        r.method(http::Method::GET).f(span::get_dependencies)
+    })
+    .resource("/api/v2/autocompleteKeys", |r| {
+        r.method(http::Method::GET).f(span::get_autocomplete_keys)
+    })
+    .resource("/api/v2/autocompleteValues", |r| {
+        r.method(http::Method::GET).f(span::get_autocomplete_values)
+    })
+    .resource("/v1/traces", |r| {
+        r.method(http::Method::POST).f(span::ingest_otlp)
+    })
+    .resource("/api/v1/import/junit", |r| {
+        r.method(http::Method::POST).f(import::import_junit)
+    })
+    .resource("/api/v1/import/libtest", |r| {
+        r.method(http::Method::POST).f(import::import_libtest)
+    })
+    .resource("/api/v1/import/cucumber", |r| {
+        r.method(http::Method::POST).f(import::import_cucumber)
+    })
+    .resource("/api/v1/import/gotest", |r| {
+        r.method(http::Method::POST).f(import::import_gotest)
+    })
     .resource("/api/v1/tests", |r| {
         r.method(http::Method::GET).f(test::get_tests_by_parent)
     })
+    .resource("/api/v1/tests/flaky", |r| {
+        r.method(http::Method::GET).f(test::get_flaky_tests)
+    })
+    .resource("/api/v1/tests/regressions", |r| {
+        r.method(http::Method::GET)
+            .f(test::get_duration_regressions)
+    })
     .resource("/api/v1/tests/{testId}", |r| {
         r.method(http::Method::GET).f(test::get_test)
     })
+    .resource("/api/v1/tests/{testId}/baseline", |r| {
+        r.method(http::Method::GET).f(test::get_duration_baseline)
+    })
+    .resource("/api/v1/tests/{testId}/tracediff", |r| {
+        r.method(http::Method::GET).f(test::get_trace_diff)
+    })
     .resource("/api/v1/testresults", |r| {
         r.method(http::Method::GET).f(test::get_test_results)
     })
     .resource("/api/v1/environments", |r| {
         r.method(http::Method::GET).f(test::get_environments)
     })
+    .resource("/api/v1/failures", |r| {
+        r.method(http::Method::GET).f(test::get_failures)
+    })
+    .resource("/api/v1/runs", |r| {
+        r.method(http::Method::GET).f(run::get_runs)
+    })
+    .resource("/api/v1/runs/compare", |r| {
+        r.method(http::Method::GET).f(run::compare)
+    })
+    .resource("/api/v1/runs/{runId}", |r| {
+        r.method(http::Method::GET).f(run::get_run)
+    })
     .resource("/api/v1/scripts", |r| {
         r.method(http::Method::GET).f(script::list_scripts);
         r.method(http::Method::POST).f(script::save_script);
@@ -0,0 +1,126 @@
+use actix_web::{AsyncResponder, HttpRequest, HttpResponse};
+use futures::future::result;
+use futures::Future;
+use serde_urlencoded;
+
+use super::{errors, AppState};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestRunsQueryParams {
+    pub branch: Option<String>,
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+pub fn get_runs(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match serde_urlencoded::from_str::<TestRunsQueryParams>(req.query_string()) {
+        Ok(query_params) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::run::GetTestRuns(query_params.into()))
+            .from_err()
+            .and_then(|res| Ok(HttpResponse::Ok().json(res)))
+            .responder(),
+        Err(err) => result(Err(super::errors::IkError::BadRequest(format!(
+            "invalid query parameters: '{}'",
+            err
+        ))))
+        .responder(),
+    }
+}
+
+pub fn get_run(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match req.match_info().get("runId") {
+        Some(run_id) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::run::GetTestRun(run_id.to_string()))
+            .from_err()
+            .and_then(|res| match res {
+                Some(test_run) => Ok(HttpResponse::Ok().json(test_run)),
+                None => Err(super::errors::IkError::NotFound(
+                    "test run not found".to_string(),
+                )),
+            })
+            .responder(),
+        None => result(Err(super::errors::IkError::BadRequest(
+            "missing path parameter".to_string(),
+        )))
+        .responder(),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareQueryParams {
+    pub base_run: Option<String>,
+    pub base_environment: Option<String>,
+    pub target_run: Option<String>,
+    pub target_environment: Option<String>,
+    pub report_group: Option<String>,
+    pub report_name: Option<String>,
+    pub lookback: Option<i64>,
+}
+
+fn compared_side(
+    run: Option<String>,
+    environment: Option<String>,
+) -> Option<crate::engine::compare::ComparedSide> {
+    match (run, environment) {
+        (Some(run), None) => Some(crate::engine::compare::ComparedSide::Run(run)),
+        (None, Some(environment)) => Some(crate::engine::compare::ComparedSide::Environment(
+            environment,
+        )),
+        _ => None,
+    }
+}
+
+pub fn compare(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    let query_params = match serde_urlencoded::from_str::<CompareQueryParams>(req.query_string()) {
+        Ok(query_params) => query_params,
+        Err(err) => {
+            return result(Err(super::errors::IkError::BadRequest(format!(
+                "invalid query parameters: '{}'",
+                err
+            ))))
+            .responder()
+        }
+    };
+    let report = match (query_params.report_group, query_params.report_name) {
+        (Some(report_group), Some(report_name)) => Some((report_group, report_name)),
+        (None, None) => None,
+        _ => {
+            return result(Err(super::errors::IkError::BadRequest(
+                "reportGroup and reportName must be set together".to_string(),
+            )))
+            .responder()
+        }
+    };
+    match (
+        compared_side(query_params.base_run, query_params.base_environment),
+        compared_side(query_params.target_run, query_params.target_environment),
+    ) {
+        (Some(base), Some(target)) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::run::CompareTestResults {
+                base,
+                target,
+                report,
+                lookback: query_params.lookback.map(chrono::Duration::milliseconds),
+            })
+            .from_err()
+            .and_then(|res| match res {
+                Some(comparison) => Ok(HttpResponse::Ok().json(comparison)),
+                None => Err(super::errors::IkError::NotFound(
+                    "report not found".to_string(),
+                )),
+            })
+            .responder(),
+        _ => result(Err(super::errors::IkError::BadRequest(
+            "one run or environment must be set for each side of the comparison".to_string(),
+        )))
+        .responder(),
+    }
+}
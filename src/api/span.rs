@@ -7,30 +7,114 @@ use super::{errors, AppState};
 use crate::engine::ingestor::IngestEvents;
 use crate::opentracing::Span;
 
+static TRACE_SPANS_LIMIT: i64 = 1000;
+static TRACES_QUERY_LIMIT: i64 = 10;
+pub(super) static INGEST_BODY_LIMIT: usize = 16 * 1024 * 1024;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IngestResponse {
-    ingest_id: crate::engine::IngestId,
+    pub ingest_id: crate::engine::IngestId,
     pub nb_events: usize,
 }
 
 pub fn ingest(
     req: &HttpRequest<AppState>,
 ) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match req.content_type() {
+        "application/x-protobuf" => req
+            .body()
+            .limit(INGEST_BODY_LIMIT)
+            .from_err()
+            .and_then(|body| {
+                crate::opentracing::zipkin_proto3::decode(&body)
+                    .map_err(|err| errors::IkError::BadRequest(format!("{}", err)))
+            })
+            .and_then(ingest_spans)
+            .responder(),
+        "application/x-thrift" => req
+            .body()
+            .limit(INGEST_BODY_LIMIT)
+            .from_err()
+            .and_then(|body| {
+                crate::opentracing::zipkin_thrift::decode(&body)
+                    .map_err(|err| errors::IkError::BadRequest(format!("{}", err)))
+            })
+            .and_then(ingest_spans)
+            .responder(),
+        _ => req
+            .json()
+            .limit(INGEST_BODY_LIMIT)
+            .from_err()
+            .and_then(ingest_spans)
+            .responder(),
+    }
+}
+
+pub(super) fn send_to_ingestor(spans: Vec<Span>) -> IngestResponse {
     let ingestor = actix::System::current()
         .registry()
         .get::<crate::engine::ingestor::Ingestor>();
-    req.json()
+    let nb_spans = spans.len();
+    let ingest = IngestEvents::new(spans);
+    let ingest_id = ingest.ingest_id.clone();
+    debug!("ingesting {} event(s) as {}", nb_spans, ingest_id,);
+    ingestor.do_send(ingest);
+    IngestResponse {
+        ingest_id,
+        nb_events: nb_spans,
+    }
+}
+
+fn ingest_spans(spans: Vec<Span>) -> Result<HttpResponse, errors::IkError> {
+    Ok(HttpResponse::Ok().json(send_to_ingestor(spans)))
+}
+
+pub fn get_ingest(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match req.match_info().get("ingestId") {
+        Some(ingest_id) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::ingest::GetIngest(ingest_id.to_string()))
+            .from_err()
+            .and_then(|res| match res {
+                Some(ingest) => Ok(HttpResponse::Ok().json(ingest)),
+                None => Err(super::errors::IkError::NotFound(
+                    "ingest not found".to_string(),
+                )),
+            })
+            .responder(),
+
+        _ => result(Err(super::errors::IkError::BadRequest(
+            "missing ingestId path parameter".to_string(),
+        )))
+        .responder(),
+    }
+}
+
+pub fn ingest_otlp(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    let is_protobuf = req.content_type() == "application/x-protobuf";
+    req.body()
+        .limit(INGEST_BODY_LIMIT)
         .from_err()
-        .and_then(move |val: Vec<Span>| {
-            let nb_spans = val.len();
-            let ingest = IngestEvents::new(val);
-            let ingest_id = ingest.ingest_id.clone();
-            debug!("ingesting {} event(s) as {}", nb_spans, ingest_id,);
-            ingestor.do_send(ingest);
-            Ok(HttpResponse::Ok().json(IngestResponse {
-                ingest_id,
-                nb_events: nb_spans,
-            }))
+        .and_then(move |body| {
+            let spans = if is_protobuf {
+                crate::opentracing::otlp::decode_protobuf(&body)
+                    .map_err(|err| errors::IkError::BadRequest(format!("{}", err)))?
+            } else {
+                crate::opentracing::otlp::decode_json(&body)
+                    .map_err(|err| errors::IkError::BadRequest(format!("{}", err)))?
+            };
+            send_to_ingestor(spans);
+            // an empty ExportTraceServiceResponse
+            Ok(if is_protobuf {
+                HttpResponse::Ok()
+                    .content_type("application/x-protobuf")
+                    .finish()
+            } else {
+                HttpResponse::Ok().json(HashMap::<String, String>::new())
+            })
         })
         .responder()
 }
@@ -75,6 +159,143 @@ pub fn get_spans_by_service(
     }
 }
 
+pub fn get_remote_services(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match req.query().get("serviceName") {
+        Some(service_name) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::span::GetRemoteServices(
+                service_name.to_string(),
+            ))
+            .from_err()
+            .and_then(|services| Ok(HttpResponse::Ok().json(services)))
+            .responder(),
+
+        _ => result(Err(super::errors::IkError::BadRequest(
+            "missing serviceName query parameter".to_string(),
+        )))
+        .responder(),
+    }
+}
+
+pub fn get_span_names_v2(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match req.query().get("serviceName") {
+        Some(_) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::span::GetSpans(
+                crate::db::read::span::SpanQuery::from_req(&req).only_local_service(),
+            ))
+            .from_err()
+            .and_then(|res| {
+                let mut span_names = res
+                    .iter()
+                    .filter_map(|span| span.name.clone())
+                    .collect::<Vec<String>>();
+                span_names.sort_unstable();
+                span_names.dedup();
+                Ok(HttpResponse::Ok().json(span_names))
+            })
+            .responder(),
+
+        _ => result(Err(super::errors::IkError::BadRequest(
+            "missing serviceName query parameter".to_string(),
+        )))
+        .responder(),
+    }
+}
+
+pub fn get_autocomplete_keys(
+    _req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    crate::DB_READ_EXECUTOR_POOL
+        .send(crate::db::read::span::GetTagKeys)
+        .from_err()
+        .and_then(|keys| Ok(HttpResponse::Ok().json(keys)))
+        .responder()
+}
+
+pub fn get_autocomplete_values(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match req.query().get("key") {
+        Some(key) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::span::GetTagValues(key.to_string()))
+            .from_err()
+            .and_then(|values| Ok(HttpResponse::Ok().json(values)))
+            .responder(),
+
+        _ => result(Err(super::errors::IkError::BadRequest(
+            "missing key query parameter".to_string(),
+        )))
+        .responder(),
+    }
+}
+
+fn as_v2(spans: Vec<Span>) -> Vec<Span> {
+    spans
+        .into_iter()
+        .map(|span| Span {
+            binary_annotations: vec![],
+            ..span
+        })
+        .collect()
+}
+
+pub fn get_spans_by_trace_id_v2(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match req.match_info().get("traceId") {
+        Some(trace_id) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::span::GetTrace(trace_id.to_string()))
+            .from_err()
+            .and_then(|res| match res.len() {
+                0 => Err(super::errors::IkError::NotFound(
+                    "trace not found".to_string(),
+                )),
+                _ => Ok(HttpResponse::Ok().json(as_v2(res))),
+            })
+            .responder(),
+
+        _ => result(Err(super::errors::IkError::BadRequest(
+            "missing traceId path parameter".to_string(),
+        )))
+        .responder(),
+    }
+}
+
+pub fn get_traces_v2(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    let nb_traces = req
+        .query()
+        .get("limit")
+        .and_then(|s| s.parse::<i64>().ok())
+        .map(|v| v.min(crate::db::read::span::SPAN_QUERY_LIMIT))
+        .unwrap_or(TRACES_QUERY_LIMIT);
+    crate::DB_READ_EXECUTOR_POOL
+        .send(crate::db::read::span::GetTraceIds(
+            crate::db::read::span::SpanQuery::from_req(&req)
+                .with_limit(nb_traces)
+                .only_local_service(),
+        ))
+        .from_err()
+        .and_then(|trace_ids| {
+            futures::future::join_all(trace_ids.into_iter().map(|trace_id| {
+                crate::DB_READ_EXECUTOR_POOL.send(crate::db::read::span::GetSpans(
+                    crate::db::read::span::SpanQuery::default()
+                        .with_trace_id(trace_id)
+                        .with_limit(TRACE_SPANS_LIMIT),
+                ))
+            }))
+            .from_err()
+        })
+        .and_then(|traces| {
+            Ok(HttpResponse::Ok().json(traces.into_iter().map(as_v2).collect::<Vec<_>>()))
+        })
+        .responder()
+}
+
 pub fn get_spans_by_trace_id(
     req: &HttpRequest<AppState>,
 ) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
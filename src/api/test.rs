@@ -18,6 +18,7 @@ pub struct TestResultsQueryParams {
     pub status: Option<crate::engine::test_result::TestStatus>,
     pub test_id: Option<String>,
     pub environment: Option<String>,
+    pub run_id: Option<String>,
     pub min_duration: Option<i64>,
     pub max_duration: Option<i64>,
     pub ts: Option<i64>,
@@ -49,6 +50,7 @@ pub struct TestDetails {
     pub path: Vec<TestItem>,
     pub children: Vec<TestItem>,
     pub last_results: Vec<crate::engine::test_result::TestResult>,
+    pub flaky: f64,
 }
 pub fn get_test(
     req: &HttpRequest<AppState>,
@@ -91,6 +93,7 @@ pub fn get_test(
                         })
                         .collect(),
                     last_results: vec![],
+                    flaky: 0.0,
                 })),
             })
             .responder(),
@@ -145,3 +148,169 @@ pub fn get_environments(
         .and_then(|res| Ok(HttpResponse::Ok().json(res)))
         .responder()
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FlakyTestsQueryParams {
+    environment: Option<String>,
+    lookback: Option<i64>,
+    window: Option<usize>,
+}
+
+pub fn get_flaky_tests(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match serde_urlencoded::from_str::<FlakyTestsQueryParams>(req.query_string()) {
+        Ok(params) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::test::GetFlakyTests {
+                environment: params.environment,
+                lookback: params.lookback.map(chrono::Duration::milliseconds),
+                window: params
+                    .window
+                    .unwrap_or(crate::engine::flaky::FLAKY_WINDOW)
+                    .max(2),
+            })
+            .from_err()
+            .and_then(|res| Ok(HttpResponse::Ok().json(res)))
+            .responder(),
+        Err(err) => result(Err(super::errors::IkError::BadRequest(format!(
+            "invalid query parameters: '{}'",
+            err
+        ))))
+        .responder(),
+    }
+}
+
+#[derive(Deserialize)]
+struct FailuresQueryParams {
+    environment: Option<String>,
+    lookback: Option<i64>,
+}
+
+pub fn get_failures(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match serde_urlencoded::from_str::<FailuresQueryParams>(req.query_string()) {
+        Ok(params) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::test::GetFailureClusters {
+                environment: params.environment,
+                lookback: params.lookback.map(chrono::Duration::milliseconds),
+            })
+            .from_err()
+            .and_then(|res| Ok(HttpResponse::Ok().json(res)))
+            .responder(),
+        Err(err) => result(Err(super::errors::IkError::BadRequest(format!(
+            "invalid query parameters: '{}'",
+            err
+        ))))
+        .responder(),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DurationRegressionsQueryParams {
+    pub test_id: Option<String>,
+    pub environment: Option<String>,
+    pub limit: Option<i64>,
+}
+
+pub fn get_duration_regressions(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    match serde_urlencoded::from_str::<DurationRegressionsQueryParams>(req.query_string()) {
+        Ok(params) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::test::GetDurationRegressions(params.into()))
+            .from_err()
+            .and_then(|res| Ok(HttpResponse::Ok().json(res)))
+            .responder(),
+        Err(err) => result(Err(super::errors::IkError::BadRequest(format!(
+            "invalid query parameters: '{}'",
+            err
+        ))))
+        .responder(),
+    }
+}
+
+#[derive(Deserialize)]
+struct BaselineQueryParams {
+    environment: Option<String>,
+}
+
+pub fn get_duration_baseline(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    let params = serde_urlencoded::from_str::<BaselineQueryParams>(req.query_string());
+    match (req.match_info().get("testId"), params) {
+        (Some(test_id), Ok(params)) => {
+            let environment = params.environment;
+            crate::DB_READ_EXECUTOR_POOL
+                .send(crate::db::read::test::GetTestResults(
+                    crate::db::read::test::TestResultQuery {
+                        test_id: Some(test_id.to_string()),
+                        environment: environment.clone(),
+                        status: Some(crate::engine::test_result::TestStatus::Success.as_i32()),
+                        limit: crate::engine::baseline::BASELINE_WINDOW,
+                        ..Default::default()
+                    },
+                ))
+                .from_err()
+                .and_then(move |history| {
+                    crate::engine::baseline::baseline(
+                        history.iter().filter(|tr| tr.environment == environment),
+                    )
+                    .map(|baseline| HttpResponse::Ok().json(baseline))
+                    .ok_or_else(|| {
+                        super::errors::IkError::NotFound(
+                            "not enough results for a baseline".to_string(),
+                        )
+                    })
+                })
+                .responder()
+        }
+        (None, _) => result(Err(super::errors::IkError::BadRequest(
+            "missing testId path parameter".to_string(),
+        )))
+        .responder(),
+        (_, Err(err)) => result(Err(super::errors::IkError::BadRequest(format!(
+            "invalid query parameters: '{}'",
+            err
+        ))))
+        .responder(),
+    }
+}
+
+#[derive(Deserialize)]
+struct TraceDiffQueryParams {
+    environment: Option<String>,
+}
+
+pub fn get_trace_diff(
+    req: &HttpRequest<AppState>,
+) -> Box<dyn Future<Item = HttpResponse, Error = errors::IkError>> {
+    let params = serde_urlencoded::from_str::<TraceDiffQueryParams>(req.query_string());
+    match (req.match_info().get("testId"), params) {
+        (Some(test_id), Ok(params)) => crate::DB_READ_EXECUTOR_POOL
+            .send(crate::db::read::test::GetTraceDiff {
+                test_id: test_id.to_string(),
+                environment: params.environment,
+            })
+            .from_err()
+            .and_then(|res| match res {
+                Some(trace_diff) => Ok(HttpResponse::Ok().json(trace_diff)),
+                None => Err(super::errors::IkError::NotFound(
+                    "no failure following a success with their traces".to_string(),
+                )),
+            })
+            .responder(),
+        (None, _) => result(Err(super::errors::IkError::BadRequest(
+            "missing testId path parameter".to_string(),
+        )))
+        .responder(),
+        (_, Err(err)) => result(Err(super::errors::IkError::BadRequest(format!(
+            "invalid query parameters: '{}'",
+            err
+        ))))
+        .responder(),
+    }
+}
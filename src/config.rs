@@ -18,12 +18,21 @@ impl Default for CleanUpConfig {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct JaegerAgentConfig {
+    pub host: String,
+    pub port: u16,
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub host: String,
     pub port: u16,
     pub db_url: String,
     pub cleanup: CleanUpConfig,
+    pub jaeger_agent: Option<JaegerAgentConfig>,
+    pub trace_quiet_period: u32,
+    pub migrate_only: bool,
 }
 impl Default for Config {
     fn default() -> Self {
@@ -32,6 +41,9 @@ impl Default for Config {
             port: 7878,
             db_url: "127.0.0.1:5042".to_string(),
             cleanup: CleanUpConfig::default(),
+            jaeger_agent: None,
+            trace_quiet_period: 500,
+            migrate_only: false,
         }
     }
 }
@@ -55,6 +67,13 @@ pub struct CleanUpConfigLoader {
     pub schedule: Option<u32>,
 }
 
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename = "jaeger_agent")]
+pub struct JaegerAgentConfigLoader {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+}
+
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct ConfigLoader {
     pub host: Option<String>,
@@ -62,6 +81,8 @@ pub struct ConfigLoader {
     pub db_nb_connection: Option<usize>,
     pub db_url: Option<String>,
     pub cleanup: Option<CleanUpConfigLoader>,
+    pub jaeger_agent: Option<JaegerAgentConfigLoader>,
+    pub trace_quiet_period: Option<u32>,
 }
 
 #[derive(Debug, Clone, Deserialize, StructOpt)]
@@ -86,6 +107,29 @@ pub struct ConfigLoaderCmd {
         help = "URL to connect to the database"
     )]
     pub db_url: Option<String>,
+    #[structopt(
+        long = "jaeger-agent-host",
+        env = "JAEGER_AGENT_HOST",
+        help = "Listen for Jaeger batches over UDP on the specified host, by default 0.0.0.0"
+    )]
+    pub jaeger_agent_host: Option<String>,
+    #[structopt(
+        long = "jaeger-agent-port",
+        env = "JAEGER_AGENT_PORT",
+        help = "Listen for Jaeger batches over UDP on the specified port, disabled by default"
+    )]
+    pub jaeger_agent_port: Option<u16>,
+    #[structopt(
+        long = "trace-quiet-period",
+        env = "TRACE_QUIET_PERIOD",
+        help = "Milliseconds without new spans before a trace is parsed, by default 500"
+    )]
+    pub trace_quiet_period: Option<u32>,
+    #[structopt(
+        long = "migrate-only",
+        help = "Apply the database migrations then exit without starting the server"
+    )]
+    pub migrate_only: bool,
 }
 
 // fn load_config_from_toml() -> ConfigLoader {
@@ -107,6 +151,20 @@ fn load_config_from_hocon() -> ConfigLoader {
         .unwrap_or_else(|_| ConfigLoader::default())
 }
 
+/// The jaeger agent is started only when a port is configured, arguments win over the config file
+fn jaeger_agent_config(
+    host: Option<String>,
+    port: Option<u16>,
+    from_hocon: JaegerAgentConfigLoader,
+) -> Option<JaegerAgentConfig> {
+    port.or(from_hocon.port).map(|port| JaegerAgentConfig {
+        host: host
+            .or(from_hocon.host)
+            .unwrap_or_else(|| "0.0.0.0".to_string()),
+        port,
+    })
+}
+
 fn merge_configs() -> Result<Config, String> {
     let from_args = ConfigLoaderCmd::from_args();
     let from_hocon = load_config_from_hocon();
@@ -138,5 +196,54 @@ fn merge_configs() -> Result<Config, String> {
                 .and_then(|cleanup| cleanup.schedule)
                 .unwrap_or(default.cleanup.schedule),
         },
+        jaeger_agent: jaeger_agent_config(
+            from_args.jaeger_agent_host,
+            from_args.jaeger_agent_port,
+            from_hocon.jaeger_agent.unwrap_or_default(),
+        ),
+        trace_quiet_period: from_args
+            .trace_quiet_period
+            .or(from_hocon.trace_quiet_period)
+            .unwrap_or(default.trace_quiet_period),
+        migrate_only: from_args.migrate_only,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jaeger_agent_needs_a_port() {
+        assert!(jaeger_agent_config(
+            Some("127.0.0.1".to_string()),
+            None,
+            JaegerAgentConfigLoader::default()
+        )
+        .is_none());
+
+        let config = jaeger_agent_config(
+            None,
+            None,
+            JaegerAgentConfigLoader {
+                host: None,
+                port: Some(6831),
+            },
+        )
+        .unwrap();
+        assert_eq!(config.host, "0.0.0.0");
+        assert_eq!(config.port, 6831);
+
+        let config = jaeger_agent_config(
+            Some("127.0.0.1".to_string()),
+            Some(6832),
+            JaegerAgentConfigLoader {
+                host: Some("10.0.0.1".to_string()),
+                port: Some(6831),
+            },
+        )
+        .unwrap();
+        assert_eq!(config.host, "127.0.0.1");
+        assert_eq!(config.port, 6832);
+    }
+}
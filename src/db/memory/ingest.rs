@@ -0,0 +1,32 @@
+use crate::engine::ingestor::Ingest;
+
+impl crate::db::storage::IngestStorage for super::MemoryStorage {
+    fn save_ingest(&mut self, ingest: Ingest) {
+        self.write()
+            .ingests
+            .insert(ingest.ingest_id.0.clone(), ingest);
+    }
+
+    fn get_ingest(&self, ingest_id: &str) -> Option<Ingest> {
+        self.read().ingests.get(ingest_id).cloned()
+    }
+
+    fn interrupt_unfinished_ingests(
+        &mut self,
+        at: chrono::NaiveDateTime,
+    ) -> Result<usize, crate::db::storage::StorageError> {
+        let mut data = self.write();
+        let mut nb_interrupted = 0;
+        for ingest in data
+            .ingests
+            .values_mut()
+            .filter(|ingest| ingest.processed_at.is_none())
+        {
+            ingest.processed_at = Some(at);
+            ingest.nb_failed = ingest.nb_received - ingest.nb_stored;
+            ingest.interrupted = true;
+            nb_interrupted += 1;
+        }
+        Ok(nb_interrupted)
+    }
+}
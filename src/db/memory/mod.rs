@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::db::storage::ResultCleanupStatus;
+
+mod ingest;
+mod reports;
+mod run;
+mod scripts;
+mod span;
+mod test;
+
+pub static URL_SCHEME: &str = "memory://";
+
+#[derive(Default)]
+struct Data {
+    /// spans by trace ID, span ID and if shared, the client and server sides of a shared
+    /// span are kept apart
+    spans: HashMap<(String, String, bool), crate::opentracing::Span>,
+    test_items: HashMap<String, test::TestItemRow>,
+    test_results: Vec<test::TestResultRow>,
+    duration_regressions: Vec<crate::engine::baseline::DurationRegression>,
+    reports: Vec<reports::ReportRow>,
+    results_in_report: Vec<reports::ResultInReportRow>,
+    scripts: Vec<crate::engine::streams::Script>,
+    ingests: HashMap<String, crate::engine::ingestor::Ingest>,
+    test_runs: HashMap<String, crate::engine::run::TestRun>,
+}
+
+lazy_static! {
+    static ref SHARED: MemoryStorage = MemoryStorage::default();
+}
+
+/// Storage kept in memory and lost on restart, shared by all executors of the process
+#[derive(Clone, Default)]
+pub struct MemoryStorage(Arc<RwLock<Data>>);
+
+impl MemoryStorage {
+    pub fn shared() -> Self {
+        SHARED.clone()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Data> {
+        self.0.read().expect("memory storage poisoned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Data> {
+        self.0.write().expect("memory storage poisoned")
+    }
+}
+
+// timestamps are in microseconds
+fn to_micros(date: chrono::NaiveDateTime) -> i64 {
+    date.timestamp() * 1000 * 1000 + i64::from(date.timestamp_subsec_micros())
+}
+
+fn delay_limit(delay: u32) -> i64 {
+    to_micros(chrono::Utc::now().naive_utc() - chrono::Duration::milliseconds(i64::from(delay)))
+}
+
+impl super::storage::Storage for MemoryStorage {
+    fn clean_up(&mut self) {
+        let mut data = self.write();
+
+        let results_limit = delay_limit(crate::CONFIG.cleanup.delay_test_results);
+        let (deleted, kept): (Vec<_>, Vec<_>) = data.test_results.drain(..).partition(|tr| {
+            tr.date < results_limit && tr.cleanup_status == ResultCleanupStatus::Shell
+        });
+        data.test_results = kept;
+        data.results_in_report.retain(|in_report| {
+            !deleted
+                .iter()
+                .any(|tr| tr.trace_id == in_report.trace_id && tr.test_id == in_report.test_id)
+        });
+        data.duration_regressions.retain(|regression| {
+            !deleted
+                .iter()
+                .any(|tr| tr.trace_id == regression.trace_id && tr.test_id == regression.test_id)
+        });
+
+        let spans_limit = delay_limit(crate::CONFIG.cleanup.delay_spans);
+        let mut to_clean = vec![];
+        for tr in &mut data.test_results {
+            if tr.date < spans_limit && tr.cleanup_status == ResultCleanupStatus::WithData {
+                tr.cleanup_status = ResultCleanupStatus::Shell;
+                to_clean.push(tr.trace_id.clone());
+            }
+        }
+        data.spans
+            .retain(|_, span| !to_clean.contains(&span.trace_id));
+        data.test_runs.retain(|_, run| run.end >= results_limit);
+
+        let reports_limit = delay_limit(crate::CONFIG.cleanup.delay_reports);
+        let (removed, kept): (Vec<_>, Vec<_>) = data
+            .reports
+            .drain(..)
+            .partition(|report| to_micros(report.last_update) < reports_limit);
+        data.reports = kept;
+        data.results_in_report.retain(|in_report| {
+            removed
+                .iter()
+                .all(|report| report.id != in_report.report_id)
+        });
+
+        info!(
+            "deleted {} test results, cleaned {} and removed {} reports",
+            deleted.len(),
+            to_clean.len(),
+            removed.len(),
+        );
+    }
+
+    fn is_connected(&self) -> bool {
+        true
+    }
+
+    fn migrate(&mut self) -> Result<(), crate::db::storage::StorageError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::read::span::{AnnotationQuery, SpanQuery};
+    use crate::db::read::test::{TestItemQuery, TestResultQuery};
+    use crate::db::storage::{SpanStorage, TestStorage};
+    use crate::engine::test_result::{TestResult, TestStatus};
+    use crate::opentracing::span::SpanBuilder;
+
+    use super::MemoryStorage;
+
+    fn span(id: &str) -> SpanBuilder {
+        SpanBuilder::new(id)
+            .name("Span Name")
+            .service("Service")
+            .timestamp(super::to_micros(chrono::Utc::now().naive_utc()) - 1000)
+    }
+
+    #[test]
+    fn can_save_and_query_spans() {
+        let mut storage = MemoryStorage::default();
+        storage
+            .save_spans(&[
+                span("root")
+                    .duration(None)
+                    .tag("Test.Name", "value")
+                    .build(),
+                span("child").child_of("root").duration(Some(5)).build(),
+            ])
+            .unwrap();
+        storage
+            .save_spans(&[span("root").duration(Some(10)).build()])
+            .unwrap();
+
+        let spans = storage.get_spans(SpanQuery::default().with_trace_id("trace".to_string()));
+        assert_eq!(spans.len(), 2);
+        let root = spans.iter().find(|span| span.id == "root").unwrap();
+        assert_eq!(root.duration, Some(10));
+        assert_eq!(root.name, Some("span name".to_string()));
+        assert_eq!(root.tags.get("test.name"), Some(&"value".to_string()));
+
+        let spans = storage.get_spans(SpanQuery {
+            annotation_query: vec![AnnotationQuery::Key("test.name".to_string())],
+            ..Default::default()
+        });
+        assert_eq!(spans.len(), 1);
+
+        assert_eq!(storage.get_services(), vec!["service".to_string()]);
+        assert_eq!(storage.get_tag_keys(), vec!["test.name".to_string()]);
+    }
+
+    #[test]
+    fn can_save_and_query_test_results() {
+        let mut storage = MemoryStorage::default();
+        let test_result = TestResult {
+            test_id: "n/a".to_string(),
+            path: vec!["suite".to_string(), "class".to_string()],
+            date: super::to_micros(chrono::Utc::now().naive_utc()) - 1000,
+            ..Default::default()
+        };
+        let saved = storage.save_test_result(test_result.clone());
+        assert_ne!(saved.test_id, "n/a");
+        let saved_again = storage.save_test_result(TestResult {
+            status: TestStatus::Failure,
+            ..test_result
+        });
+        assert_eq!(saved.test_id, saved_again.test_id);
+
+        let results = storage.get_test_results(TestResultQuery::default());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].status, TestStatus::Failure);
+        assert_eq!(results[0].path, vec!["suite", "class"]);
+
+        let items = storage.get_test_items(TestItemQuery {
+            id: Some(saved.test_id),
+            with_full_path: true,
+            with_traces: true,
+            ..Default::default()
+        });
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path.len(), 2);
+        assert_eq!(items[0].last_results.len(), 1);
+    }
+}
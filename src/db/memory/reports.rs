@@ -0,0 +1,228 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::engine::test_result::TestStatus;
+
+static REPORT_QUERY_LIMIT: usize = 200;
+
+#[derive(Debug, Clone)]
+pub(super) struct ReportRow {
+    pub id: String,
+    name: String,
+    folder: String,
+    created_on: chrono::NaiveDateTime,
+    pub last_update: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct ResultInReportRow {
+    pub report_id: String,
+    pub test_id: String,
+    pub trace_id: String,
+    category: String,
+    environment: Option<String>,
+    status: TestStatus,
+}
+
+// environment as queried, "None" being results without environment
+fn environment_matches(row_environment: &Option<String>, environment: Option<&str>) -> bool {
+    match environment {
+        Some("None") | None => row_environment.is_none(),
+        Some(v) => row_environment.as_ref().map(String::as_str) == Some(v),
+    }
+}
+
+impl super::Data {
+    fn environments_in_report(&self, report_id: &str) -> Vec<String> {
+        self.results_in_report
+            .iter()
+            .filter(|in_report| in_report.report_id == report_id)
+            .map(|in_report| match in_report.environment {
+                Some(ref v) => v.clone(),
+                None => "None".to_string(),
+            })
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+impl crate::db::storage::ReportStorage for super::MemoryStorage {
+    fn save_result_for_report(
+        &mut self,
+        result_for_report: crate::engine::report::ResultForReport,
+    ) {
+        let mut data = self.write();
+        let now = chrono::Utc::now().naive_utc();
+
+        let existing = data.reports.iter_mut().find(|report| {
+            report.folder == result_for_report.report_group
+                && report.name == result_for_report.report_name
+        });
+        let found_report_id = match existing {
+            Some(existing) => {
+                existing.last_update = now;
+                existing.id.clone()
+            }
+            None => {
+                let new_id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+                data.reports.push(ReportRow {
+                    id: new_id.clone(),
+                    name: result_for_report.report_name.clone(),
+                    folder: result_for_report.report_group.clone(),
+                    created_on: now,
+                    last_update: now,
+                });
+                new_id
+            }
+        };
+
+        let result = result_for_report.result;
+        let category = result_for_report
+            .category
+            .unwrap_or(result_for_report.report_name);
+        let is_result_in_report = |in_report: &ResultInReportRow| {
+            in_report.report_id == found_report_id
+                && in_report.test_id == result.test_id
+                && in_report.category == category
+                && in_report.environment == result.environment
+        };
+        let reported_attempt = data
+            .results_in_report
+            .iter()
+            .find(|in_report| is_result_in_report(in_report))
+            .and_then(|in_report| {
+                data.test_results
+                    .iter()
+                    .find(|tr| tr.test_id == in_report.test_id && tr.trace_id == in_report.trace_id)
+            })
+            .map(|tr| (tr.run_id.clone(), tr.attempt, tr.status.clone()));
+        let existing = data
+            .results_in_report
+            .iter_mut()
+            .find(|in_report| is_result_in_report(in_report));
+        match (existing, reported_attempt) {
+            (Some(existing), Some((reported_run_id, reported_attempt, reported_status)))
+                if result.is_earlier_attempt(&reported_run_id, reported_attempt) =>
+            {
+                // keep the last attempt, its status may have changed with this one
+                existing.status = reported_status;
+            }
+            (Some(existing), _) => {
+                existing.trace_id = result.trace_id;
+                existing.status = result.status;
+            }
+            (None, _) => data.results_in_report.push(ResultInReportRow {
+                report_id: found_report_id,
+                test_id: result.test_id,
+                trace_id: result.trace_id,
+                category,
+                environment: result.environment,
+                status: result.status,
+            }),
+        }
+    }
+
+    fn get_reports(&self) -> Vec<crate::api::report::Report> {
+        let data = self.read();
+
+        let mut reports: Vec<&ReportRow> = data.reports.iter().collect();
+        reports.sort_by_key(|report| std::cmp::Reverse(report.last_update));
+
+        reports
+            .iter()
+            .take(REPORT_QUERY_LIMIT)
+            .map(|report| {
+                let statuses = [
+                    TestStatus::Success,
+                    TestStatus::Failure,
+                    TestStatus::Skipped,
+                    TestStatus::PassedOnRetry,
+                ];
+                let summary: HashMap<TestStatus, usize> = statuses
+                    .iter()
+                    .map(|one_status| {
+                        let tests: BTreeSet<&String> = data
+                            .results_in_report
+                            .iter()
+                            .filter(|in_report| {
+                                in_report.report_id == report.id && &in_report.status == one_status
+                            })
+                            .map(|in_report| &in_report.test_id)
+                            .collect();
+                        (one_status.clone(), tests.len())
+                    })
+                    .collect();
+
+                crate::api::report::Report {
+                    name: report.name.clone(),
+                    group: report.folder.clone(),
+                    created_on: report.created_on,
+                    last_update: report.last_update,
+                    categories: None,
+                    environments: data.environments_in_report(&report.id),
+                    summary: Some(summary),
+                }
+            })
+            .collect()
+    }
+
+    fn get_report(
+        &self,
+        report_group: &str,
+        report_name: &str,
+        environment: Option<&str>,
+    ) -> Option<crate::api::report::Report> {
+        let data = self.read();
+
+        let report = data
+            .reports
+            .iter()
+            .find(|report| report.folder == report_group && report.name == report_name)?;
+        let in_report: Vec<&ResultInReportRow> = data
+            .results_in_report
+            .iter()
+            .filter(|in_report| in_report.report_id == report.id)
+            .collect();
+        let categories: BTreeSet<&String> = in_report
+            .iter()
+            .map(|in_report| &in_report.category)
+            .collect();
+
+        let test_results = categories
+            .into_iter()
+            .map(|category| {
+                let traces: Vec<&String> = in_report
+                    .iter()
+                    .filter(|in_report| {
+                        &in_report.category == category
+                            && environment_matches(&in_report.environment, environment)
+                    })
+                    .map(|in_report| &in_report.trace_id)
+                    .collect();
+                let mut results: Vec<&super::test::TestResultRow> = data
+                    .test_results
+                    .iter()
+                    .filter(|tr| {
+                        traces.contains(&&tr.trace_id)
+                            && environment_matches(&tr.environment, environment)
+                    })
+                    .collect();
+                results.sort_by_key(|tr| std::cmp::Reverse(tr.date));
+                (
+                    category.clone(),
+                    results.iter().map(|tr| data.to_test_result(tr)).collect(),
+                )
+            })
+            .collect();
+
+        Some(crate::api::report::Report {
+            name: report.name.clone(),
+            group: report.folder.clone(),
+            created_on: report.created_on,
+            last_update: report.last_update,
+            categories: Some(test_results),
+            environments: data.environments_in_report(&report.id),
+            summary: None,
+        })
+    }
+}
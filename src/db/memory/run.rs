@@ -0,0 +1,56 @@
+use crate::db::read::run::TestRunQuery;
+use crate::engine::run::{RunAttempt, TestRun};
+
+impl crate::db::storage::RunStorage for super::MemoryStorage {
+    fn save_test_run(&mut self, test_run: TestRun) {
+        let mut data = self.write();
+        let test_run = match data.test_runs.remove(&test_run.id) {
+            Some(existing) => existing.merge(test_run),
+            None => test_run,
+        };
+        data.test_runs.insert(test_run.id.clone(), test_run);
+    }
+
+    fn get_test_runs(&self, query: TestRunQuery) -> Vec<TestRun> {
+        let data = self.read();
+        let mut runs: Vec<TestRun> = data
+            .test_runs
+            .values()
+            .filter(|run| query.branch.is_none() || run.branch == query.branch)
+            .filter(|run| {
+                query
+                    .before
+                    .map(|before| run.start < before)
+                    .unwrap_or(true)
+            })
+            .cloned()
+            .collect();
+        runs.sort_by_key(|run| std::cmp::Reverse(run.start));
+        runs.truncate(query.limit as usize);
+        runs
+    }
+
+    fn get_test_run(&self, run_id: &str) -> Option<TestRun> {
+        self.read().test_runs.get(run_id).cloned()
+    }
+
+    fn get_run_attempts(&self, run_ids: &[String]) -> Vec<RunAttempt> {
+        self.read()
+            .test_results
+            .iter()
+            .filter_map(|tr| {
+                tr.run_id
+                    .as_ref()
+                    .filter(|run_id| run_ids.contains(run_id))
+                    .map(|run_id| RunAttempt {
+                        run_id: run_id.clone(),
+                        test_id: tr.test_id.clone(),
+                        environment: tr.environment.clone(),
+                        attempt: tr.attempt,
+                        date: tr.date,
+                        status: tr.status.clone(),
+                    })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,62 @@
+use crate::engine::streams::{Script, ScriptType};
+
+fn type_as_i32(script_type: &ScriptType) -> i32 {
+    script_type.clone().into()
+}
+
+impl crate::db::storage::ScriptStorage for super::MemoryStorage {
+    fn save_script(&mut self, script: Script) {
+        self.write().scripts.push(script);
+    }
+
+    fn update_script(&mut self, script: Script) {
+        if let Some(existing) = self
+            .write()
+            .scripts
+            .iter_mut()
+            .find(|existing| existing.id == script.id)
+        {
+            existing.name = script.name;
+            existing.source = script.source;
+            existing.status = script.status;
+        }
+    }
+
+    fn delete_script(&mut self, script_id: &str) -> Option<Script> {
+        let mut data = self.write();
+        let position = data
+            .scripts
+            .iter()
+            .position(|script| script.id.as_deref() == Some(script_id))?;
+        Some(data.scripts.remove(position))
+    }
+
+    fn get_scripts(&self, script_types: Option<Vec<ScriptType>>) -> Vec<Script> {
+        let mut scripts: Vec<Script> = self
+            .read()
+            .scripts
+            .iter()
+            .filter(|script| {
+                script_types
+                    .as_ref()
+                    .map(|types| {
+                        types
+                            .iter()
+                            .any(|ty| type_as_i32(ty) == type_as_i32(&script.script_type))
+                    })
+                    .unwrap_or(true)
+            })
+            .cloned()
+            .collect();
+        scripts.sort_by(|a, b| a.name.cmp(&b.name));
+        scripts
+    }
+
+    fn get_script(&self, script_id: &str) -> Option<Script> {
+        self.read()
+            .scripts
+            .iter()
+            .find(|script| script.id.as_deref() == Some(script_id))
+            .cloned()
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::db::read::span::{AnnotationQuery, SpanQuery};
+use crate::opentracing::span::{BinaryTag, Endpoint};
+use crate::opentracing::Span;
+
+static ENDPOINT_QUERY_LIMIT: usize = 1000;
+static TAG_QUERY_LIMIT: usize = 100;
+static ANNOTATION_QUERY_LIMIT: usize = 100;
+
+fn normalize_endpoint(endpoint: Option<Endpoint>) -> Option<Endpoint> {
+    endpoint.map(|endpoint| Endpoint {
+        service_name: endpoint.service_name.map(|s| s.to_lowercase()),
+        ..endpoint
+    })
+}
+
+fn normalize(span: &Span) -> Span {
+    Span {
+        name: span.name.clone().map(|s| s.to_lowercase()),
+        local_endpoint: normalize_endpoint(span.local_endpoint.clone()),
+        remote_endpoint: normalize_endpoint(span.remote_endpoint.clone()),
+        tags: span
+            .tags
+            .iter()
+            .map(|(key, value)| (key.to_lowercase(), value.clone()))
+            .collect(),
+        binary_annotations: vec![],
+        ..span.clone()
+    }
+}
+
+fn service_name(endpoint: &Option<Endpoint>) -> Option<&String> {
+    endpoint
+        .as_ref()
+        .and_then(|endpoint| endpoint.service_name.as_ref())
+}
+
+fn matches(span: &Span, query: &SpanQuery) -> bool {
+    if query.filter_finish && span.duration.is_none() {
+        return false;
+    }
+    if let Some(query_service_name) = query.service_name.as_ref() {
+        let query_service_name = Some(query_service_name.to_lowercase());
+        let local = service_name(&span.local_endpoint) == query_service_name.as_ref();
+        let remote = service_name(&span.remote_endpoint) == query_service_name.as_ref();
+        if !(local || (!query.only_local_service && remote)) {
+            return false;
+        }
+    }
+    if let Some(query_remote_service_name) = query.remote_service_name.as_ref() {
+        if service_name(&span.remote_endpoint) != Some(&query_remote_service_name.to_lowercase()) {
+            return false;
+        }
+    }
+    if let Some(query_span_name) = query.span_name.as_ref() {
+        if span.name != Some(query_span_name.to_lowercase()) {
+            return false;
+        }
+    }
+    let annotations_match =
+        query
+            .annotation_query
+            .iter()
+            .all(|annotation_query| match annotation_query {
+                AnnotationQuery::KeyValue(key, value) => span.tags.get(key) == Some(value),
+                AnnotationQuery::Key(key) => {
+                    span.tags.contains_key(key)
+                        || span
+                            .annotations
+                            .iter()
+                            .any(|annotation| &annotation.value == key)
+                }
+            });
+    if !annotations_match {
+        return false;
+    }
+    if let Some(query_trace_id) = query.trace_id.as_ref() {
+        if &span.trace_id != query_trace_id {
+            return false;
+        }
+    }
+    if let Some(query_max_duration) = query.max_duration {
+        if span
+            .duration
+            .map(|d| d > query_max_duration)
+            .unwrap_or(true)
+        {
+            return false;
+        }
+    }
+    if let Some(query_min_duration) = query.min_duration {
+        if span
+            .duration
+            .map(|d| d < query_min_duration)
+            .unwrap_or(true)
+        {
+            return false;
+        }
+    }
+    // a trace is fetched whole, even with spans timestamped after end_ts
+    if query.trace_id.is_none() {
+        let end_ts = super::to_micros(query.end_ts);
+        let start_ts = query
+            .lookback
+            .map(|lookback| super::to_micros(query.end_ts - lookback));
+        match span.timestamp {
+            Some(ts) if ts <= end_ts && start_ts.map(|start| ts >= start).unwrap_or(true) => (),
+            _ => return false,
+        }
+    }
+    if query.only_endpoint && span.remote_endpoint.is_none() {
+        return false;
+    }
+    true
+}
+
+fn to_output(span: &Span, only_endpoint: bool) -> Span {
+    let (annotations, tags) = if only_endpoint {
+        (vec![], HashMap::new())
+    } else {
+        (
+            span.annotations
+                .iter()
+                .take(ANNOTATION_QUERY_LIMIT)
+                .cloned()
+                .collect(),
+            span.tags.clone(),
+        )
+    };
+    let binary_annotation_endpoint = span
+        .remote_endpoint
+        .clone()
+        .or_else(|| span.local_endpoint.clone());
+    Span {
+        name: span.name.clone().map(|s| s.chars().take(250).collect()),
+        annotations,
+        binary_annotations: tags
+            .iter()
+            .map(|(k, v)| BinaryTag {
+                key: k.clone(),
+                value: v.clone(),
+                endpoint: binary_annotation_endpoint.clone(),
+            })
+            .collect(),
+        tags,
+        ..span.clone()
+    }
+}
+
+impl crate::db::storage::SpanStorage for super::MemoryStorage {
+    fn save_spans(&mut self, spans: &[Span]) -> Result<(), crate::db::storage::StorageError> {
+        let mut data = self.write();
+        for span in spans {
+            let span = normalize(span);
+            let key = (span.trace_id.clone(), span.id.clone(), span.shared);
+            match data.spans.get_mut(&key) {
+                Some(existing) => {
+                    //TODO: manage more update cases than duration
+                    existing.duration = span.duration;
+                    existing.annotations.extend(span.annotations);
+                    existing.tags.extend(span.tags);
+                }
+                None => {
+                    data.spans.insert(key, span);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_services(&self) -> Vec<String> {
+        self.read()
+            .spans
+            .values()
+            .flat_map(|span| {
+                vec![
+                    service_name(&span.local_endpoint),
+                    service_name(&span.remote_endpoint),
+                ]
+            })
+            .flatten()
+            .cloned()
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .take(ENDPOINT_QUERY_LIMIT)
+            .collect()
+    }
+
+    fn get_remote_services(&self, query_service_name: &str) -> Vec<String> {
+        let query_service_name = query_service_name.to_lowercase();
+        self.read()
+            .spans
+            .values()
+            .filter(|span| service_name(&span.local_endpoint) == Some(&query_service_name))
+            .filter_map(|span| service_name(&span.remote_endpoint))
+            .cloned()
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .take(ENDPOINT_QUERY_LIMIT)
+            .collect()
+    }
+
+    fn get_tag_keys(&self) -> Vec<String> {
+        self.read()
+            .spans
+            .values()
+            .flat_map(|span| span.tags.keys())
+            .cloned()
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .take(TAG_QUERY_LIMIT)
+            .collect()
+    }
+
+    fn get_tag_values(&self, tag_key: &str) -> Vec<String> {
+        let tag_key = tag_key.to_lowercase();
+        self.read()
+            .spans
+            .values()
+            .filter_map(|span| span.tags.get(&tag_key))
+            .cloned()
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .take(TAG_QUERY_LIMIT)
+            .collect()
+    }
+
+    fn get_spans(&self, query: SpanQuery) -> Vec<Span> {
+        let data = self.read();
+        let mut spans: Vec<&Span> = data
+            .spans
+            .values()
+            .filter(|span| matches(span, &query))
+            .collect();
+        spans.sort_by_key(|span| span.timestamp);
+        spans
+            .into_iter()
+            .take(query.limit as usize)
+            .map(|span| to_output(span, query.only_endpoint))
+            .collect()
+    }
+
+    fn get_trace_ids(&self, query: SpanQuery) -> Vec<String> {
+        let data = self.read();
+        let mut latest_by_trace: HashMap<&String, Option<i64>> = HashMap::new();
+        for span in data.spans.values().filter(|span| matches(span, &query)) {
+            let latest = latest_by_trace.entry(&span.trace_id).or_insert(None);
+            *latest = (*latest).max(span.timestamp);
+        }
+        let mut trace_ids: Vec<(&String, Option<i64>)> = latest_by_trace.into_iter().collect();
+        trace_ids.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        trace_ids
+            .into_iter()
+            .take(query.limit as usize)
+            .map(|(trace_id, _)| trace_id.clone())
+            .collect()
+    }
+}
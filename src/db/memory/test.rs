@@ -0,0 +1,378 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::db::read::test::{DurationRegressionQuery, TestItemQuery, TestResultQuery};
+use crate::db::storage::ResultCleanupStatus;
+use crate::engine::baseline::DurationRegression;
+use crate::engine::failure::{BlamedComponent, TestFailure};
+use crate::engine::test_result::{TestResult, TestStatus, TestStep};
+
+static TEST_ITEM_QUERY_LIMIT: usize = 200;
+
+#[derive(Debug, Clone)]
+pub(super) struct TestItemRow {
+    id: String,
+    parent_id: String,
+    name: String,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct TestResultRow {
+    pub test_id: String,
+    pub trace_id: String,
+    pub date: i64,
+    pub status: TestStatus,
+    duration: i64,
+    pub environment: Option<String>,
+    build: Option<String>,
+    pub run_id: Option<String>,
+    pub attempt: i32,
+    components_called: HashMap<String, i32>,
+    nb_spans: i32,
+    steps: Vec<TestStep>,
+    failure: Option<TestFailure>,
+    blamed_component: Option<BlamedComponent>,
+    pub cleanup_status: ResultCleanupStatus,
+}
+
+impl super::Data {
+    fn find_test_or_insert(&mut self, parent_id: String, name: String) -> String {
+        if let Some(existing) = self
+            .test_items
+            .values()
+            .find(|item| item.parent_id == parent_id && item.name == name)
+        {
+            return existing.id.clone();
+        }
+        let id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+        self.test_items.insert(
+            id.clone(),
+            TestItemRow {
+                id: id.clone(),
+                parent_id,
+                name,
+            },
+        );
+        id
+    }
+
+    // parents of a test item, from the root
+    fn path(&self, test_item: &TestItemRow) -> Vec<&TestItemRow> {
+        let mut path = vec![];
+        let mut test_item_to_get = test_item.parent_id.as_str();
+        while let Some(parent) = self.test_items.get(test_item_to_get) {
+            path.push(parent);
+            test_item_to_get = parent.parent_id.as_str();
+        }
+        path.reverse();
+        path
+    }
+
+    pub(super) fn to_test_result(&self, row: &TestResultRow) -> TestResult {
+        let test_item = self.test_items.get(&row.test_id);
+        TestResult {
+            test_id: row.test_id.clone(),
+            path: test_item
+                .map(|test_item| {
+                    self.path(test_item)
+                        .iter()
+                        .map(|parent| parent.name.clone())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            name: test_item
+                .map(|test_item| test_item.name.clone())
+                .unwrap_or_else(|| "missing name".to_string()),
+            date: row.date,
+            duration: row.duration,
+            environment: row.environment.clone(),
+            build: row.build.clone(),
+            run_id: row.run_id.clone(),
+            attempt: row.attempt,
+            status: row.status.clone(),
+            trace_id: row.trace_id.clone(),
+            components_called: row.components_called.clone(),
+            nb_spans: row.nb_spans,
+            steps: row.steps.clone(),
+            failure: row.failure.clone(),
+            blamed_component: row.blamed_component.clone(),
+            main_span: None,
+        }
+    }
+}
+
+fn sorted_by_date_desc(mut rows: Vec<&TestResultRow>) -> Vec<&TestResultRow> {
+    rows.sort_by_key(|tr| std::cmp::Reverse(tr.date));
+    rows
+}
+
+impl crate::db::storage::TestStorage for super::MemoryStorage {
+    fn save_test_result(&mut self, mut test_result: TestResult) -> TestResult {
+        let mut data = self.write();
+
+        let mut parent_id = "root".to_string();
+        for item in test_result.path.clone() {
+            parent_id = data.find_test_or_insert(parent_id, item);
+        }
+        let test_id = data.find_test_or_insert(parent_id, test_result.name.clone());
+
+        let (run_id, environment) = (test_result.run_id.clone(), test_result.environment.clone());
+        let is_other_attempt = |tr: &TestResultRow| {
+            tr.test_id == test_id
+                && tr.run_id.is_some()
+                && tr.run_id == run_id
+                && tr.environment == environment
+        };
+        let earlier_attempt_failed = data.test_results.iter().any(|tr| {
+            is_other_attempt(tr)
+                && tr.attempt < test_result.attempt
+                && tr.status == TestStatus::Failure
+        });
+        test_result.status = test_result.status.of_attempt(earlier_attempt_failed);
+        if test_result.status == TestStatus::Failure {
+            // attempts received before this one that succeeded after it
+            for tr in data
+                .test_results
+                .iter_mut()
+                .filter(|tr| is_other_attempt(tr) && tr.attempt > test_result.attempt)
+            {
+                tr.status = tr.status.clone().of_attempt(true);
+            }
+        }
+
+        let existing = data
+            .test_results
+            .iter_mut()
+            .find(|tr| tr.test_id == test_id && tr.trace_id == test_result.trace_id);
+        match existing {
+            Some(existing) => {
+                // trace received more spans since it was last parsed
+                existing.date = test_result.date;
+                existing.status = test_result.status.clone();
+                existing.duration = test_result.duration;
+                existing.environment = test_result.environment.clone();
+                existing.build = test_result.build.clone();
+                existing.run_id = test_result.run_id.clone();
+                existing.attempt = test_result.attempt;
+                existing.components_called = test_result.components_called.clone();
+                existing.nb_spans = test_result.nb_spans;
+                existing.steps = test_result.steps.clone();
+                existing.failure = test_result.failure.clone();
+                existing.blamed_component = test_result.blamed_component.clone();
+            }
+            None => data.test_results.push(TestResultRow {
+                test_id: test_id.clone(),
+                trace_id: test_result.trace_id.clone(),
+                date: test_result.date,
+                status: test_result.status.clone(),
+                duration: test_result.duration,
+                environment: test_result.environment.clone(),
+                build: test_result.build.clone(),
+                run_id: test_result.run_id.clone(),
+                attempt: test_result.attempt,
+                components_called: test_result.components_called.clone(),
+                nb_spans: test_result.nb_spans,
+                steps: test_result.steps.clone(),
+                failure: test_result.failure.clone(),
+                blamed_component: test_result.blamed_component.clone(),
+                cleanup_status: match test_result.status {
+                    TestStatus::Success => ResultCleanupStatus::ToKeep,
+                    _ => ResultCleanupStatus::WithData,
+                },
+            }),
+        }
+
+        for tr in &mut data.test_results {
+            if tr.test_id == test_id
+                && tr.cleanup_status == ResultCleanupStatus::ToKeep
+                && tr.date < test_result.date
+            {
+                tr.cleanup_status = ResultCleanupStatus::WithData;
+            }
+        }
+
+        TestResult {
+            test_id,
+            ..test_result
+        }
+    }
+
+    fn get_test_items(&self, query: TestItemQuery) -> Vec<crate::api::test::TestDetails> {
+        let data = self.read();
+
+        let mut test_items: Vec<&TestItemRow> = data
+            .test_items
+            .values()
+            .filter(|item| {
+                query
+                    .parent_id
+                    .as_ref()
+                    .map(|parent_id| &item.parent_id == parent_id)
+                    .unwrap_or(true)
+                    && query.id.as_ref().map(|id| &item.id == id).unwrap_or(true)
+            })
+            .collect();
+        test_items.sort_by(|a, b| a.name.cmp(&b.name));
+
+        test_items
+            .iter()
+            .map(|ti| {
+                let path: Vec<crate::api::test::TestItem> = if query.with_full_path {
+                    data.path(ti)
+                        .iter()
+                        .map(|parent| crate::api::test::TestItem {
+                            id: parent.id.clone(),
+                            name: parent.name.clone(),
+                        })
+                        .collect()
+                } else {
+                    vec![]
+                };
+
+                let children = if query.with_children {
+                    let mut children: Vec<&TestItemRow> = data
+                        .test_items
+                        .values()
+                        .filter(|item| item.parent_id == ti.id)
+                        .collect();
+                    children.sort_by(|a, b| a.name.cmp(&b.name));
+                    children
+                        .iter()
+                        .take(TEST_ITEM_QUERY_LIMIT)
+                        .map(|child| crate::api::test::TestItem {
+                            id: child.id.clone(),
+                            name: child.name.clone(),
+                        })
+                        .collect()
+                } else {
+                    vec![]
+                };
+
+                let traces: Vec<TestResult> = if query.with_traces {
+                    sorted_by_date_desc(
+                        data.test_results
+                            .iter()
+                            .filter(|tr| tr.test_id == ti.id)
+                            .collect(),
+                    )
+                    .iter()
+                    .take(crate::engine::flaky::FLAKY_WINDOW)
+                    .map(|tr| TestResult {
+                        path: path.iter().map(|ti| ti.name.clone()).collect(),
+                        ..data.to_test_result(tr)
+                    })
+                    .collect()
+                } else {
+                    vec![]
+                };
+
+                let flaky = crate::engine::flaky::score(&traces);
+                crate::api::test::TestDetails {
+                    children,
+                    last_results: traces.into_iter().take(5).collect(),
+                    flaky,
+                    name: ti.name.clone(),
+                    path,
+                    test_id: ti.id.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn get_test_results(&self, query: TestResultQuery) -> Vec<TestResult> {
+        let data = self.read();
+
+        let end_ts = super::to_micros(query.ts);
+        let start_ts = query
+            .lookback
+            .map(|lookback| super::to_micros(query.ts - lookback));
+        sorted_by_date_desc(
+            data.test_results
+                .iter()
+                .filter(|tr| {
+                    query
+                        .trace_id
+                        .as_ref()
+                        .map(|trace_id| &tr.trace_id == trace_id)
+                        .unwrap_or(true)
+                        && query
+                            .status
+                            .map(|status| tr.status.as_i32() == status)
+                            .unwrap_or(true)
+                        && query
+                            .test_id
+                            .as_ref()
+                            .map(|test_id| &tr.test_id == test_id)
+                            .unwrap_or(true)
+                        && query
+                            .environment
+                            .as_ref()
+                            .map(|environment| tr.environment.as_ref() == Some(environment))
+                            .unwrap_or(true)
+                        && query
+                            .run_id
+                            .as_ref()
+                            .map(|run_id| tr.run_id.as_ref() == Some(run_id))
+                            .unwrap_or(true)
+                        && query
+                            .max_duration
+                            .map(|max_duration| tr.duration <= max_duration)
+                            .unwrap_or(true)
+                        && query
+                            .min_duration
+                            .map(|min_duration| tr.duration >= min_duration)
+                            .unwrap_or(true)
+                        && tr.date <= end_ts
+                        && start_ts.map(|start_ts| tr.date >= start_ts).unwrap_or(true)
+                })
+                .collect(),
+        )
+        .iter()
+        .take(query.limit as usize)
+        .map(|tr| data.to_test_result(tr))
+        .collect()
+    }
+
+    fn get_environments(&self) -> Vec<String> {
+        self.read()
+            .test_results
+            .iter()
+            .filter_map(|tr| tr.environment.clone())
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect()
+    }
+
+    fn save_duration_regression(&mut self, regression: DurationRegression) {
+        let mut data = self.write();
+        data.duration_regressions.retain(|existing| {
+            existing.test_id != regression.test_id || existing.trace_id != regression.trace_id
+        });
+        data.duration_regressions.push(regression);
+    }
+
+    fn get_duration_regressions(&self, query: DurationRegressionQuery) -> Vec<DurationRegression> {
+        let data = self.read();
+        let mut regressions: Vec<&DurationRegression> = data
+            .duration_regressions
+            .iter()
+            .filter(|regression| {
+                query
+                    .test_id
+                    .as_ref()
+                    .map(|test_id| &regression.test_id == test_id)
+                    .unwrap_or(true)
+                    && query
+                        .environment
+                        .as_ref()
+                        .map(|environment| regression.environment.as_ref() == Some(environment))
+                        .unwrap_or(true)
+            })
+            .collect();
+        regressions.sort_by_key(|regression| std::cmp::Reverse(regression.date));
+        regressions
+            .into_iter()
+            .take(query.limit as usize)
+            .cloned()
+            .collect()
+    }
+}
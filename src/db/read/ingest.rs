@@ -0,0 +1,17 @@
+use actix::prelude::*;
+
+pub struct GetIngest(pub String);
+
+impl Message for GetIngest {
+    type Result = Option<crate::engine::ingestor::Ingest>;
+}
+
+impl Handler<GetIngest> for super::DbReadExecutor {
+    type Result = MessageResult<GetIngest>;
+
+    fn handle(&mut self, msg: GetIngest, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_ingest(&msg.0);
+        self.check_db_connection(ctx);
+        MessageResult(result)
+    }
+}
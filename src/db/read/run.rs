@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use actix::{Handler, Message, MessageResult};
+
+use super::test::TestResultQuery;
+use crate::engine::compare::ComparedSide;
+
+static TEST_RUN_QUERY_LIMIT: i64 = 50;
+static RUN_RESULTS_LIMIT: i64 = 10_000;
+
+#[derive(Debug)]
+pub struct TestRunQuery {
+    pub branch: Option<String>,
+    /// only runs started before, in microseconds
+    pub before: Option<i64>,
+    pub limit: i64,
+}
+
+impl Default for TestRunQuery {
+    fn default() -> Self {
+        TestRunQuery {
+            branch: None,
+            before: None,
+            limit: TEST_RUN_QUERY_LIMIT,
+        }
+    }
+}
+
+impl From<crate::api::run::TestRunsQueryParams> for TestRunQuery {
+    fn from(params: crate::api::run::TestRunsQueryParams) -> Self {
+        TestRunQuery {
+            branch: params.branch,
+            // query timestamp is in milliseconds
+            before: params.before.map(|before| before * 1000),
+            limit: params
+                .limit
+                .map(|v| v.min(TEST_RUN_QUERY_LIMIT))
+                .unwrap_or(TEST_RUN_QUERY_LIMIT),
+        }
+    }
+}
+
+impl super::DbReadExecutor {
+    fn get_run_results(&self, run_id: &str) -> Vec<crate::engine::test_result::TestResult> {
+        self.0.get_test_results(TestResultQuery {
+            run_id: Some(run_id.to_string()),
+            limit: RUN_RESULTS_LIMIT,
+            ..Default::default()
+        })
+    }
+}
+
+pub struct GetTestRuns(pub TestRunQuery);
+impl Message for GetTestRuns {
+    type Result = Vec<crate::engine::run::TestRun>;
+}
+impl Handler<GetTestRuns> for super::DbReadExecutor {
+    type Result = MessageResult<GetTestRuns>;
+
+    fn handle(&mut self, msg: GetTestRuns, ctx: &mut Self::Context) -> Self::Result {
+        let runs = self.0.get_test_runs(msg.0);
+        let run_ids: Vec<String> = runs.iter().map(|run| run.id.clone()).collect();
+        let mut summaries = crate::engine::run::summaries(&self.0.get_run_attempts(&run_ids));
+        let runs = runs
+            .into_iter()
+            .map(|run| run.with_summary_from(&mut summaries))
+            .collect();
+        self.check_db_connection(ctx);
+        MessageResult(runs)
+    }
+}
+
+pub struct GetTestRun(pub String);
+impl Message for GetTestRun {
+    type Result = Option<crate::engine::run::TestRun>;
+}
+impl Handler<GetTestRun> for super::DbReadExecutor {
+    type Result = MessageResult<GetTestRun>;
+
+    fn handle(&mut self, msg: GetTestRun, ctx: &mut Self::Context) -> Self::Result {
+        let run = self.0.get_test_run(&msg.0).map(|run| {
+            let results = self.get_run_results(&run.id);
+            let previous_run = run.branch.clone().and_then(|branch| {
+                self.0
+                    .get_test_runs(TestRunQuery {
+                        branch: Some(branch),
+                        before: Some(run.start),
+                        limit: 1,
+                    })
+                    .into_iter()
+                    .next()
+                    .map(|previous_run| {
+                        let previous_results = self.get_run_results(&previous_run.id);
+                        (previous_run.id, previous_results)
+                    })
+            });
+            run.with_details(&results, previous_run)
+        });
+        self.check_db_connection(ctx);
+        MessageResult(run)
+    }
+}
+
+pub struct CompareTestResults {
+    pub base: ComparedSide,
+    pub target: ComparedSide,
+    /// report whose categories group the changes, changes are grouped by test suite if missing
+    pub report: Option<(String, String)>,
+    /// how far back to look for results of an environment
+    pub lookback: Option<chrono::Duration>,
+}
+impl Message for CompareTestResults {
+    type Result = Option<crate::engine::compare::Comparison>;
+}
+impl super::DbReadExecutor {
+    fn get_side_results(
+        &self,
+        side: &ComparedSide,
+        lookback: Option<chrono::Duration>,
+    ) -> Vec<crate::engine::test_result::TestResult> {
+        match side {
+            ComparedSide::Run(run_id) => self.get_run_results(run_id),
+            ComparedSide::Environment(environment) => self.0.get_test_results(TestResultQuery {
+                environment: Some(environment.clone()),
+                lookback,
+                limit: RUN_RESULTS_LIMIT,
+                ..Default::default()
+            }),
+        }
+    }
+}
+impl Handler<CompareTestResults> for super::DbReadExecutor {
+    type Result = MessageResult<CompareTestResults>;
+
+    fn handle(&mut self, msg: CompareTestResults, ctx: &mut Self::Context) -> Self::Result {
+        let categories_by_test: Option<HashMap<String, Vec<String>>> = match msg.report {
+            Some((report_group, report_name)) => {
+                match self.0.get_report(&report_group, &report_name, None) {
+                    Some(report) => {
+                        let mut categories_by_test: HashMap<String, Vec<String>> = HashMap::new();
+                        for (category, results) in report.categories.unwrap_or_default() {
+                            for tr in results {
+                                let categories = categories_by_test.entry(tr.test_id).or_default();
+                                if !categories.contains(&category) {
+                                    categories.push(category.clone());
+                                }
+                            }
+                        }
+                        Some(categories_by_test)
+                    }
+                    None => {
+                        self.check_db_connection(ctx);
+                        return MessageResult(None);
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let base_results = self.get_side_results(&msg.base, msg.lookback);
+        let target_results = self.get_side_results(&msg.target, msg.lookback);
+        let comparison = crate::engine::compare::compare(
+            msg.base,
+            &base_results,
+            msg.target,
+            &target_results,
+            |tr| match &categories_by_test {
+                Some(categories_by_test) => categories_by_test
+                    .get(&tr.test_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                None => vec![tr.path.first().cloned().unwrap_or_default()],
+            },
+        );
+        self.check_db_connection(ctx);
+        MessageResult(Some(comparison))
+    }
+}
@@ -2,84 +2,93 @@ use actix::prelude::Message;
 use actix::{Handler, MessageResult};
 use actix_web;
 use chrono;
-use diesel::prelude::*;
-use std::collections::HashMap;
 use std::str::FromStr;
 
-static SPAN_QUERY_LIMIT: i64 = 500;
-use crate::db::schema::span;
-#[derive(Debug, Insertable, Queryable)]
-#[table_name = "span"]
-pub struct SpanDb {
-    pub trace_id: String,
-    pub id: String,
-    parent_id: Option<String>,
-    name: Option<String>,
-    kind: Option<String>,
-    duration: Option<i64>,
-    ts: Option<chrono::NaiveDateTime>,
-    debug: bool,
-    shared: bool,
-    local_endpoint_id: Option<String>,
-    remote_endpoint_id: Option<String>,
+pub static SPAN_QUERY_LIMIT: i64 = 500;
+
+pub struct GetServices;
+impl Message for GetServices {
+    type Result = Vec<String>;
 }
 
-static ENDPOINT_QUERY_LIMIT: i64 = 1000;
-use crate::db::schema::endpoint;
-#[derive(Debug, Insertable, Queryable)]
-#[table_name = "endpoint"]
-pub struct EndpointDb {
-    endpoint_id: String,
-    service_name: Option<String>,
-    ipv4: Option<String>,
-    ipv6: Option<String>,
-    port: Option<i32>,
+impl Handler<GetServices> for super::DbReadExecutor {
+    type Result = MessageResult<GetServices>;
+
+    fn handle(&mut self, _msg: GetServices, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_services();
+        self.check_db_connection(ctx);
+        MessageResult(result)
+    }
 }
 
-static TAG_QUERY_LIMIT: i64 = 100;
-use crate::db::schema::tag;
-#[derive(Debug, Insertable, Queryable)]
-#[table_name = "tag"]
-pub struct TagDb {
-    span_id: String,
-    name: String,
-    value: String,
+pub struct GetRemoteServices(pub String);
+impl Message for GetRemoteServices {
+    type Result = Vec<String>;
 }
 
-static ANNOTATION_QUERY_LIMIT: i64 = 100;
-use crate::db::schema::annotation;
-#[derive(Debug, Insertable, Queryable)]
-#[table_name = "annotation"]
-pub struct AnnotationDb {
-    annotation_id: String,
-    trace_id: String,
-    span_id: String,
-    ts: chrono::NaiveDateTime,
-    value: String,
+impl Handler<GetRemoteServices> for super::DbReadExecutor {
+    type Result = MessageResult<GetRemoteServices>;
+
+    fn handle(&mut self, msg: GetRemoteServices, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_remote_services(&msg.0);
+        self.check_db_connection(ctx);
+        MessageResult(result)
+    }
 }
 
-pub struct GetServices;
-impl Message for GetServices {
+pub struct GetTagKeys;
+impl Message for GetTagKeys {
     type Result = Vec<String>;
 }
 
-impl Handler<GetServices> for super::DbReadExecutor {
-    type Result = MessageResult<GetServices>;
+impl Handler<GetTagKeys> for super::DbReadExecutor {
+    type Result = MessageResult<GetTagKeys>;
 
-    fn handle(&mut self, _msg: GetServices, _: &mut Self::Context) -> Self::Result {
-        use super::super::schema::endpoint::dsl::*;
+    fn handle(&mut self, _msg: GetTagKeys, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_tag_keys();
+        self.check_db_connection(ctx);
+        MessageResult(result)
+    }
+}
 
-        MessageResult(
-            endpoint
-                .limit(ENDPOINT_QUERY_LIMIT)
-                .order(service_name.asc())
-                .load::<EndpointDb>(self.0.as_ref().expect("fail to get DB"))
-                .ok()
-                .unwrap_or_else(|| vec![])
-                .iter()
-                .map(|ep| ep.service_name.clone().unwrap_or_else(|| "".to_string()))
-                .collect(),
-        )
+pub struct GetTagValues(pub String);
+impl Message for GetTagValues {
+    type Result = Vec<String>;
+}
+
+impl Handler<GetTagValues> for super::DbReadExecutor {
+    type Result = MessageResult<GetTagValues>;
+
+    fn handle(&mut self, msg: GetTagValues, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_tag_values(&msg.0);
+        self.check_db_connection(ctx);
+        MessageResult(result)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotationQuery {
+    // matches a span with this tag, or with an annotation of this value
+    Key(String),
+    // matches a span with this tag set to this value
+    KeyValue(String, String),
+}
+
+impl AnnotationQuery {
+    // Zipkin annotation query, like "error and http.method=GET"
+    pub fn parse(query: &str) -> Vec<AnnotationQuery> {
+        query
+            .split(" and ")
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(|term| match term.find('=') {
+                Some(index) => AnnotationQuery::KeyValue(
+                    term[..index].trim().to_lowercase(),
+                    term[index + 1..].trim().to_string(),
+                ),
+                None => AnnotationQuery::Key(term.to_lowercase()),
+            })
+            .collect()
     }
 }
 
@@ -87,7 +96,9 @@ impl Handler<GetServices> for super::DbReadExecutor {
 pub struct SpanQuery {
     pub filter_finish: bool,
     pub service_name: Option<String>,
+    pub remote_service_name: Option<String>,
     pub span_name: Option<String>,
+    pub annotation_query: Vec<AnnotationQuery>,
     pub trace_id: Option<String>,
     pub min_duration: Option<i64>,
     pub max_duration: Option<i64>,
@@ -95,6 +106,7 @@ pub struct SpanQuery {
     pub lookback: Option<chrono::Duration>,
     pub limit: i64,
     pub only_endpoint: bool,
+    pub only_local_service: bool,
 }
 
 impl Default for SpanQuery {
@@ -102,7 +114,9 @@ impl Default for SpanQuery {
         SpanQuery {
             filter_finish: true,
             service_name: None,
+            remote_service_name: None,
             span_name: None,
+            annotation_query: vec![],
             trace_id: None,
             min_duration: None,
             max_duration: None,
@@ -110,6 +124,7 @@ impl Default for SpanQuery {
             lookback: None,
             limit: SPAN_QUERY_LIMIT,
             only_endpoint: false,
+            only_local_service: false,
         }
     }
 }
@@ -125,11 +140,23 @@ impl SpanQuery {
             service_name: req
                 .query()
                 .get("serviceName")
+                .filter(|s| *s != "all")
+                .map(std::string::ToString::to_string),
+            remote_service_name: req
+                .query()
+                .get("remoteServiceName")
+                .filter(|s| *s != "all")
                 .map(std::string::ToString::to_string),
             span_name: req
                 .query()
                 .get("spanName")
+                .filter(|s| *s != "all")
                 .map(std::string::ToString::to_string),
+            annotation_query: req
+                .query()
+                .get("annotationQuery")
+                .map(|s| AnnotationQuery::parse(s))
+                .unwrap_or_else(|| vec![]),
             trace_id: req
                 .query()
                 .get("traceId")
@@ -172,6 +199,7 @@ impl SpanQuery {
                 })
                 .unwrap_or(SPAN_QUERY_LIMIT),
             only_endpoint: false,
+            only_local_service: false,
         }
     }
 
@@ -190,6 +218,12 @@ impl SpanQuery {
             ..self
         }
     }
+    pub fn only_local_service(self) -> Self {
+        SpanQuery {
+            only_local_service: true,
+            ..self
+        }
+    }
 }
 
 pub struct GetSpans(pub SpanQuery);
@@ -200,184 +234,44 @@ impl Message for GetSpans {
 impl Handler<GetSpans> for super::DbReadExecutor {
     type Result = MessageResult<GetSpans>;
 
-    fn handle(&mut self, msg: GetSpans, _: &mut Self::Context) -> Self::Result {
-        let query_endpoint: Option<Result<EndpointDb, _>> = {
-            use super::super::schema::endpoint::dsl::*;
-
-            msg.0.service_name.map(|query_service_name| {
-                endpoint
-                    .filter(service_name.eq(query_service_name.to_lowercase()))
-                    .first::<EndpointDb>(self.0.as_ref().expect("fail to get DB"))
-            })
-        };
-        if let Some(Err(_err)) = query_endpoint {
-            // no endpoint found matching query
-            return MessageResult(vec![]);
-        }
-
-        let spans: Vec<SpanDb> = {
-            use super::super::schema::span::dsl::*;
-
-            let mut query = span.into_boxed();
-
-            if msg.0.filter_finish {
-                query = query.filter(duration.is_not_null());
-            }
-
-            if let Some(Ok(query_endpoint)) = query_endpoint {
-                query = query.filter(
-                    remote_endpoint_id
-                        .eq(query_endpoint.endpoint_id.clone())
-                        .or(local_endpoint_id.eq(query_endpoint.endpoint_id)),
-                );
-            }
-
-            if let Some(query_span_name) = msg.0.span_name {
-                query = query.filter(name.eq(query_span_name));
-            }
-
-            if let Some(query_trace_id) = msg.0.trace_id {
-                query = query.filter(trace_id.eq(query_trace_id));
-            }
-
-            if let Some(query_max_duration) = msg.0.max_duration {
-                query = query.filter(duration.le(query_max_duration));
-            }
-            if let Some(query_min_duration) = msg.0.min_duration {
-                query = query.filter(duration.ge(query_min_duration));
-            }
-
-            query = query.filter(ts.le(msg.0.end_ts));
-            if let Some(query_lookback) = msg.0.lookback {
-                query = query.filter(ts.ge(msg.0.end_ts - query_lookback));
-            }
-
-            if msg.0.only_endpoint {
-                query = query.filter(remote_endpoint_id.is_not_null());
-            }
-
-            query
-                .order(ts.asc())
-                .limit(msg.0.limit)
-                .load::<SpanDb>(self.0.as_ref().expect("fail to get DB"))
-                .ok()
-                .unwrap_or_else(|| vec![])
-        };
-
-        let without_tags = msg.0.only_endpoint;
-        let without_annotations = msg.0.only_endpoint;
-
-        let mut endpoint_cache = super::super::helper::Cacher::new();
-
-        MessageResult(
-            spans
-                .iter()
-                .map(|spandb| {
-                    let local_endpoint = spandb.local_endpoint_id.clone().and_then(|lep_id| {
-                        endpoint_cache
-                            .get(&lep_id, |id| {
-                                use super::super::schema::endpoint::dsl::*;
-
-                                endpoint
-                                    .filter(endpoint_id.eq(id))
-                                    .first::<EndpointDb>(self.0.as_ref().expect("fail to get DB"))
-                                    .ok()
-                                    .map(|ep| crate::opentracing::span::Endpoint {
-                                        service_name: ep.service_name,
-                                        ipv4: ep.ipv4,
-                                        ipv6: ep.ipv6,
-                                        port: ep.port,
-                                    })
-                            })
-                            .clone()
-                    });
-                    let remote_endpoint = spandb.remote_endpoint_id.clone().and_then(|rep_id| {
-                        endpoint_cache
-                            .get(&rep_id, |id| {
-                                use super::super::schema::endpoint::dsl::*;
-
-                                endpoint
-                                    .filter(endpoint_id.eq(id))
-                                    .first::<EndpointDb>(self.0.as_ref().expect("fail to get DB"))
-                                    .ok()
-                                    .map(|ep| crate::opentracing::span::Endpoint {
-                                        service_name: ep.service_name,
-                                        ipv4: ep.ipv4,
-                                        ipv6: ep.ipv6,
-                                        port: ep.port,
-                                    })
-                            })
-                            .clone()
-                    });
-
-                    let annotations = if !without_annotations {
-                        use super::super::schema::annotation::dsl::*;
+    fn handle(&mut self, msg: GetSpans, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_spans(msg.0);
+        self.check_db_connection(ctx);
+        MessageResult(result)
+    }
+}
 
-                        annotation
-                            .filter(trace_id.eq(&spandb.trace_id).and(span_id.eq(&spandb.id)))
-                            .limit(ANNOTATION_QUERY_LIMIT)
-                            .load::<AnnotationDb>(self.0.as_ref().expect("fail to get DB"))
-                            .ok()
-                            .unwrap_or_else(|| vec![])
-                            .iter()
-                            .map(|an| crate::opentracing::span::Annotation {
-                                timestamp: ((an.ts.timestamp() * 1000)
-                                    + i64::from(an.ts.timestamp_subsec_millis()))
-                                    * 1000,
-                                value: an.value.clone(),
-                            })
-                            .collect()
-                    } else {
-                        vec![]
-                    };
+/// Get all the spans of a trace, without the limit of a `SpanQuery`
+pub struct GetTrace(pub String);
+impl Message for GetTrace {
+    type Result = Vec<crate::opentracing::Span>;
+}
 
-                    //TODO: way too slow
-                    let tags: HashMap<String, String> = if !without_tags {
-                        use super::super::schema::tag::dsl::*;
+impl Handler<GetTrace> for super::DbReadExecutor {
+    type Result = MessageResult<GetTrace>;
+
+    fn handle(&mut self, msg: GetTrace, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_spans(
+            SpanQuery::default()
+                .with_trace_id(msg.0)
+                .with_limit(i64::MAX),
+        );
+        self.check_db_connection(ctx);
+        MessageResult(result)
+    }
+}
 
-                        tag.filter(span_id.eq(&spandb.id))
-                            .limit(TAG_QUERY_LIMIT)
-                            .load::<TagDb>(self.0.as_ref().expect("fail to get DB"))
-                            .ok()
-                            .unwrap_or_else(|| vec![])
-                            .iter()
-                            .map(|t| (t.name.clone(), t.value.clone()))
-                            .collect()
-                    } else {
-                        HashMap::new()
-                    };
+pub struct GetTraceIds(pub SpanQuery);
+impl Message for GetTraceIds {
+    type Result = Vec<String>;
+}
 
-                    let binary_annotation_endpoint =
-                        remote_endpoint.clone().or_else(|| local_endpoint.clone());
+impl Handler<GetTraceIds> for super::DbReadExecutor {
+    type Result = MessageResult<GetTraceIds>;
 
-                    crate::opentracing::Span {
-                        trace_id: spandb.trace_id.clone(),
-                        id: spandb.id.clone(),
-                        parent_id: spandb.parent_id.clone(),
-                        name: spandb.name.clone().map(|s| s.chars().take(250).collect()),
-                        kind: spandb.kind.clone().map(std::convert::Into::into),
-                        timestamp: spandb.ts.map(|ts| {
-                            ((ts.timestamp() * 1000) + i64::from(ts.timestamp_subsec_millis()))
-                                * 1000
-                        }),
-                        duration: spandb.duration,
-                        debug: spandb.debug,
-                        shared: spandb.shared,
-                        local_endpoint,
-                        remote_endpoint,
-                        annotations,
-                        tags: tags.clone(),
-                        binary_annotations: tags
-                            .iter()
-                            .map(|(k, v)| crate::opentracing::span::BinaryTag {
-                                key: k.clone(),
-                                value: v.clone(),
-                                endpoint: binary_annotation_endpoint.clone(),
-                            })
-                            .collect(),
-                    }
-                })
-                .collect(),
-        )
+    fn handle(&mut self, msg: GetTraceIds, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_trace_ids(msg.0);
+        self.check_db_connection(ctx);
+        MessageResult(result)
     }
 }
@@ -1,69 +1,12 @@
 use actix::{Handler, Message, MessageResult};
 use chrono;
-use diesel::prelude::*;
-use serde_json;
-
-static TEST_ITEM_QUERY_LIMIT: i64 = 200;
-use crate::db::schema::test_item;
-#[derive(Debug, Insertable, Queryable, Clone, Identifiable)]
-#[table_name = "test_item"]
-pub struct TestItemDb {
-    pub id: String,
-    pub parent_id: String,
-    pub name: String,
-    source: i32,
-}
 
 static TEST_RESULT_QUERY_LIMIT: i64 = 100;
-use crate::db::schema::test_result;
-#[derive(Debug, Insertable, Queryable, Associations, Identifiable)]
-#[belongs_to(TestItemDb, foreign_key = "test_id")]
-#[primary_key(test_id, trace_id)]
-#[table_name = "test_result"]
-pub struct TestResultDb {
-    pub test_id: String,
-    pub trace_id: String,
-    pub date: chrono::NaiveDateTime,
-    pub status: i32,
-    pub duration: i64,
-    pub environment: Option<String>,
-    pub components_called: String,
-    pub nb_spans: i32,
-    pub cleanup_status: i32,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum ResultCleanupStatus {
-    WithData,
-    Important,
-    ToKeep,
-    Shell,
-}
-impl From<i32> for ResultCleanupStatus {
-    fn from(v: i32) -> Self {
-        match v {
-            1 => ResultCleanupStatus::Important,
-            2 => ResultCleanupStatus::Shell,
-            3 => ResultCleanupStatus::ToKeep,
-            _ => ResultCleanupStatus::WithData,
-        }
-    }
-}
-impl ResultCleanupStatus {
-    pub fn as_i32(&self) -> i32 {
-        match self {
-            ResultCleanupStatus::WithData => 0,
-            ResultCleanupStatus::Important => 1,
-            ResultCleanupStatus::Shell => 2,
-            ResultCleanupStatus::ToKeep => 3,
-        }
-    }
-}
-impl Into<i32> for ResultCleanupStatus {
-    fn into(self) -> i32 {
-        self.as_i32()
-    }
-}
+static FLAKY_HISTORY_LIMIT: i64 = 10_000;
+static FAILURE_HISTORY_LIMIT: i64 = 10_000;
+static TRACE_DIFF_SPANS_LIMIT: i64 = 10_000;
+static TRACE_DIFF_SUCCESS_LOOKUP: i64 = 100;
+static DURATION_REGRESSION_QUERY_LIMIT: i64 = 100;
 
 #[derive(Default)]
 pub struct TestItemQuery {
@@ -82,132 +25,20 @@ impl Message for GetTestItems {
 impl Handler<GetTestItems> for super::DbReadExecutor {
     type Result = MessageResult<GetTestItems>;
 
-    fn handle(&mut self, msg: GetTestItems, _: &mut Self::Context) -> Self::Result {
-        use super::super::schema::test_item::dsl::*;
-
-        let mut query = test_item.into_boxed();
-
-        if let Some(filter_parent_id) = msg.0.parent_id.clone() {
-            query = query.filter(parent_id.eq(filter_parent_id));
-        }
-
-        if let Some(filter_id) = msg.0.id.clone() {
-            query = query.filter(id.eq(filter_id));
-        }
-
-        let mut test_item_cache = super::super::helper::Cacher::new();
-
-        MessageResult(
-            query
-                .order(name.asc())
-                .load::<TestItemDb>(self.0.as_ref().expect("fail to get DB"))
-                .unwrap_or_else(|err| {
-                    error!("error loading test items: {:?}", err);
-                    vec![]
-                })
-                .iter()
-                .map(|ti| {
-                    let mut test_item_to_get = match ti.parent_id.as_ref() {
-                        "root" => None,
-                        item_id => Some(item_id.to_string()),
-                    };
-                    let mut path = vec![];
-                    if msg.0.with_full_path {
-                        while let Some(test_item_got) = test_item_to_get {
-                            if let Some(test) = test_item_cache
-                                .get(&test_item_got, |ti_id| {
-                                    use super::super::schema::test_item::dsl::*;
-                                    test_item
-                                        .filter(id.eq(ti_id))
-                                        .first::<TestItemDb>(
-                                            self.0.as_ref().expect("fail to get DB"),
-                                        )
-                                        .ok()
-                                })
-                                .clone()
-                            {
-                                test_item_to_get = match test.parent_id.as_ref() {
-                                    "root" => None,
-                                    item_id => Some(item_id.to_string()),
-                                };
-                                path.push(crate::api::test::TestItem {
-                                    id: test.id.clone(),
-                                    name: test.name.clone(),
-                                });
-                            } else {
-                                test_item_to_get = None;
-                            }
-                        }
-                        path.reverse();
-                    }
-
-                    let children = if msg.0.with_children {
-                        use super::super::schema::test_item::dsl::*;
-                        test_item
-                            .filter(parent_id.eq(&ti.id))
-                            .order(name.asc())
-                            .limit(TEST_ITEM_QUERY_LIMIT)
-                            .load::<TestItemDb>(self.0.as_ref().expect("fail to get DB"))
-                            .ok()
-                            .unwrap_or_else(|| vec![])
-                            .iter()
-                            .map(|ti| crate::api::test::TestItem {
-                                name: ti.name.clone(),
-                                id: ti.id.clone(),
-                            })
-                            .collect()
-                    } else {
-                        vec![]
-                    };
-
-                    let traces = if msg.0.with_traces {
-                        use super::super::schema::test_result::dsl::*;
-
-                        let query = TestResultDb::belonging_to(ti).order(date.desc()).limit(5);
-                        query
-                            .load::<TestResultDb>(self.0.as_ref().expect("fail to get DB"))
-                            .ok()
-                            .unwrap_or_else(|| vec![])
-                            .iter()
-                            .map(|tr| crate::engine::test_result::TestResult {
-                                test_id: tr.test_id.clone(),
-                                path: path.iter().map(|ti| ti.name.clone()).collect(),
-                                name: ti.name.clone(),
-                                date: (((tr.date.timestamp() * 1000)
-                                    + i64::from(tr.date.timestamp_subsec_millis()))
-                                    * 1000),
-                                duration: tr.duration,
-                                environment: tr.environment.clone(),
-                                status: tr.status.into(),
-                                trace_id: tr.trace_id.clone(),
-                                components_called: serde_json::from_str(&tr.components_called)
-                                    .unwrap(),
-                                nb_spans: tr.nb_spans,
-                                main_span: None,
-                            })
-                            .collect()
-                    } else {
-                        vec![]
-                    };
-
-                    crate::api::test::TestDetails {
-                        children,
-                        last_results: traces,
-                        name: ti.name.clone(),
-                        path,
-                        test_id: ti.id.clone(),
-                    }
-                })
-                .collect(),
-        )
+    fn handle(&mut self, msg: GetTestItems, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_test_items(msg.0);
+        self.check_db_connection(ctx);
+        MessageResult(result)
     }
 }
+
 #[derive(Debug)]
 pub struct TestResultQuery {
     pub trace_id: Option<String>,
     pub status: Option<i32>,
     pub test_id: Option<String>,
     pub environment: Option<String>,
+    pub run_id: Option<String>,
     pub min_duration: Option<i64>,
     pub max_duration: Option<i64>,
     pub ts: chrono::NaiveDateTime,
@@ -222,6 +53,7 @@ impl Default for TestResultQuery {
             status: None,
             test_id: None,
             environment: None,
+            run_id: None,
             min_duration: None,
             max_duration: None,
             ts: chrono::Utc::now().naive_utc(),
@@ -241,6 +73,7 @@ impl From<crate::api::test::TestResultsQueryParams> for TestResultQuery {
             }),
             test_id: params.test_id,
             environment: params.environment,
+            run_id: params.run_id,
             min_duration: params.min_duration,
             max_duration: params.max_duration,
             ts: params
@@ -276,155 +109,207 @@ impl Handler<GetTestResults> for super::DbReadExecutor {
     type Result = MessageResult<GetTestResults>;
 
     fn handle(&mut self, msg: GetTestResults, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_test_results(msg.0);
         self.check_db_connection(ctx);
-        use super::super::schema::test_result::dsl::*;
-
-        let mut query = test_result.into_boxed();
+        MessageResult(result)
+    }
+}
 
-        if let Some(query_trace_id) = msg.0.trace_id {
-            query = query.filter(trace_id.eq(query_trace_id));
-        }
+pub struct GetEnvironments;
+impl Message for GetEnvironments {
+    type Result = Vec<String>;
+}
+impl Handler<GetEnvironments> for super::DbReadExecutor {
+    type Result = MessageResult<GetEnvironments>;
 
-        if let Some(query_status) = msg.0.status {
-            query = query.filter(status.eq(query_status));
-        }
+    fn handle(&mut self, _msg: GetEnvironments, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_environments();
+        self.check_db_connection(ctx);
+        MessageResult(result)
+    }
+}
 
-        if let Some(query_test_id) = msg.0.test_id {
-            query = query.filter(test_id.eq(query_test_id));
-        }
+pub struct GetFlakyTests {
+    pub environment: Option<String>,
+    pub lookback: Option<chrono::Duration>,
+    pub window: usize,
+}
+impl Message for GetFlakyTests {
+    type Result = Vec<crate::engine::flaky::FlakyTest>;
+}
+impl Handler<GetFlakyTests> for super::DbReadExecutor {
+    type Result = MessageResult<GetFlakyTests>;
+
+    fn handle(&mut self, msg: GetFlakyTests, ctx: &mut Self::Context) -> Self::Result {
+        let history = self.0.get_test_results(TestResultQuery {
+            environment: msg.environment,
+            lookback: msg.lookback,
+            limit: FLAKY_HISTORY_LIMIT,
+            ..Default::default()
+        });
+        self.check_db_connection(ctx);
+        MessageResult(
+            crate::engine::flaky::compute(&history, msg.window)
+                .into_iter()
+                .filter(crate::engine::flaky::FlakyTest::is_flaky)
+                .collect(),
+        )
+    }
+}
 
-        if let Some(query_environment) = msg.0.environment {
-            query = query.filter(environment.eq(query_environment));
-        }
+pub struct GetFailureClusters {
+    pub environment: Option<String>,
+    pub lookback: Option<chrono::Duration>,
+}
+impl Message for GetFailureClusters {
+    type Result = Vec<crate::engine::failure::FailureCluster>;
+}
+impl Handler<GetFailureClusters> for super::DbReadExecutor {
+    type Result = MessageResult<GetFailureClusters>;
+
+    fn handle(&mut self, msg: GetFailureClusters, ctx: &mut Self::Context) -> Self::Result {
+        let failures = self.0.get_test_results(TestResultQuery {
+            status: Some(crate::engine::test_result::TestStatus::Failure.as_i32()),
+            environment: msg.environment,
+            lookback: msg.lookback,
+            limit: FAILURE_HISTORY_LIMIT,
+            ..Default::default()
+        });
+        self.check_db_connection(ctx);
+        MessageResult(crate::engine::failure::cluster(&failures))
+    }
+}
 
-        if let Some(query_max_duration) = msg.0.max_duration {
-            query = query.filter(duration.le(query_max_duration));
-        }
-        if let Some(query_min_duration) = msg.0.min_duration {
-            query = query.filter(duration.ge(query_min_duration));
-        }
+pub struct GetTraceDiff {
+    pub test_id: String,
+    /// latest failure of any environment if missing, compared to a success of its environment
+    pub environment: Option<String>,
+}
+impl Message for GetTraceDiff {
+    type Result = Option<crate::engine::trace_diff::TraceDiff>;
+}
+impl super::DbReadExecutor {
+    fn latest_failure(
+        &self,
+        test_id: &str,
+        environment: Option<String>,
+    ) -> Option<crate::engine::test_result::TestResult> {
+        self.0
+            .get_test_results(TestResultQuery {
+                test_id: Some(test_id.to_string()),
+                environment,
+                status: Some(crate::engine::test_result::TestStatus::Failure.as_i32()),
+                limit: 1,
+                ..Default::default()
+            })
+            .into_iter()
+            .next()
+    }
 
-        query = query.filter(date.le(msg.0.ts));
-        if let Some(query_lookback) = msg.0.lookback {
-            query = query.filter(date.ge(msg.0.ts - query_lookback));
-        }
+    /// last success before a failure, in the environment of the failure
+    fn success_before(
+        &self,
+        failure: &crate::engine::test_result::TestResult,
+    ) -> Option<crate::engine::test_result::TestResult> {
+        self.0
+            .get_test_results(TestResultQuery {
+                test_id: Some(failure.test_id.clone()),
+                environment: failure.environment.clone(),
+                status: Some(crate::engine::test_result::TestStatus::Success.as_i32()),
+                ts: chrono::NaiveDateTime::from_timestamp(
+                    failure.date / 1000 / 1000,
+                    (failure.date % (1000 * 1000) * 1000) as u32,
+                ),
+                limit: TRACE_DIFF_SUCCESS_LOOKUP,
+                ..Default::default()
+            })
+            .into_iter()
+            // a missing environment queries results of all environments
+            .find(|success| success.environment == failure.environment)
+    }
 
-        let test_results: Vec<TestResultDb> = query
-            .order(date.desc())
-            .limit(msg.0.limit)
-            .load(self.0.as_ref().expect("fail to get DB"))
-            .unwrap_or_else(|err| {
-                error!("error loading test results: {:?}", err);
-                self.reconnect_if_needed(ctx, &err);
-                vec![]
+    fn get_trace(&self, trace_id: &str) -> Vec<crate::opentracing::Span> {
+        self.0.get_spans(
+            super::span::SpanQuery::default()
+                .with_trace_id(trace_id.to_string())
+                .with_limit(TRACE_DIFF_SPANS_LIMIT),
+        )
+    }
+}
+impl Handler<GetTraceDiff> for super::DbReadExecutor {
+    type Result = MessageResult<GetTraceDiff>;
+
+    fn handle(&mut self, msg: GetTraceDiff, ctx: &mut Self::Context) -> Self::Result {
+        let GetTraceDiff {
+            test_id,
+            environment,
+        } = msg;
+        let trace_diff = self
+            .latest_failure(&test_id, environment)
+            .and_then(|failure| {
+                self.success_before(&failure)
+                    .map(|success| (success, failure))
+            })
+            .and_then(|(success, failure)| {
+                let success_spans = self.get_trace(&success.trace_id);
+                let failure_spans = self.get_trace(&failure.trace_id);
+                if success_spans.is_empty() || failure_spans.is_empty() {
+                    // spans were already cleaned up
+                    return None;
+                }
+                Some(crate::engine::trace_diff::diff(
+                    test_id,
+                    success.trace_id,
+                    &success_spans,
+                    failure.trace_id,
+                    &failure_spans,
+                ))
             });
+        self.check_db_connection(ctx);
+        MessageResult(trace_diff)
+    }
+}
 
-        let mut test_item_cache = super::super::helper::Cacher::new_with({
-            //prefetch first level test items in one query
-            use super::super::schema::test_item::dsl::*;
-
-            let mut query = test_item.into_boxed();
-            for tr in &test_results {
-                query = query.or_filter(id.eq(&tr.test_id));
-            }
-            query
-                .load::<TestItemDb>(self.0.as_ref().expect("fail to get DB"))
-                .ok()
-                .unwrap_or_else(|| vec![])
-                .iter()
-                .map(|item| (item.id.clone(), Some(item.clone())))
-                .collect()
-        });
-
-        MessageResult(
-            test_results
-                .iter()
-                .map(|tr| {
-                    let test = test_item_cache
-                        .get(&tr.test_id, |ti_id| {
-                            use super::super::schema::test_item::dsl::*;
-
-                            test_item
-                                .filter(id.eq(ti_id))
-                                .first::<TestItemDb>(self.0.as_ref().expect("fail to get DB"))
-                                .ok()
-                        })
-                        .clone();
-
-                    let mut test_item_to_get =
-                        test.clone().and_then(|t| match t.parent_id.as_ref() {
-                            "root" => None,
-                            item_id => Some(item_id.to_string()),
-                        });
-                    let mut path = vec![];
-                    while let Some(test_item_got) = test_item_to_get {
-                        if let Some(test) = test_item_cache
-                            .get(&test_item_got, |ti_id| {
-                                use super::super::schema::test_item::dsl::*;
-                                test_item
-                                    .filter(id.eq(ti_id))
-                                    .first::<TestItemDb>(self.0.as_ref().expect("fail to get DB"))
-                                    .ok()
-                            })
-                            .clone()
-                        {
-                            test_item_to_get = match test.parent_id.as_ref() {
-                                "root" => None,
-                                item_id => Some(item_id.to_string()),
-                            };
-                            path.push(test.name);
-                        } else {
-                            test_item_to_get = None;
-                        }
-                    }
-                    path.reverse();
+#[derive(Debug)]
+pub struct DurationRegressionQuery {
+    pub test_id: Option<String>,
+    pub environment: Option<String>,
+    pub limit: i64,
+}
 
-                    crate::engine::test_result::TestResult {
-                        test_id: tr.test_id.clone(),
-                        path,
-                        name: test
-                            .map(|t| t.name)
-                            .unwrap_or_else(|| "missing name".to_string()),
-                        date: (((tr.date.timestamp() * 1000)
-                            + i64::from(tr.date.timestamp_subsec_millis()))
-                            * 1000),
-                        duration: tr.duration,
-                        environment: tr.environment.clone(),
-                        status: tr.status.into(),
-                        trace_id: tr.trace_id.clone(),
-                        components_called: serde_json::from_str(&tr.components_called).unwrap(),
-                        nb_spans: tr.nb_spans,
-                        main_span: None,
-                    }
-                })
-                .collect::<Vec<crate::engine::test_result::TestResult>>(),
-        )
+impl Default for DurationRegressionQuery {
+    fn default() -> Self {
+        DurationRegressionQuery {
+            test_id: None,
+            environment: None,
+            limit: DURATION_REGRESSION_QUERY_LIMIT,
+        }
     }
 }
 
-pub struct GetEnvironments;
-impl Message for GetEnvironments {
-    type Result = Vec<String>;
+impl From<crate::api::test::DurationRegressionsQueryParams> for DurationRegressionQuery {
+    fn from(params: crate::api::test::DurationRegressionsQueryParams) -> Self {
+        DurationRegressionQuery {
+            test_id: params.test_id,
+            environment: params.environment,
+            limit: params
+                .limit
+                .map(|v| v.min(DURATION_REGRESSION_QUERY_LIMIT))
+                .unwrap_or(DURATION_REGRESSION_QUERY_LIMIT),
+        }
+    }
 }
-impl Handler<GetEnvironments> for super::DbReadExecutor {
-    type Result = MessageResult<GetEnvironments>;
 
-    fn handle(&mut self, _msg: GetEnvironments, _: &mut Self::Context) -> Self::Result {
-        use super::super::schema::test_result::dsl::*;
+pub struct GetDurationRegressions(pub DurationRegressionQuery);
+impl Message for GetDurationRegressions {
+    type Result = Vec<crate::engine::baseline::DurationRegression>;
+}
+impl Handler<GetDurationRegressions> for super::DbReadExecutor {
+    type Result = MessageResult<GetDurationRegressions>;
 
-        MessageResult(
-            test_result
-                .select(environment)
-                .filter(environment.is_not_null())
-                .distinct()
-                .load::<Option<String>>(self.0.as_ref().expect("fail to get DB"))
-                .unwrap_or_else(|err| {
-                    error!("error loading environment from test results: {:?}", err);
-                    vec![]
-                })
-                .iter()
-                .filter_map(std::clone::Clone::clone)
-                .collect(),
-        )
+    fn handle(&mut self, msg: GetDurationRegressions, ctx: &mut Self::Context) -> Self::Result {
+        let result = self.0.get_duration_regressions(msg.0);
+        self.check_db_connection(ctx);
+        MessageResult(result)
     }
 }
@@ -3,8 +3,23 @@ table! {
         annotation_id -> Varchar,
         trace_id -> Varchar,
         span_id -> Varchar,
+        shared -> Bool,
         ts -> Timestamp,
-        value -> Varchar,
+        value -> Text,
+    }
+}
+
+table! {
+    duration_regression (test_id, trace_id) {
+        test_id -> Varchar,
+        trace_id -> Varchar,
+        environment -> Nullable<Varchar>,
+        date -> Timestamp,
+        duration -> Int8,
+        nb_results -> Int4,
+        median -> Int8,
+        p90 -> Int8,
+        p95 -> Int8,
     }
 }
 
@@ -18,6 +33,19 @@ table! {
     }
 }
 
+table! {
+    ingest (id) {
+        id -> Varchar,
+        created_at -> Timestamp,
+        processed_at -> Nullable<Timestamp>,
+        nb_received -> Int4,
+        nb_stored -> Int4,
+        nb_failed -> Int4,
+        nb_test_results -> Int4,
+        interrupted -> Bool,
+    }
+}
+
 table! {
     report (id) {
         id -> Varchar,
@@ -40,7 +68,7 @@ table! {
 }
 
 table! {
-    span (trace_id, id) {
+    span (trace_id, id, shared) {
         trace_id -> Varchar,
         id -> Varchar,
         parent_id -> Nullable<Varchar>,
@@ -56,8 +84,10 @@ table! {
 }
 
 table! {
-    tag (span_id, name) {
+    tag (trace_id, span_id, shared, name) {
+        trace_id -> Varchar,
         span_id -> Varchar,
+        shared -> Bool,
         name -> Varchar,
         value -> Varchar,
     }
@@ -83,6 +113,23 @@ table! {
         components_called -> Varchar,
         nb_spans -> Int4,
         cleanup_status -> Int4,
+        build -> Nullable<Varchar>,
+        steps -> Varchar,
+        failure -> Nullable<Varchar>,
+        blamed_component -> Nullable<Varchar>,
+        run_id -> Nullable<Varchar>,
+        attempt -> Int4,
+    }
+}
+
+table! {
+    test_run (id) {
+        id -> Varchar,
+        commit_sha -> Nullable<Varchar>,
+        branch -> Nullable<Varchar>,
+        ci_url -> Nullable<Varchar>,
+        start_date -> Timestamp,
+        end_date -> Timestamp,
     }
 }
 
@@ -97,12 +144,15 @@ table! {
     }
 }
 
+joinable!(duration_regression -> test_item (test_id));
 joinable!(test_result -> test_item (test_id));
 joinable!(test_result_in_report -> report (report_id));
 
 allow_tables_to_appear_in_same_query!(
     annotation,
+    duration_regression,
     endpoint,
+    ingest,
     report,
     script,
     span,
@@ -110,4 +160,5 @@ allow_tables_to_appear_in_same_query!(
     test_item,
     test_result,
     test_result_in_report,
+    test_run,
 );
@@ -0,0 +1,174 @@
+use diesel::prelude::*;
+
+use crate::db::storage::ResultCleanupStatus;
+
+macro_rules! impl_cleanup_storage {
+    ($connection:ty) => {
+        impl super::SqlStorage<$connection> {
+            pub(super) fn delete_old_data(&self) {
+                use crate::db::schema::test_result::dsl::*;
+
+                let deleted = {
+                    let limit = chrono::Utc::now().naive_utc()
+                        - chrono::Duration::milliseconds(i64::from(
+                            crate::CONFIG.cleanup.delay_test_results,
+                        ));
+
+                    test_result
+                        .filter(date.lt(limit))
+                        .filter(cleanup_status.eq(ResultCleanupStatus::Shell.as_i32()))
+                        .load::<super::test::TestResultDb>(self.conn())
+                        .ok()
+                        .unwrap_or_else(|| vec![])
+                        .iter()
+                        .for_each(|tr| {
+                            use crate::db::schema::test_result_in_report::dsl::*;
+
+                            diesel::delete(
+                                test_result_in_report
+                                    .filter(trace_id.eq(&tr.trace_id).and(test_id.eq(&tr.test_id))),
+                            )
+                            .execute(self.conn())
+                            .ok();
+
+                            {
+                                use crate::db::schema::duration_regression::dsl::*;
+
+                                diesel::delete(duration_regression.filter(
+                                    trace_id.eq(&tr.trace_id).and(test_id.eq(&tr.test_id)),
+                                ))
+                                .execute(self.conn())
+                                .ok();
+                            }
+                        });
+
+                    diesel::delete(
+                        test_result
+                            .filter(date.lt(limit))
+                            .filter(cleanup_status.eq(ResultCleanupStatus::Shell.as_i32())),
+                    )
+                    .execute(self.conn())
+                    .unwrap()
+                };
+
+                {
+                    let limit = chrono::Utc::now().naive_utc()
+                        - chrono::Duration::milliseconds(i64::from(
+                            crate::CONFIG.cleanup.delay_test_results,
+                        ));
+
+                    use crate::db::schema::test_run::dsl::*;
+
+                    diesel::delete(test_run.filter(end_date.lt(limit)))
+                        .execute(self.conn())
+                        .ok();
+                }
+
+                let to_clean: Vec<super::test::TestResultDb> = {
+                    let limit = chrono::Utc::now().naive_utc()
+                        - chrono::Duration::milliseconds(i64::from(
+                            crate::CONFIG.cleanup.delay_spans,
+                        ));
+
+                    let to_clean = test_result
+                        .filter(date.lt(limit))
+                        .filter(cleanup_status.eq(ResultCleanupStatus::WithData.as_i32()))
+                        .load::<super::test::TestResultDb>(self.conn())
+                        .ok()
+                        .unwrap_or_else(|| vec![]);
+
+                    diesel::update(
+                        test_result
+                            .filter(date.lt(limit))
+                            .filter(cleanup_status.eq(ResultCleanupStatus::WithData.as_i32())),
+                    )
+                    .set(cleanup_status.eq(ResultCleanupStatus::Shell.as_i32()))
+                    .execute(self.conn())
+                    .ok();
+
+                    to_clean
+                };
+
+                to_clean.iter().for_each(|tr| {
+                    use crate::db::schema::span::dsl::*;
+
+                    let spans: Vec<super::span::SpanDb> = {
+                        span.filter(trace_id.eq(&tr.trace_id))
+                            .load::<super::span::SpanDb>(self.conn())
+                            .ok()
+                            .unwrap_or_else(|| vec![])
+                    };
+
+                    spans.iter().for_each(|spandb| {
+                        {
+                            use crate::db::schema::annotation::dsl::*;
+
+                            diesel::delete(
+                                annotation.filter(
+                                    trace_id.eq(&spandb.trace_id).and(span_id.eq(&spandb.id)),
+                                ),
+                            )
+                            .execute(self.conn())
+                            .ok();
+                        }
+
+                        {
+                            use crate::db::schema::tag::dsl::*;
+
+                            diesel::delete(
+                                tag.filter(
+                                    trace_id.eq(&spandb.trace_id).and(span_id.eq(&spandb.id)),
+                                ),
+                            )
+                            .execute(self.conn())
+                            .ok();
+                        }
+                    });
+
+                    diesel::delete(span.filter(trace_id.eq(&tr.trace_id)))
+                        .execute(self.conn())
+                        .ok();
+                });
+
+                let reports_cleaned = {
+                    let limit = chrono::Utc::now().naive_utc()
+                        - chrono::Duration::milliseconds(i64::from(
+                            crate::CONFIG.cleanup.delay_reports,
+                        ));
+
+                    use crate::db::schema::report::dsl::*;
+
+                    report
+                        .filter(last_update.lt(limit))
+                        .load::<super::reports::ReportDb>(self.conn())
+                        .ok()
+                        .unwrap_or_else(|| vec![])
+                        .iter()
+                        .for_each(|rep| {
+                            use crate::db::schema::test_result_in_report::dsl::*;
+
+                            diesel::delete(test_result_in_report.filter(report_id.eq(&rep.id)))
+                                .execute(self.conn())
+                                .ok();
+                        });
+
+                    diesel::delete(report.filter(last_update.lt(limit)))
+                        .execute(self.conn())
+                        .unwrap()
+                };
+
+                info!(
+                    "deleted {} test results, cleaned {} and removed {} reports",
+                    deleted,
+                    to_clean.len(),
+                    reports_cleaned,
+                );
+            }
+        }
+    };
+}
+
+#[cfg(feature = "postgres")]
+impl_cleanup_storage!(diesel::PgConnection);
+#[cfg(feature = "sqlite")]
+impl_cleanup_storage!(diesel::SqliteConnection);
@@ -0,0 +1,105 @@
+use diesel::prelude::*;
+
+use crate::engine::ingestor::Ingest;
+
+use crate::db::schema::ingest;
+#[derive(Debug, Insertable, Queryable, AsChangeset)]
+#[table_name = "ingest"]
+#[changeset_options(treat_none_as_null = "true")]
+struct IngestDb {
+    id: String,
+    created_at: chrono::NaiveDateTime,
+    processed_at: Option<chrono::NaiveDateTime>,
+    nb_received: i32,
+    nb_stored: i32,
+    nb_failed: i32,
+    nb_test_results: i32,
+    interrupted: bool,
+}
+
+impl From<IngestDb> for Ingest {
+    fn from(ingest_db: IngestDb) -> Self {
+        Ingest {
+            ingest_id: crate::engine::IngestId(ingest_db.id),
+            created_at: ingest_db.created_at,
+            processed_at: ingest_db.processed_at,
+            nb_received: ingest_db.nb_received,
+            nb_stored: ingest_db.nb_stored,
+            nb_failed: ingest_db.nb_failed,
+            nb_test_results: ingest_db.nb_test_results,
+            interrupted: ingest_db.interrupted,
+        }
+    }
+}
+
+macro_rules! impl_ingest_storage {
+    ($connection:ty) => {
+        impl crate::db::storage::IngestStorage for super::SqlStorage<$connection> {
+            fn save_ingest(&mut self, ingest_to_save: Ingest) {
+                use crate::db::schema::ingest::dsl::*;
+                let ingest_db = IngestDb {
+                    id: ingest_to_save.ingest_id.0,
+                    created_at: ingest_to_save.created_at,
+                    processed_at: ingest_to_save.processed_at,
+                    nb_received: ingest_to_save.nb_received,
+                    nb_stored: ingest_to_save.nb_stored,
+                    nb_failed: ingest_to_save.nb_failed,
+                    nb_test_results: ingest_to_save.nb_test_results,
+                    interrupted: ingest_to_save.interrupted,
+                };
+                let exists = ingest
+                    .filter(id.eq(&ingest_db.id))
+                    .count()
+                    .get_result::<i64>(self.conn())
+                    .map(|count| count > 0)
+                    .unwrap_or(false);
+                let saved = if exists {
+                    diesel::update(ingest.filter(id.eq(&ingest_db.id)))
+                        .set(&ingest_db)
+                        .execute(self.conn())
+                } else {
+                    diesel::insert_into(ingest)
+                        .values(&ingest_db)
+                        .execute(self.conn())
+                };
+                if let Err(err) = saved {
+                    error!("error saving ingest {}: {:?}", ingest_db.id, err);
+                    self.reconnect_if_needed(&err);
+                }
+            }
+
+            fn get_ingest(&self, ingest_id: &str) -> Option<Ingest> {
+                use crate::db::schema::ingest::dsl::*;
+                ingest
+                    .filter(id.eq(ingest_id))
+                    .first::<IngestDb>(self.conn())
+                    .map_err(|err| self.reconnect_if_needed(&err))
+                    .ok()
+                    .map(Ingest::from)
+            }
+
+            fn interrupt_unfinished_ingests(
+                &mut self,
+                at: chrono::NaiveDateTime,
+            ) -> Result<usize, crate::db::storage::StorageError> {
+                use crate::db::schema::ingest::dsl::*;
+                diesel::update(ingest.filter(processed_at.is_null()))
+                    .set((
+                        processed_at.eq(Some(at)),
+                        nb_failed.eq(nb_received - nb_stored),
+                        interrupted.eq(true),
+                    ))
+                    .execute(self.conn())
+                    .map_err(|err| {
+                        self.reconnect_if_needed(&err);
+                        crate::db::storage::StorageError(err.to_string())
+                    })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "postgres")]
+impl_ingest_storage!(diesel::PgConnection);
+#[cfg(feature = "sqlite")]
+impl_ingest_storage!(diesel::SqliteConnection);
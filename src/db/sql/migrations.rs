@@ -0,0 +1,56 @@
+use diesel_migrations::MigrationConnection;
+
+use crate::db::storage::StorageError;
+
+embed_migrations!();
+
+/// version of the most recent migration directory, as diesel names it, found by `build.rs`
+static LATEST_VERSION: &str = env!("IKRELLN_LATEST_MIGRATION");
+
+impl<C: MigrationConnection> super::SqlStorage<C> {
+    /// Apply the migrations missing from the database, refusing a schema from a newer i'Krelln
+    pub(super) fn run_migrations(&self) -> Result<(), StorageError> {
+        let conn = self
+            .connection
+            .as_ref()
+            .ok_or_else(|| StorageError("could not connect to DB".to_string()))?;
+
+        diesel_migrations::setup_database(conn)
+            .map_err(|err| StorageError(format!("could not set up migrations: {}", err)))?;
+        let applied = conn
+            .latest_run_migration_version()
+            .map_err(|err| StorageError(format!("could not read schema version: {}", err)))?;
+        if let Some(applied) = applied {
+            if applied.as_str() > LATEST_VERSION {
+                return Err(StorageError(format!(
+                    "DB schema version {} is newer than the latest known {}",
+                    applied, LATEST_VERSION
+                )));
+            }
+        }
+
+        let mut output = vec![];
+        let result = embedded_migrations::run_with_output(conn, &mut output);
+        for line in String::from_utf8_lossy(&output).lines() {
+            info!("{}", line);
+        }
+        result.map_err(|err| StorageError(format!("could not migrate DB: {}", err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn latest_version_is_the_last_migration() {
+        let mut directories: Vec<String> = std::fs::read_dir("migrations")
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        directories.sort();
+        let last = directories.last().unwrap();
+        assert_eq!(
+            super::LATEST_VERSION,
+            last.split('_').next().unwrap().replace("-", "")
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use std::cell::Cell;
+
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+use super::storage::{Storage, StorageError};
+
+mod cleanup;
+mod ingest;
+mod migrations;
+mod reports;
+mod run;
+mod scripts;
+mod span;
+mod test;
+
+/// Storage in a SQL database through Diesel, one connection per storage
+pub struct SqlStorage<C> {
+    connection: Option<C>,
+    connection_lost: Cell<bool>,
+}
+
+impl<C: Connection> SqlStorage<C> {
+    fn from_connection(connection: ConnectionResult<C>) -> Self {
+        SqlStorage {
+            connection: connection
+                .map_err(|err| error!("error opening connection to DB: {:?}", err))
+                .ok(),
+            connection_lost: Cell::new(false),
+        }
+    }
+
+    fn conn(&self) -> &C {
+        self.connection.as_ref().expect("fail to get DB")
+    }
+
+    fn reconnect_if_needed(&self, error: &DieselError) {
+        if let DieselError::DatabaseError(DatabaseErrorKind::UnableToSendCommand, _) = error {
+            self.connection_lost.set(true);
+        }
+    }
+}
+
+macro_rules! impl_storage {
+    ($connection:ty) => {
+        impl Storage for SqlStorage<$connection> {
+            fn clean_up(&mut self) {
+                self.delete_old_data();
+            }
+
+            fn is_connected(&self) -> bool {
+                self.connection.is_some() && !self.connection_lost.get()
+            }
+
+            fn migrate(&mut self) -> Result<(), StorageError> {
+                self.run_migrations()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "postgres")]
+impl_storage!(PgConnection);
+#[cfg(feature = "sqlite")]
+impl_storage!(SqliteConnection);
+
+#[cfg(feature = "postgres")]
+pub fn open_postgres(database_url: &str) -> Box<dyn Storage> {
+    info!("opening connection to DB {}", database_url);
+    Box::new(SqlStorage::from_connection(PgConnection::establish(
+        database_url,
+    )))
+}
+
+#[cfg(not(feature = "postgres"))]
+pub fn open_postgres(_database_url: &str) -> Box<dyn Storage> {
+    panic!("ikrelln was built without PostgreSQL support")
+}
+
+#[cfg(feature = "sqlite")]
+pub fn open_sqlite(database_path: &str) -> Box<dyn Storage> {
+    info!("opening connection to DB {}", database_path);
+    let connection = SqliteConnection::establish(database_path).and_then(|connection| {
+        // wait for concurrent writes instead of failing with "database is locked"
+        connection
+            .execute("PRAGMA busy_timeout = 5000;")
+            .map_err(ConnectionError::CouldntSetupConfiguration)?;
+        Ok(connection)
+    });
+    Box::new(SqlStorage::from_connection(connection))
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn open_sqlite(_database_path: &str) -> Box<dyn Storage> {
+    panic!("ikrelln was built without SQLite support")
+}
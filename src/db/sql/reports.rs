@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use crate::engine::test_result::TestStatus;
+
+static REPORT_QUERY_LIMIT: i64 = 200;
+use crate::db::schema::report;
+#[derive(Debug, Insertable, Queryable, Clone)]
+#[table_name = "report"]
+pub struct ReportDb {
+    pub id: String,
+    name: String,
+    folder: String,
+    created_on: chrono::NaiveDateTime,
+    last_update: chrono::NaiveDateTime,
+}
+
+use crate::db::schema::test_result_in_report;
+#[derive(Debug, Insertable, Queryable, Clone)]
+#[table_name = "test_result_in_report"]
+struct TestResultInReportDb {
+    report_id: String,
+    test_id: String,
+    trace_id: String,
+    category: String,
+    environment: Option<String>,
+    status: i32,
+}
+
+macro_rules! impl_report_storage {
+    ($connection:ty) => {
+        impl super::SqlStorage<$connection> {
+            fn find_report(&self, report_db: &ReportDb) -> Option<ReportDb> {
+                use crate::db::schema::report::dsl::*;
+                report
+                    .filter(folder.eq(&report_db.folder))
+                    .filter(name.eq(&report_db.name))
+                    .first::<ReportDb>(self.conn())
+                    .ok()
+            }
+
+            fn update_report_or_create(&self, report_db: &ReportDb) -> String {
+                use crate::db::schema::report::dsl::*;
+
+                match self.find_report(report_db) {
+                    Some(existing) => {
+                        diesel::update(report.filter(id.eq(&existing.id)))
+                            .set(last_update.eq(report_db.last_update))
+                            .execute(self.conn())
+                            .ok();
+                        existing.id
+                    }
+                    None => {
+                        let new_id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+                        let could_insert = diesel::insert_into(report)
+                            .values(&ReportDb {
+                                id: new_id.clone(),
+                                ..(*report_db).clone()
+                            })
+                            .execute(self.conn());
+                        if could_insert.is_err() {
+                            self.find_report(report_db)
+                                .map(|existing| {
+                                    diesel::update(report.filter(id.eq(&existing.id)))
+                                        .set(last_update.eq(report_db.last_update))
+                                        .execute(self.conn())
+                                        .ok();
+                                    existing.id
+                                })
+                                .expect("fail to find report")
+                        } else {
+                            new_id
+                        }
+                    }
+                }
+            }
+
+            /// run ID, attempt and status of the result already in a report
+            fn reported_attempt(
+                &self,
+                in_report: &TestResultInReportDb,
+            ) -> Option<(Option<String>, i32, i32)> {
+                use crate::db::schema::test_result::dsl::*;
+                test_result
+                    .select((run_id, attempt, status))
+                    .filter(test_id.eq(&in_report.test_id))
+                    .filter(trace_id.eq(&in_report.trace_id))
+                    .first::<(Option<String>, i32, i32)>(self.conn())
+                    .ok()
+            }
+        }
+
+        impl crate::db::storage::ReportStorage for super::SqlStorage<$connection> {
+            fn save_result_for_report(
+                &mut self,
+                result_for_report: crate::engine::report::ResultForReport,
+            ) {
+                let report = ReportDb {
+                    id: "n/a".to_string(),
+                    name: result_for_report.report_name.clone(),
+                    folder: result_for_report.report_group.clone(),
+                    created_on: chrono::Utc::now().naive_utc(),
+                    last_update: chrono::Utc::now().naive_utc(),
+                };
+
+                let found_report_id = self.update_report_or_create(&report);
+
+                use crate::db::schema::test_result_in_report::dsl::*;
+                let mut find_tr = test_result_in_report
+                    .filter(report_id.eq(&found_report_id))
+                    .filter(test_id.eq(&result_for_report.result.test_id))
+                    .into_boxed();
+                if let Some(category_from_input) = result_for_report.category.clone() {
+                    find_tr = find_tr.filter(category.eq(category_from_input));
+                } else {
+                    find_tr = find_tr.filter(category.eq(&result_for_report.report_name));
+                }
+                if let Some(environment_from_input) = result_for_report.result.environment.clone() {
+                    find_tr = find_tr.filter(environment.eq(environment_from_input));
+                } else {
+                    find_tr = find_tr.filter(environment.is_null());
+                }
+                if let Ok(existing) = find_tr.first::<TestResultInReportDb>(self.conn()) {
+                    let (kept_trace_id, kept_status) = match self.reported_attempt(&existing) {
+                        Some((reported_run_id, reported_attempt, reported_status))
+                            if result_for_report
+                                .result
+                                .is_earlier_attempt(&reported_run_id, reported_attempt) =>
+                        {
+                            // keep the last attempt, its status may have changed with this one
+                            (existing.trace_id, reported_status)
+                        }
+                        _ => (
+                            result_for_report.result.trace_id.clone(),
+                            result_for_report.result.status.as_i32(),
+                        ),
+                    };
+                    match (
+                        result_for_report.category,
+                        result_for_report.result.environment,
+                    ) {
+                        (Some(category_from_input), Some(environment_from_input)) => {
+                            diesel::update(
+                                test_result_in_report
+                                    .filter(report_id.eq(&found_report_id))
+                                    .filter(test_id.eq(&result_for_report.result.test_id))
+                                    .filter(category.eq(category_from_input))
+                                    .filter(environment.eq(environment_from_input)),
+                            )
+                            .set((trace_id.eq(kept_trace_id), status.eq(kept_status)))
+                            .execute(self.conn())
+                            .ok();
+                        }
+                        (Some(category_from_input), None) => {
+                            diesel::update(
+                                test_result_in_report
+                                    .filter(report_id.eq(&found_report_id))
+                                    .filter(test_id.eq(&result_for_report.result.test_id))
+                                    .filter(category.eq(category_from_input))
+                                    .filter(environment.is_null()),
+                            )
+                            .set((trace_id.eq(kept_trace_id), status.eq(kept_status)))
+                            .execute(self.conn())
+                            .ok();
+                        }
+
+                        (None, Some(environment_from_input)) => {
+                            diesel::update(
+                                test_result_in_report
+                                    .filter(report_id.eq(&found_report_id))
+                                    .filter(test_id.eq(&result_for_report.result.test_id))
+                                    .filter(category.eq(&result_for_report.report_name))
+                                    .filter(environment.eq(environment_from_input)),
+                            )
+                            .set((trace_id.eq(kept_trace_id), status.eq(kept_status)))
+                            .execute(self.conn())
+                            .ok();
+                        }
+                        (None, None) => {
+                            diesel::update(
+                                test_result_in_report
+                                    .filter(report_id.eq(&found_report_id))
+                                    .filter(test_id.eq(&result_for_report.result.test_id))
+                                    .filter(category.eq(&result_for_report.report_name))
+                                    .filter(environment.is_null()),
+                            )
+                            .set((trace_id.eq(kept_trace_id), status.eq(kept_status)))
+                            .execute(self.conn())
+                            .ok();
+                        }
+                    };
+                } else {
+                    diesel::insert_into(test_result_in_report)
+                        .values(&TestResultInReportDb {
+                            test_id: result_for_report.result.test_id.clone(),
+                            trace_id: result_for_report.result.trace_id.clone(),
+                            report_id: found_report_id.clone(),
+                            category: result_for_report
+                                .category
+                                .clone()
+                                .unwrap_or_else(|| result_for_report.report_name.clone()),
+                            environment: result_for_report.result.environment,
+                            status: result_for_report.result.status.into(),
+                        })
+                        .execute(self.conn())
+                        .ok();
+                }
+            }
+
+            fn get_reports(&self) -> Vec<crate::api::report::Report> {
+                use crate::db::schema::report::dsl::*;
+
+                let reports: Vec<ReportDb> = report
+                    .order(last_update.desc())
+                    .limit(REPORT_QUERY_LIMIT)
+                    .load(self.conn())
+                    .unwrap_or_else(|err| {
+                        error!("error loading reports: {:?}", err);
+                        vec![]
+                    });
+
+                reports
+                    .iter()
+                    .map(|report_from_db| {
+                        let environments: Vec<String> = {
+                            use crate::db::schema::test_result_in_report::dsl::*;
+                            test_result_in_report
+                                .select(environment)
+                                .filter(report_id.eq(&report_from_db.id))
+                                .order(environment.asc())
+                                .distinct()
+                                .load::<Option<String>>(self.conn())
+                                .unwrap_or_else(|err| {
+                                    error!("error loading environment from reports: {:?}", err);
+                                    vec![]
+                                })
+                                .iter()
+                                .map(|vo| match vo {
+                                    Some(ref v) => v.clone(),
+                                    None => "None".to_string(),
+                                })
+                                .collect()
+                        };
+                        let statuses = [
+                            TestStatus::Success,
+                            TestStatus::Failure,
+                            TestStatus::Skipped,
+                            TestStatus::PassedOnRetry,
+                        ];
+                        let summary: HashMap<TestStatus, usize> = {
+                            use crate::db::schema::test_result_in_report::dsl::*;
+
+                            let mut summary = HashMap::new();
+                            for one_status in &statuses {
+                                let query = test_result_in_report
+                                    .select(test_id)
+                                    .distinct()
+                                    .filter(report_id.eq(&report_from_db.id))
+                                    .filter(status.eq(one_status.as_i32()));
+
+                                summary.insert(
+                                    one_status.clone(),
+                                    query
+                                        .load(self.conn())
+                                        .map(|v: Vec<String>| v.len())
+                                        .unwrap_or(0),
+                                );
+                            }
+                            summary
+                        };
+
+                        crate::api::report::Report {
+                            name: report_from_db.name.clone(),
+                            group: report_from_db.folder.clone(),
+                            created_on: report_from_db.created_on,
+                            last_update: report_from_db.last_update,
+                            categories: None,
+                            environments,
+                            summary: Some(summary),
+                        }
+                    })
+                    .collect()
+            }
+
+            fn get_report(
+                &self,
+                report_group: &str,
+                report_name: &str,
+                environment_query: Option<&str>,
+            ) -> Option<crate::api::report::Report> {
+                use crate::db::schema::report::dsl::*;
+
+                let report_from_db: Option<ReportDb> = report
+                    .filter(folder.eq(report_group))
+                    .filter(name.eq(report_name))
+                    .first(self.conn())
+                    .ok();
+
+                report_from_db.map(|report_from_db| {
+                    use crate::db::schema::test_result_in_report::dsl::*;
+                    let categories: Vec<String> = test_result_in_report
+                        .select(category)
+                        .filter(report_id.eq(&report_from_db.id))
+                        .order(category.asc())
+                        .distinct()
+                        .load::<String>(self.conn())
+                        .unwrap_or_else(|err| {
+                            error!("error loading categories for report: {:?}", err);
+                            vec![]
+                        });
+
+                    let mut test_results: HashMap<
+                        String,
+                        Vec<crate::engine::test_result::TestResult>,
+                    > = HashMap::new();
+                    categories.iter().for_each(|category_found| {
+                        let mut traces_query = test_result_in_report
+                            .select(trace_id)
+                            .filter(report_id.eq(&report_from_db.id))
+                            .filter(category.eq(category_found))
+                            .into_boxed();
+                        traces_query = match environment_query {
+                            Some("None") => traces_query.filter(environment.is_null()),
+                            Some(v) => traces_query.filter(environment.eq(v)),
+                            None => traces_query.filter(environment.is_null()),
+                        };
+                        let traces: Vec<_> = traces_query
+                            .load::<String>(self.conn())
+                            .unwrap_or_else(|err| {
+                                error!("error loading test results from category: {:?}", err);
+                                vec![]
+                            });
+                        let results = {
+                            use crate::db::schema::test_result::dsl::*;
+                            let mut test_item_cache = crate::db::helper::Cacher::new();
+
+                            let mut tr_query =
+                                test_result.filter(trace_id.eq_any(traces)).into_boxed();
+                            tr_query = match environment_query {
+                                Some("None") => tr_query.filter(environment.is_null()),
+                                Some(v) => tr_query.filter(environment.eq(v)),
+                                None => tr_query.filter(environment.is_null()),
+                            };
+
+                            tr_query
+                                .order(date.desc())
+                                .load::<super::test::TestResultDb>(self.conn())
+                                .unwrap_or_else(|err| {
+                                    error!("error loading test results: {:?}", err);
+                                    vec![]
+                                })
+                                .iter()
+                                .map(|tr| {
+                                    let test = test_item_cache
+                                        .get(&tr.test_id, |ti_id| {
+                                            use crate::db::schema::test_item::dsl::*;
+
+                                            test_item
+                                                .filter(id.eq(ti_id))
+                                                .first::<super::test::TestItemDb>(self.conn())
+                                                .ok()
+                                        })
+                                        .clone();
+
+                                    let mut test_item_to_get =
+                                        test.clone().and_then(|t| match t.parent_id.as_ref() {
+                                            "root" => None,
+                                            item_id => Some(item_id.to_string()),
+                                        });
+                                    let mut path = vec![];
+                                    while let Some(test_item) = test_item_to_get {
+                                        if let Some(test) = test_item_cache
+                                            .get(&test_item, |ti_id| {
+                                                use crate::db::schema::test_item::dsl::*;
+                                                test_item
+                                                    .filter(id.eq(ti_id))
+                                                    .first::<super::test::TestItemDb>(self.conn())
+                                                    .ok()
+                                            })
+                                            .clone()
+                                        {
+                                            test_item_to_get = match test.parent_id.as_ref() {
+                                                "root" => None,
+                                                item_id => Some(item_id.to_string()),
+                                            };
+                                            path.push(test.name);
+                                        } else {
+                                            test_item_to_get = None;
+                                        }
+                                    }
+                                    path.reverse();
+
+                                    crate::engine::test_result::TestResult {
+                                        test_id: tr.test_id.clone(),
+                                        path,
+                                        name: test
+                                            .map(|t| t.name)
+                                            .unwrap_or_else(|| "missing name".to_string()),
+                                        date: (((tr.date.timestamp() * 1000)
+                                            + i64::from(tr.date.timestamp_subsec_millis()))
+                                            * 1000),
+                                        duration: tr.duration,
+                                        environment: tr.environment.clone(),
+                                        build: tr.build.clone(),
+                                        run_id: tr.run_id.clone(),
+                                        attempt: tr.attempt,
+                                        status: tr.status.into(),
+                                        trace_id: tr.trace_id.clone(),
+                                        components_called: serde_json::from_str(
+                                            &tr.components_called,
+                                        )
+                                        .unwrap(),
+                                        nb_spans: tr.nb_spans,
+                                        steps: serde_json::from_str(&tr.steps).unwrap_or_default(),
+                                        failure: tr
+                                            .failure
+                                            .as_ref()
+                                            .and_then(|saved| serde_json::from_str(saved).ok()),
+                                        blamed_component: tr
+                                            .blamed_component
+                                            .as_ref()
+                                            .and_then(|saved| serde_json::from_str(saved).ok()),
+                                        main_span: None,
+                                    }
+                                })
+                                .collect::<Vec<crate::engine::test_result::TestResult>>()
+                        };
+                        test_results.insert(category_found.clone(), results);
+                    });
+
+                    let environments: Vec<String> = {
+                        use crate::db::schema::test_result_in_report::dsl::*;
+                        test_result_in_report
+                            .select(environment)
+                            .filter(report_id.eq(&report_from_db.id))
+                            .order(environment.asc())
+                            .distinct()
+                            .load::<Option<String>>(self.conn())
+                            .unwrap_or_else(|err| {
+                                error!("error loading environments from report: {:?}", err);
+                                vec![]
+                            })
+                            .iter()
+                            .map(|vo| match vo {
+                                Some(ref v) => v.clone(),
+                                None => "None".to_string(),
+                            })
+                            .collect()
+                    };
+
+                    crate::api::report::Report {
+                        name: report_from_db.name.clone(),
+                        group: report_from_db.folder.clone(),
+                        created_on: report_from_db.created_on,
+                        last_update: report_from_db.last_update,
+                        categories: Some(test_results),
+                        environments,
+                        summary: None,
+                    }
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "postgres")]
+impl_report_storage!(diesel::PgConnection);
+#[cfg(feature = "sqlite")]
+impl_report_storage!(diesel::SqliteConnection);
@@ -0,0 +1,174 @@
+use diesel::prelude::*;
+
+use crate::db::read::run::TestRunQuery;
+use crate::engine::run::{RunAttempt, TestRun};
+
+use crate::db::schema::test_run;
+#[derive(Debug, Insertable, Queryable, AsChangeset)]
+#[table_name = "test_run"]
+#[changeset_options(treat_none_as_null = "true")]
+struct TestRunDb {
+    id: String,
+    commit_sha: Option<String>,
+    branch: Option<String>,
+    ci_url: Option<String>,
+    start_date: chrono::NaiveDateTime,
+    end_date: chrono::NaiveDateTime,
+}
+
+fn to_micros(date: chrono::NaiveDateTime) -> i64 {
+    ((date.timestamp() * 1000) + i64::from(date.timestamp_subsec_millis())) * 1000
+}
+
+fn from_micros(micros: i64) -> chrono::NaiveDateTime {
+    chrono::NaiveDateTime::from_timestamp(
+        micros / 1000 / 1000,
+        (micros % (1000 * 1000) * 1000) as u32,
+    )
+}
+
+impl From<TestRunDb> for TestRun {
+    fn from(test_run_db: TestRunDb) -> Self {
+        TestRun::new(
+            test_run_db.id,
+            test_run_db.commit_sha,
+            test_run_db.branch,
+            test_run_db.ci_url,
+            to_micros(test_run_db.start_date),
+            to_micros(test_run_db.end_date),
+        )
+    }
+}
+
+impl From<TestRun> for TestRunDb {
+    fn from(test_run: TestRun) -> Self {
+        TestRunDb {
+            id: test_run.id,
+            commit_sha: test_run.commit,
+            branch: test_run.branch,
+            ci_url: test_run.ci_url,
+            start_date: from_micros(test_run.start),
+            end_date: from_micros(test_run.end),
+        }
+    }
+}
+
+macro_rules! impl_run_storage {
+    ($connection:ty) => {
+        impl crate::db::storage::RunStorage for super::SqlStorage<$connection> {
+            fn save_test_run(&mut self, run_to_save: TestRun) {
+                use crate::db::schema::test_run::dsl::*;
+                let run_id = run_to_save.id.clone();
+
+                let existing = test_run
+                    .filter(id.eq(&run_id))
+                    .first::<TestRunDb>(self.conn())
+                    .ok()
+                    .map(TestRun::from);
+                let saved = match existing {
+                    Some(existing) => {
+                        let test_run_db = TestRunDb::from(existing.merge(run_to_save));
+                        diesel::update(test_run.filter(id.eq(&test_run_db.id)))
+                            .set(&test_run_db)
+                            .execute(self.conn())
+                    }
+                    None => diesel::insert_into(test_run)
+                        .values(&TestRunDb::from(run_to_save.clone()))
+                        .execute(self.conn())
+                        .or_else(|_| {
+                            // run may have been created concurrently by another result
+                            let existing = test_run
+                                .filter(id.eq(&run_id))
+                                .first::<TestRunDb>(self.conn())
+                                .map(TestRun::from)?;
+                            let test_run_db = TestRunDb::from(existing.merge(run_to_save));
+                            diesel::update(test_run.filter(id.eq(&test_run_db.id)))
+                                .set(&test_run_db)
+                                .execute(self.conn())
+                        }),
+                };
+                if let Err(err) = saved {
+                    error!("error saving test run {}: {:?}", run_id, err);
+                    self.reconnect_if_needed(&err);
+                }
+            }
+
+            fn get_test_runs(&self, query: TestRunQuery) -> Vec<TestRun> {
+                use crate::db::schema::test_run::dsl::*;
+
+                let mut runs = test_run.into_boxed();
+                if let Some(query_branch) = query.branch {
+                    runs = runs.filter(branch.eq(query_branch));
+                }
+                if let Some(query_before) = query.before {
+                    runs = runs.filter(start_date.lt(from_micros(query_before)));
+                }
+
+                runs.order(start_date.desc())
+                    .limit(query.limit)
+                    .load::<TestRunDb>(self.conn())
+                    .map_err(|err| self.reconnect_if_needed(&err))
+                    .unwrap_or_else(|_| vec![])
+                    .into_iter()
+                    .map(TestRun::from)
+                    .collect()
+            }
+
+            fn get_test_run(&self, run_id: &str) -> Option<TestRun> {
+                use crate::db::schema::test_run::dsl::*;
+
+                test_run
+                    .filter(id.eq(run_id))
+                    .first::<TestRunDb>(self.conn())
+                    .map_err(|err| self.reconnect_if_needed(&err))
+                    .ok()
+                    .map(TestRun::from)
+            }
+
+            fn get_run_attempts(&self, run_ids: &[String]) -> Vec<RunAttempt> {
+                use crate::db::schema::test_result::dsl::*;
+
+                test_result
+                    .select((run_id, test_id, environment, attempt, date, status))
+                    .filter(run_id.eq_any(run_ids))
+                    .load::<(
+                        Option<String>,
+                        String,
+                        Option<String>,
+                        i32,
+                        chrono::NaiveDateTime,
+                        i32,
+                    )>(self.conn())
+                    .unwrap_or_else(|err| {
+                        error!("error loading attempts of runs: {:?}", err);
+                        self.reconnect_if_needed(&err);
+                        vec![]
+                    })
+                    .into_iter()
+                    .map(
+                        |(
+                            attempt_run_id,
+                            attempt_test_id,
+                            attempt_environment,
+                            number,
+                            attempt_date,
+                            attempt_status,
+                        )| RunAttempt {
+                            run_id: attempt_run_id.unwrap_or_default(),
+                            test_id: attempt_test_id,
+                            environment: attempt_environment,
+                            attempt: number,
+                            date: to_micros(attempt_date),
+                            status: attempt_status.into(),
+                        },
+                    )
+                    .collect()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "postgres")]
+impl_run_storage!(diesel::PgConnection);
+#[cfg(feature = "sqlite")]
+impl_run_storage!(diesel::SqliteConnection);
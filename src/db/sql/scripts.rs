@@ -0,0 +1,127 @@
+use diesel::prelude::*;
+
+use crate::engine::streams::{Script, ScriptStatus, ScriptType};
+
+use crate::db::schema::script;
+#[derive(Debug, Insertable, Queryable, Clone)]
+#[table_name = "script"]
+struct ScriptDb {
+    id: String,
+    name: String,
+    source: String,
+    script_type: i32,
+    date_added: chrono::NaiveDateTime,
+    status: i32,
+}
+
+impl From<ScriptDb> for Script {
+    fn from(script_from_db: ScriptDb) -> Self {
+        Script {
+            id: Some(script_from_db.id),
+            date_added: Some(script_from_db.date_added),
+            script_type: script_from_db.script_type.into(),
+            name: script_from_db.name,
+            source: script_from_db.source,
+            status: Some(match script_from_db.status {
+                0 => ScriptStatus::Enabled,
+                _ => ScriptStatus::Disabled,
+            }),
+        }
+    }
+}
+
+macro_rules! impl_script_storage {
+    ($connection:ty) => {
+        impl crate::db::storage::ScriptStorage for super::SqlStorage<$connection> {
+            fn save_script(&mut self, script_to_save: Script) {
+                use crate::db::schema::script::dsl::*;
+                diesel::insert_into(script)
+                    .values(&ScriptDb {
+                        id: script_to_save.id.expect("script should have an ID"),
+                        name: script_to_save.name,
+                        source: script_to_save.source,
+                        script_type: script_to_save.script_type.into(),
+                        date_added: script_to_save
+                            .date_added
+                            .expect("script should have a date_added"),
+                        status: match script_to_save.status.expect("script should have a status") {
+                            ScriptStatus::Enabled => 0,
+                            ScriptStatus::Disabled => 1,
+                        },
+                    })
+                    .execute(self.conn())
+                    .unwrap();
+            }
+
+            fn update_script(&mut self, script_to_update: Script) {
+                use crate::db::schema::script::dsl::*;
+                diesel::update(
+                    script.filter(id.eq(&script_to_update.id.expect("script should have an ID"))),
+                )
+                .set((
+                    name.eq(&script_to_update.name),
+                    source.eq(&script_to_update.source),
+                    status.eq(
+                        match script_to_update
+                            .status
+                            .expect("script should have a status")
+                        {
+                            ScriptStatus::Enabled => 0,
+                            ScriptStatus::Disabled => 1,
+                        },
+                    ),
+                ))
+                .execute(self.conn())
+                .unwrap();
+            }
+
+            fn delete_script(&mut self, script_id: &str) -> Option<Script> {
+                use crate::db::schema::script::dsl::*;
+                let script_found = script
+                    .filter(id.eq(script_id))
+                    .first::<ScriptDb>(self.conn())
+                    .ok();
+
+                diesel::delete(script.filter(id.eq(script_id)))
+                    .execute(self.conn())
+                    .ok();
+
+                script_found.map(Script::from)
+            }
+
+            fn get_scripts(&self, script_types: Option<Vec<ScriptType>>) -> Vec<Script> {
+                use crate::db::schema::script::dsl::*;
+                let mut script_query = script.into_boxed();
+                if let Some(types) = script_types {
+                    let types: Vec<i32> = types.into_iter().map(std::convert::Into::into).collect();
+                    script_query = script_query.filter(script_type.eq_any(types))
+                }
+                script_query
+                    .order(script_type.asc())
+                    .order(name.asc())
+                    .load::<ScriptDb>(self.conn())
+                    .unwrap_or_else(|err| {
+                        error!("error loading scripts: {:?}", err);
+                        vec![]
+                    })
+                    .into_iter()
+                    .map(Script::from)
+                    .collect()
+            }
+
+            fn get_script(&self, script_id: &str) -> Option<Script> {
+                use crate::db::schema::script::dsl::*;
+                script
+                    .filter(id.eq(script_id))
+                    .first::<ScriptDb>(self.conn())
+                    .ok()
+                    .map(Script::from)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "postgres")]
+impl_script_storage!(diesel::PgConnection);
+#[cfg(feature = "sqlite")]
+impl_script_storage!(diesel::SqliteConnection);
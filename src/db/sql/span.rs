@@ -0,0 +1,751 @@
+use std::collections::{HashMap, HashSet};
+
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+
+use crate::db::read::span::{AnnotationQuery, SpanQuery};
+
+use crate::db::schema::span;
+#[derive(Debug, Insertable, Queryable)]
+#[table_name = "span"]
+pub struct SpanDb {
+    pub trace_id: String,
+    pub id: String,
+    parent_id: Option<String>,
+    name: Option<String>,
+    kind: Option<String>,
+    duration: Option<i64>,
+    ts: Option<chrono::NaiveDateTime>,
+    debug: bool,
+    shared: bool,
+    local_endpoint_id: Option<String>,
+    remote_endpoint_id: Option<String>,
+}
+
+static ENDPOINT_QUERY_LIMIT: i64 = 1000;
+use crate::db::schema::endpoint;
+#[derive(Debug, Insertable, Queryable)]
+#[table_name = "endpoint"]
+pub struct EndpointDb {
+    endpoint_id: String,
+    service_name: Option<String>,
+    ipv4: Option<String>,
+    ipv6: Option<String>,
+    port: Option<i32>,
+}
+
+static TAG_QUERY_LIMIT: i64 = 100;
+use crate::db::schema::tag;
+#[derive(Debug, Insertable, Queryable)]
+#[table_name = "tag"]
+pub struct TagDb {
+    trace_id: String,
+    span_id: String,
+    shared: bool,
+    name: String,
+    value: String,
+}
+
+static ANNOTATION_QUERY_LIMIT: i64 = 100;
+use crate::db::schema::annotation;
+#[derive(Debug, Insertable, Queryable)]
+#[table_name = "annotation"]
+pub struct AnnotationDb {
+    annotation_id: String,
+    trace_id: String,
+    span_id: String,
+    shared: bool,
+    ts: chrono::NaiveDateTime,
+    value: String,
+}
+
+struct FromSpan {
+    span_db: SpanDb,
+    local_endpoint: Option<EndpointDb>,
+    remote_endpoint: Option<EndpointDb>,
+    tags: Vec<TagDb>,
+    annotations: Vec<AnnotationDb>,
+}
+
+fn get_all_from_span(span: &crate::opentracing::Span) -> FromSpan {
+    let trace_id = span.trace_id.clone();
+    let span_id = span.id.clone();
+
+    let span_db = SpanDb {
+        trace_id: trace_id.clone(),
+        id: span_id.clone(),
+        parent_id: span.parent_id.clone(),
+        name: span.name.clone().map(|s| s.to_lowercase()),
+        kind: span.kind.clone().map(|k| k.to_string()),
+        duration: span.duration,
+        ts: span.timestamp.map(|ts| {
+            // span timestamp is in microseconds
+            chrono::NaiveDateTime::from_timestamp(
+                ts / 1000 / 1000,
+                (ts % (1000 * 1000) * 1000) as u32,
+            )
+        }),
+        debug: span.debug,
+        shared: span.shared,
+        local_endpoint_id: None,
+        remote_endpoint_id: None,
+    };
+
+    let local_endpoint = if let Some(endpoint) = span.local_endpoint.clone() {
+        Some(EndpointDb {
+            endpoint_id: "n/a".to_string(),
+            service_name: endpoint.service_name.map(|s| s.to_lowercase()),
+            ipv4: endpoint.ipv4,
+            ipv6: endpoint.ipv6,
+            port: endpoint.port,
+        })
+    } else {
+        None
+    };
+
+    let remote_endpoint = if let Some(endpoint) = span.remote_endpoint.clone() {
+        Some(EndpointDb {
+            endpoint_id: "n/a".to_string(),
+            service_name: endpoint.service_name.map(|s| s.to_lowercase()),
+            ipv4: endpoint.ipv4,
+            ipv6: endpoint.ipv6,
+            port: endpoint.port,
+        })
+    } else {
+        None
+    };
+
+    let annotations = span
+        .annotations
+        .iter()
+        .map(|annotation| {
+            AnnotationDb {
+                trace_id: trace_id.clone(),
+                span_id: span_id.clone(),
+                shared: span.shared,
+                annotation_id: uuid::Uuid::new_v4().to_hyphenated().to_string(),
+                ts: chrono::NaiveDateTime::from_timestamp(
+                    // timestamp is in microseconds
+                    annotation.timestamp / 1000 / 1000,
+                    (annotation.timestamp % (1000 * 1000) * 1000) as u32,
+                ),
+                value: annotation.value.clone(),
+            }
+        })
+        .collect();
+
+    let tags = span
+        .tags
+        .iter()
+        .map(|(key, value)| TagDb {
+            trace_id: trace_id.clone(),
+            span_id: span_id.clone(),
+            shared: span.shared,
+            name: key.clone().to_lowercase(),
+            value: value.clone(),
+        })
+        .collect();
+
+    FromSpan {
+        span_db,
+        local_endpoint,
+        remote_endpoint,
+        annotations,
+        tags,
+    }
+}
+
+// keep queries below the bind parameters limit of the databases, the lowest
+// being the default of SQLite
+static MAX_BIND_PARAMETERS: usize = 999;
+static SPAN_COLUMNS: usize = 11;
+static ANNOTATION_COLUMNS: usize = 6;
+static TAG_COLUMNS: usize = 5;
+
+type EndpointKey = (Option<String>, Option<String>, Option<String>, Option<i32>);
+
+macro_rules! impl_span_storage {
+    ($connection:ty) => {
+        impl super::SqlStorage<$connection> {
+            fn find_endpoint(&self, ep: &EndpointDb) -> Option<EndpointDb> {
+                use crate::db::schema::endpoint::dsl::*;
+
+                let mut query = endpoint.into_boxed();
+                if let Some(query_service_name) = ep.service_name.clone() {
+                    query = query.filter(service_name.eq(query_service_name));
+                }
+                if let Some(query_ipv4) = ep.ipv4.clone() {
+                    query = query.filter(ipv4.eq(query_ipv4));
+                }
+                if let Some(query_ipv6) = ep.ipv6.clone() {
+                    query = query.filter(ipv6.eq(query_ipv6));
+                }
+                if let Some(query_port) = ep.port {
+                    query = query.filter(port.eq(query_port));
+                }
+
+                query.first::<EndpointDb>(self.conn()).ok()
+            }
+
+            fn upsert_endpoint(&self, ep: Option<EndpointDb>) -> Option<String> {
+                if let Some(le) = ep {
+                    use crate::db::schema::endpoint::dsl::*;
+
+                    match self.find_endpoint(&le) {
+                        Some(existing) => Some(existing.endpoint_id),
+                        None => {
+                            let new_id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+                            let could_insert = diesel::insert_into(endpoint)
+                                .values(&EndpointDb {
+                                    endpoint_id: new_id.clone(),
+                                    service_name: le.service_name.clone(),
+                                    ipv4: le.ipv4.clone(),
+                                    ipv6: le.ipv6.clone(),
+                                    port: le.port,
+                                })
+                                .execute(self.conn());
+                            if could_insert.is_err() {
+                                self.find_endpoint(&le).map(|existing| existing.endpoint_id)
+                            } else {
+                                Some(new_id)
+                            }
+                        }
+                    }
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl super::SqlStorage<$connection> {
+            fn upsert_endpoint_cached(
+                &self,
+                cache: &mut HashMap<EndpointKey, Option<String>>,
+                ep: Option<EndpointDb>,
+            ) -> Option<String> {
+                let ep = ep?;
+                let key = (
+                    ep.service_name.clone(),
+                    ep.ipv4.clone(),
+                    ep.ipv6.clone(),
+                    ep.port,
+                );
+                if let Some(endpoint_id) = cache.get(&key) {
+                    return endpoint_id.clone();
+                }
+                let endpoint_id = self.upsert_endpoint(Some(ep));
+                cache.insert(key, endpoint_id.clone());
+                endpoint_id
+            }
+
+            fn insert_spans(&self, spans: &[crate::opentracing::Span]) -> Result<(), DieselError> {
+                let mut endpoint_cache = HashMap::new();
+                let mut spans_db: Vec<SpanDb> = vec![];
+                // copies of a span in the same batch are merged as updates of its first copy
+                let mut spans_db_index: HashMap<(String, String, bool), usize> = HashMap::new();
+                let mut tags: HashMap<(String, String, bool, String), TagDb> = HashMap::new();
+                let mut annotations = vec![];
+                for span in spans {
+                    let mut to_upsert = get_all_from_span(span);
+                    to_upsert.span_db.local_endpoint_id =
+                        self.upsert_endpoint_cached(&mut endpoint_cache, to_upsert.local_endpoint);
+                    to_upsert.span_db.remote_endpoint_id =
+                        self.upsert_endpoint_cached(&mut endpoint_cache, to_upsert.remote_endpoint);
+                    let key = (
+                        to_upsert.span_db.trace_id.clone(),
+                        to_upsert.span_db.id.clone(),
+                        to_upsert.span_db.shared,
+                    );
+                    match spans_db_index.get(&key) {
+                        Some(index) => {
+                            //TODO: manage more update cases than duration
+                            spans_db[*index].duration = to_upsert.span_db.duration;
+                        }
+                        None => {
+                            spans_db_index.insert(key, spans_db.len());
+                            spans_db.push(to_upsert.span_db);
+                        }
+                    }
+                    for item in to_upsert.tags {
+                        tags.insert(
+                            (
+                                item.trace_id.clone(),
+                                item.span_id.clone(),
+                                item.shared,
+                                item.name.clone(),
+                            ),
+                            item,
+                        );
+                    }
+                    annotations.extend(to_upsert.annotations);
+                }
+
+                // the client and server sides of a shared span are kept apart
+                let existing_spans: HashSet<(String, String, bool)> = {
+                    use crate::db::schema::span::dsl::*;
+                    let mut existing = HashSet::new();
+                    for chunk in spans_db.chunks(MAX_BIND_PARAMETERS) {
+                        existing.extend(
+                            span.select((trace_id, id, shared))
+                                .filter(id.eq_any(chunk.iter().map(|item| item.id.clone())))
+                                .load::<(String, String, bool)>(self.conn())?,
+                        );
+                    }
+                    existing
+                };
+                let span_key =
+                    |item: &SpanDb| (item.trace_id.clone(), item.id.clone(), item.shared);
+                {
+                    use crate::db::schema::span::dsl::*;
+                    let mut to_insert = vec![];
+                    for item in &spans_db {
+                        if existing_spans.contains(&span_key(item)) {
+                            //TODO: manage more update cases than duration
+                            diesel::update(
+                                span.filter(
+                                    id.eq(&item.id)
+                                        .and(trace_id.eq(&item.trace_id))
+                                        .and(shared.eq(item.shared)),
+                                ),
+                            )
+                            .set(duration.eq(item.duration))
+                            .execute(self.conn())?;
+                        } else {
+                            to_insert.push(item);
+                        }
+                    }
+                    for chunk in to_insert.chunks(MAX_BIND_PARAMETERS / SPAN_COLUMNS) {
+                        diesel::insert_into(span)
+                            .values(chunk.to_vec())
+                            .execute(self.conn())?;
+                    }
+                }
+
+                {
+                    use crate::db::schema::annotation::dsl::*;
+                    for chunk in annotations.chunks(MAX_BIND_PARAMETERS / ANNOTATION_COLUMNS) {
+                        diesel::insert_into(annotation)
+                            .values(chunk)
+                            .execute(self.conn())?;
+                    }
+                }
+
+                {
+                    use crate::db::schema::tag::dsl::*;
+                    let mut existing_tags = HashSet::new();
+                    let updated_span_ids: Vec<&String> = spans_db
+                        .iter()
+                        .filter(|item| existing_spans.contains(&span_key(item)))
+                        .map(|item| &item.id)
+                        .collect();
+                    for chunk in updated_span_ids.chunks(MAX_BIND_PARAMETERS) {
+                        existing_tags.extend(
+                            tag.select((trace_id, span_id, shared, name))
+                                .filter(span_id.eq_any(chunk.iter().map(|item| (*item).clone())))
+                                .load::<(String, String, bool, String)>(self.conn())?,
+                        );
+                    }
+                    let mut to_insert = vec![];
+                    for (key, item) in &tags {
+                        if existing_tags.contains(key) {
+                            diesel::update(
+                                tag.filter(
+                                    trace_id
+                                        .eq(&item.trace_id)
+                                        .and(span_id.eq(&item.span_id))
+                                        .and(shared.eq(item.shared))
+                                        .and(name.eq(&item.name)),
+                                ),
+                            )
+                            .set(value.eq(&item.value))
+                            .execute(self.conn())?;
+                        } else {
+                            to_insert.push(item);
+                        }
+                    }
+                    for chunk in to_insert.chunks(MAX_BIND_PARAMETERS / TAG_COLUMNS) {
+                        diesel::insert_into(tag)
+                            .values(chunk.to_vec())
+                            .execute(self.conn())?;
+                    }
+                }
+
+                Ok(())
+            }
+
+            /// Spans matching the filters of the query, `None` when none of its services
+            /// are known
+            fn filter_spans(
+                &self,
+                span_query: &SpanQuery,
+            ) -> Option<
+                crate::db::schema::span::BoxedQuery<
+                    'static,
+                    <$connection as diesel::Connection>::Backend,
+                >,
+            > {
+                let find_endpoints = |query_service_name: String| -> Vec<String> {
+                    use crate::db::schema::endpoint::dsl::*;
+
+                    endpoint
+                        .select(endpoint_id)
+                        .filter(service_name.eq(query_service_name.to_lowercase()))
+                        .load::<String>(self.conn())
+                        .ok()
+                        .unwrap_or_else(|| vec![])
+                };
+                let query_endpoints = span_query.service_name.clone().map(find_endpoints);
+                let query_remote_endpoints =
+                    span_query.remote_service_name.clone().map(find_endpoints);
+                if query_endpoints.as_ref().map(Vec::is_empty).unwrap_or(false)
+                    || query_remote_endpoints
+                        .as_ref()
+                        .map(Vec::is_empty)
+                        .unwrap_or(false)
+                {
+                    // no endpoint found matching query
+                    return None;
+                }
+
+                use crate::db::schema::span::dsl::*;
+
+                let mut query = span.into_boxed();
+
+                if span_query.filter_finish {
+                    query = query.filter(duration.is_not_null());
+                }
+
+                if let Some(query_endpoints) = query_endpoints {
+                    if span_query.only_local_service {
+                        query = query.filter(local_endpoint_id.eq_any(query_endpoints));
+                    } else {
+                        query = query.filter(
+                            remote_endpoint_id
+                                .eq_any(query_endpoints.clone())
+                                .or(local_endpoint_id.eq_any(query_endpoints)),
+                        );
+                    }
+                }
+
+                if let Some(query_remote_endpoints) = query_remote_endpoints {
+                    query = query.filter(remote_endpoint_id.eq_any(query_remote_endpoints));
+                }
+
+                if let Some(query_span_name) = span_query.span_name.as_ref() {
+                    query = query.filter(name.eq(query_span_name.to_lowercase()));
+                }
+
+                for annotation_query in span_query.annotation_query.iter().cloned() {
+                    use crate::db::schema::annotation;
+                    use crate::db::schema::tag;
+
+                    query = match annotation_query {
+                        AnnotationQuery::KeyValue(key, tag_value) => query.filter(
+                            id.eq_any(
+                                tag::table
+                                    .select(tag::span_id)
+                                    .filter(tag::name.eq(key).and(tag::value.eq(tag_value))),
+                            ),
+                        ),
+                        AnnotationQuery::Key(key) => query.filter(
+                            id.eq_any(
+                                tag::table
+                                    .select(tag::span_id)
+                                    .filter(tag::name.eq(key.clone())),
+                            )
+                            .or(id.eq_any(
+                                annotation::table
+                                    .select(annotation::span_id)
+                                    .filter(annotation::value.eq(key)),
+                            )),
+                        ),
+                    };
+                }
+
+                let by_trace_id = span_query.trace_id.is_some();
+                if let Some(query_trace_id) = span_query.trace_id.clone() {
+                    query = query.filter(trace_id.eq(query_trace_id));
+                }
+
+                if let Some(query_max_duration) = span_query.max_duration {
+                    query = query.filter(duration.le(query_max_duration));
+                }
+                if let Some(query_min_duration) = span_query.min_duration {
+                    query = query.filter(duration.ge(query_min_duration));
+                }
+
+                // a trace is fetched whole, even with spans timestamped after end_ts
+                if !by_trace_id {
+                    query = query.filter(ts.le(span_query.end_ts));
+                    if let Some(query_lookback) = span_query.lookback {
+                        query = query.filter(ts.ge(span_query.end_ts - query_lookback));
+                    }
+                }
+
+                if span_query.only_endpoint {
+                    query = query.filter(remote_endpoint_id.is_not_null());
+                }
+
+                Some(query)
+            }
+        }
+
+        impl crate::db::storage::SpanStorage for super::SqlStorage<$connection> {
+            fn save_spans(
+                &mut self,
+                spans: &[crate::opentracing::Span],
+            ) -> Result<(), crate::db::storage::StorageError> {
+                self.conn()
+                    .transaction(|| self.insert_spans(spans))
+                    .map_err(|err| {
+                        error!("error saving {} span(s): {:?}", spans.len(), err);
+                        self.reconnect_if_needed(&err);
+                        crate::db::storage::StorageError(err.to_string())
+                    })
+            }
+
+            fn get_services(&self) -> Vec<String> {
+                use crate::db::schema::endpoint::dsl::*;
+
+                endpoint
+                    .limit(ENDPOINT_QUERY_LIMIT)
+                    .order(service_name.asc())
+                    .load::<EndpointDb>(self.conn())
+                    .ok()
+                    .unwrap_or_else(|| vec![])
+                    .iter()
+                    .map(|ep| ep.service_name.clone().unwrap_or_else(|| "".to_string()))
+                    .collect()
+            }
+
+            fn get_remote_services(&self, query_service_name: &str) -> Vec<String> {
+                use crate::db::schema::endpoint::dsl::*;
+                use crate::db::schema::span;
+
+                let local_endpoints = endpoint
+                    .select(endpoint_id.nullable())
+                    .filter(service_name.eq(query_service_name.to_lowercase()));
+                let remote_endpoints = span::table
+                    .select(span::remote_endpoint_id)
+                    .filter(span::local_endpoint_id.eq_any(local_endpoints))
+                    .filter(span::remote_endpoint_id.is_not_null())
+                    .distinct()
+                    .load::<Option<String>>(self.conn())
+                    .ok()
+                    .unwrap_or_else(|| vec![]);
+
+                let mut services: Vec<String> = endpoint
+                    .select(service_name)
+                    .filter(endpoint_id.eq_any(remote_endpoints.into_iter().flatten()))
+                    .filter(service_name.is_not_null())
+                    .distinct()
+                    .limit(ENDPOINT_QUERY_LIMIT)
+                    .load::<Option<String>>(self.conn())
+                    .ok()
+                    .unwrap_or_else(|| vec![])
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                services.sort_unstable();
+                services
+            }
+
+            fn get_tag_keys(&self) -> Vec<String> {
+                use crate::db::schema::tag::dsl::*;
+
+                tag.select(name)
+                    .distinct()
+                    .order(name.asc())
+                    .limit(TAG_QUERY_LIMIT)
+                    .load::<String>(self.conn())
+                    .ok()
+                    .unwrap_or_else(|| vec![])
+            }
+
+            fn get_tag_values(&self, tag_key: &str) -> Vec<String> {
+                use crate::db::schema::tag::dsl::*;
+
+                tag.select(value)
+                    .filter(name.eq(tag_key.to_lowercase()))
+                    .distinct()
+                    .order(value.asc())
+                    .limit(TAG_QUERY_LIMIT)
+                    .load::<String>(self.conn())
+                    .ok()
+                    .unwrap_or_else(|| vec![])
+            }
+
+            fn get_spans(&self, span_query: SpanQuery) -> Vec<crate::opentracing::Span> {
+                let spans: Vec<SpanDb> = match self.filter_spans(&span_query) {
+                    None => return vec![],
+                    Some(query) => {
+                        use crate::db::schema::span::dsl::*;
+
+                        query
+                            .order(ts.asc())
+                            .limit(span_query.limit)
+                            .load::<SpanDb>(self.conn())
+                            .ok()
+                            .unwrap_or_else(|| vec![])
+                    }
+                };
+
+                let without_tags = span_query.only_endpoint;
+                let without_annotations = span_query.only_endpoint;
+
+                let mut endpoint_cache = crate::db::helper::Cacher::new();
+
+                spans
+                    .iter()
+                    .map(|spandb| {
+                        let local_endpoint = spandb.local_endpoint_id.clone().and_then(|lep_id| {
+                            endpoint_cache
+                                .get(&lep_id, |id| {
+                                    use crate::db::schema::endpoint::dsl::*;
+
+                                    endpoint
+                                        .filter(endpoint_id.eq(id))
+                                        .first::<EndpointDb>(self.conn())
+                                        .ok()
+                                        .map(|ep| crate::opentracing::span::Endpoint {
+                                            service_name: ep.service_name,
+                                            ipv4: ep.ipv4,
+                                            ipv6: ep.ipv6,
+                                            port: ep.port,
+                                        })
+                                })
+                                .clone()
+                        });
+                        let remote_endpoint =
+                            spandb.remote_endpoint_id.clone().and_then(|rep_id| {
+                                endpoint_cache
+                                    .get(&rep_id, |id| {
+                                        use crate::db::schema::endpoint::dsl::*;
+
+                                        endpoint
+                                            .filter(endpoint_id.eq(id))
+                                            .first::<EndpointDb>(self.conn())
+                                            .ok()
+                                            .map(|ep| crate::opentracing::span::Endpoint {
+                                                service_name: ep.service_name,
+                                                ipv4: ep.ipv4,
+                                                ipv6: ep.ipv6,
+                                                port: ep.port,
+                                            })
+                                    })
+                                    .clone()
+                            });
+
+                        let annotations = if !without_annotations {
+                            use crate::db::schema::annotation::dsl::*;
+
+                            annotation
+                                .filter(
+                                    trace_id
+                                        .eq(&spandb.trace_id)
+                                        .and(span_id.eq(&spandb.id))
+                                        .and(shared.eq(spandb.shared)),
+                                )
+                                .limit(ANNOTATION_QUERY_LIMIT)
+                                .load::<AnnotationDb>(self.conn())
+                                .ok()
+                                .unwrap_or_else(|| vec![])
+                                .iter()
+                                .map(|an| crate::opentracing::span::Annotation {
+                                    timestamp: (an.ts.timestamp() * 1000 * 1000)
+                                        + i64::from(an.ts.timestamp_subsec_micros()),
+                                    value: an.value.clone(),
+                                    endpoint: None,
+                                })
+                                .collect()
+                        } else {
+                            vec![]
+                        };
+
+                        //TODO: way too slow
+                        let tags: HashMap<String, String> = if !without_tags {
+                            use crate::db::schema::tag::dsl::*;
+
+                            tag.filter(
+                                trace_id
+                                    .eq(&spandb.trace_id)
+                                    .and(span_id.eq(&spandb.id))
+                                    .and(shared.eq(spandb.shared)),
+                            )
+                            .limit(TAG_QUERY_LIMIT)
+                            .load::<TagDb>(self.conn())
+                            .ok()
+                            .unwrap_or_else(|| vec![])
+                            .iter()
+                            .map(|t| (t.name.clone(), t.value.clone()))
+                            .collect()
+                        } else {
+                            HashMap::new()
+                        };
+
+                        let binary_annotation_endpoint =
+                            remote_endpoint.clone().or_else(|| local_endpoint.clone());
+
+                        crate::opentracing::Span {
+                            trace_id: spandb.trace_id.clone(),
+                            id: spandb.id.clone(),
+                            parent_id: spandb.parent_id.clone(),
+                            name: spandb.name.clone().map(|s| s.chars().take(250).collect()),
+                            kind: spandb.kind.clone().map(std::convert::Into::into),
+                            timestamp: spandb.ts.map(|ts| {
+                                (ts.timestamp() * 1000 * 1000)
+                                    + i64::from(ts.timestamp_subsec_micros())
+                            }),
+                            duration: spandb.duration,
+                            debug: spandb.debug,
+                            shared: spandb.shared,
+                            local_endpoint,
+                            remote_endpoint,
+                            annotations,
+                            tags: tags.clone(),
+                            binary_annotations: tags
+                                .iter()
+                                .map(|(k, v)| crate::opentracing::span::BinaryTag {
+                                    key: k.clone(),
+                                    value: v.clone(),
+                                    endpoint: binary_annotation_endpoint.clone(),
+                                })
+                                .collect(),
+                        }
+                    })
+                    .collect()
+            }
+
+            fn get_trace_ids(&self, span_query: SpanQuery) -> Vec<String> {
+                match self.filter_spans(&span_query) {
+                    None => vec![],
+                    Some(query) => {
+                        use crate::db::schema::span::dsl::*;
+                        use diesel::sql_types::{Nullable, Timestamp};
+
+                        query
+                            .select(trace_id)
+                            .group_by(trace_id)
+                            // boxed queries can't be ordered by an aggregate expression
+                            .order((
+                                diesel::dsl::sql::<Nullable<Timestamp>>("MAX(ts)").desc(),
+                                trace_id.asc(),
+                            ))
+                            .limit(span_query.limit)
+                            .load::<String>(self.conn())
+                            .unwrap_or_else(|err| {
+                                error!("error loading trace IDs: {:?}", err);
+                                self.reconnect_if_needed(&err);
+                                vec![]
+                            })
+                    }
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "postgres")]
+impl_span_storage!(diesel::PgConnection);
+#[cfg(feature = "sqlite")]
+impl_span_storage!(diesel::SqliteConnection);
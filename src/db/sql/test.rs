@@ -0,0 +1,672 @@
+use diesel::prelude::*;
+
+use crate::db::read::test::{DurationRegressionQuery, TestItemQuery, TestResultQuery};
+use crate::db::storage::ResultCleanupStatus;
+use crate::engine::baseline::{DurationBaseline, DurationRegression};
+use crate::engine::test_result::TestStatus;
+
+static TEST_ITEM_QUERY_LIMIT: i64 = 200;
+use crate::db::schema::test_item;
+#[derive(Debug, Insertable, Queryable, Clone, Identifiable)]
+#[table_name = "test_item"]
+pub struct TestItemDb {
+    pub id: String,
+    pub parent_id: String,
+    pub name: String,
+    source: i32,
+}
+
+use crate::db::schema::test_result;
+#[derive(Debug, Insertable, Queryable, Associations, Identifiable)]
+#[belongs_to(TestItemDb, foreign_key = "test_id")]
+#[primary_key(test_id, trace_id)]
+#[table_name = "test_result"]
+pub struct TestResultDb {
+    pub test_id: String,
+    pub trace_id: String,
+    pub date: chrono::NaiveDateTime,
+    pub status: i32,
+    pub duration: i64,
+    pub environment: Option<String>,
+    pub components_called: String,
+    pub nb_spans: i32,
+    pub cleanup_status: i32,
+    pub build: Option<String>,
+    pub steps: String,
+    pub failure: Option<String>,
+    pub blamed_component: Option<String>,
+    pub run_id: Option<String>,
+    pub attempt: i32,
+}
+
+use crate::db::schema::duration_regression;
+#[derive(Debug, Insertable, Queryable)]
+#[table_name = "duration_regression"]
+struct DurationRegressionDb {
+    test_id: String,
+    trace_id: String,
+    environment: Option<String>,
+    date: chrono::NaiveDateTime,
+    duration: i64,
+    nb_results: i32,
+    median: i64,
+    p90: i64,
+    p95: i64,
+}
+
+impl From<DurationRegression> for DurationRegressionDb {
+    fn from(regression: DurationRegression) -> Self {
+        DurationRegressionDb {
+            test_id: regression.test_id,
+            trace_id: regression.trace_id,
+            environment: regression.environment,
+            date: chrono::NaiveDateTime::from_timestamp(
+                regression.date / 1000 / 1000,
+                (regression.date % (1000 * 1000) * 1000) as u32,
+            ),
+            duration: regression.duration,
+            nb_results: regression.baseline.nb_results as i32,
+            median: regression.baseline.median,
+            p90: regression.baseline.p90,
+            p95: regression.baseline.p95,
+        }
+    }
+}
+
+impl From<DurationRegressionDb> for DurationRegression {
+    fn from(regression_db: DurationRegressionDb) -> Self {
+        DurationRegression {
+            test_id: regression_db.test_id,
+            trace_id: regression_db.trace_id,
+            environment: regression_db.environment,
+            date: (((regression_db.date.timestamp() * 1000)
+                + i64::from(regression_db.date.timestamp_subsec_millis()))
+                * 1000),
+            duration: regression_db.duration,
+            ratio: regression_db.duration as f64 / regression_db.median.max(1) as f64,
+            baseline: DurationBaseline {
+                nb_results: regression_db.nb_results as usize,
+                median: regression_db.median,
+                p90: regression_db.p90,
+                p95: regression_db.p95,
+            },
+        }
+    }
+}
+
+macro_rules! impl_test_storage {
+    ($connection:ty) => {
+        impl super::SqlStorage<$connection> {
+            fn find_test_item(&self, test_item_db: &TestItemDb) -> Option<TestItemDb> {
+                use crate::db::schema::test_item::dsl::*;
+
+                test_item
+                    .filter(name.eq(&test_item_db.name))
+                    .filter(source.eq(test_item_db.source))
+                    .filter(parent_id.eq(&test_item_db.parent_id))
+                    .first::<TestItemDb>(self.conn())
+                    .ok()
+            }
+
+            fn find_test_or_insert(&self, test_item_db: &TestItemDb) -> String {
+                use crate::db::schema::test_item::dsl::*;
+
+                match self.find_test_item(test_item_db) {
+                    Some(existing) => existing.id,
+                    None => {
+                        let new_id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+                        let could_insert = diesel::insert_into(test_item)
+                            .values(&TestItemDb {
+                                id: new_id.clone(),
+                                ..(*test_item_db).clone()
+                            })
+                            .execute(self.conn());
+                        if could_insert.is_err() {
+                            self.find_test_item(test_item_db)
+                                .map(|existing| existing.id)
+                                .expect("should have found an test ID after insertion failed")
+                        } else {
+                            new_id
+                        }
+                    }
+                }
+            }
+
+            /// trace ID, attempt and status of the other attempts of the test in the same run
+            /// and environment
+            fn other_attempts(
+                &self,
+                attempt_test_id: &str,
+                attempt_of: &crate::engine::test_result::TestResult,
+            ) -> Vec<(String, i32, i32)> {
+                use crate::db::schema::test_result::dsl::*;
+
+                let attempt_run_id = match attempt_of.run_id.as_ref() {
+                    Some(attempt_run_id) => attempt_run_id,
+                    None => return vec![],
+                };
+                let mut query = test_result
+                    .select((trace_id, attempt, status))
+                    .filter(test_id.eq(attempt_test_id))
+                    .filter(run_id.eq(attempt_run_id))
+                    .filter(trace_id.ne(&attempt_of.trace_id))
+                    .into_boxed();
+                if let Some(attempt_environment) = attempt_of.environment.as_ref() {
+                    query = query.filter(environment.eq(attempt_environment));
+                } else {
+                    query = query.filter(environment.is_null());
+                }
+                query
+                    .load::<(String, i32, i32)>(self.conn())
+                    .unwrap_or_else(|err| {
+                        error!("error loading attempts: {:?}", err);
+                        self.reconnect_if_needed(&err);
+                        vec![]
+                    })
+            }
+        }
+
+        impl crate::db::storage::TestStorage for super::SqlStorage<$connection> {
+            fn save_test_result(
+                &mut self,
+                mut test_result_to_save: crate::engine::test_result::TestResult,
+            ) -> crate::engine::test_result::TestResult {
+                let mut parent_id = "root".to_string();
+                for item in test_result_to_save.path.clone() {
+                    parent_id = self.find_test_or_insert(&TestItemDb {
+                        id: "n/a".to_string(),
+                        parent_id,
+                        name: item,
+                        source: 0,
+                    });
+                }
+
+                parent_id = self.find_test_or_insert(&TestItemDb {
+                    id: "n/a".to_string(),
+                    parent_id,
+                    name: test_result_to_save.name.clone(),
+                    source: 0,
+                });
+
+                let test_result_date = chrono::NaiveDateTime::from_timestamp(
+                    test_result_to_save.date / 1000 / 1000,
+                    (test_result_to_save.date % (1000 * 1000) * 1000) as u32,
+                );
+
+                let other_attempts = self.other_attempts(&parent_id, &test_result_to_save);
+                let earlier_attempt_failed =
+                    other_attempts
+                        .iter()
+                        .any(|(_, other_attempt, other_status)| {
+                            *other_attempt < test_result_to_save.attempt
+                                && *other_status == TestStatus::Failure.as_i32()
+                        });
+                test_result_to_save.status = test_result_to_save
+                    .status
+                    .of_attempt(earlier_attempt_failed);
+
+                use crate::db::schema::test_result::dsl::*;
+                if test_result_to_save.status == TestStatus::Failure {
+                    // attempts received before this one that succeeded after it
+                    let later_successes: Vec<&String> = other_attempts
+                        .iter()
+                        .filter(|(_, other_attempt, other_status)| {
+                            *other_attempt > test_result_to_save.attempt
+                                && *other_status == TestStatus::Success.as_i32()
+                        })
+                        .map(|(other_trace_id, _, _)| other_trace_id)
+                        .collect();
+                    if !later_successes.is_empty() {
+                        diesel::update(
+                            test_result
+                                .filter(test_id.eq(&parent_id))
+                                .filter(trace_id.eq_any(later_successes)),
+                        )
+                        .set(status.eq(TestStatus::PassedOnRetry.as_i32()))
+                        .execute(self.conn())
+                        .map_err(|err| self.reconnect_if_needed(&err))
+                        .ok();
+                    }
+                }
+                let test_result_db = TestResultDb {
+                    test_id: parent_id.clone(),
+                    trace_id: test_result_to_save.trace_id.clone(),
+                    date: test_result_date,
+                    status: test_result_to_save.status.as_i32(),
+                    duration: test_result_to_save.duration,
+                    environment: test_result_to_save.environment.clone(),
+                    components_called: serde_json::to_string(
+                        &test_result_to_save.components_called,
+                    )
+                    .unwrap(),
+                    nb_spans: test_result_to_save.nb_spans,
+                    cleanup_status: match test_result_to_save.status {
+                        crate::engine::test_result::TestStatus::Success => {
+                            ResultCleanupStatus::ToKeep.into()
+                        }
+                        _ => ResultCleanupStatus::WithData.into(),
+                    },
+                    build: test_result_to_save.build.clone(),
+                    steps: serde_json::to_string(&test_result_to_save.steps).unwrap(),
+                    failure: test_result_to_save
+                        .failure
+                        .as_ref()
+                        .map(|failure_to_save| serde_json::to_string(failure_to_save).unwrap()),
+                    blamed_component: test_result_to_save
+                        .blamed_component
+                        .as_ref()
+                        .map(|component| serde_json::to_string(component).unwrap()),
+                    run_id: test_result_to_save.run_id.clone(),
+                    attempt: test_result_to_save.attempt,
+                };
+                let existing = test_result
+                    .filter(test_id.eq(&test_result_db.test_id))
+                    .filter(trace_id.eq(&test_result_db.trace_id))
+                    .count()
+                    .get_result::<i64>(self.conn())
+                    .map(|count| count > 0)
+                    .unwrap_or(false);
+                if existing {
+                    // trace received more spans since it was last parsed
+                    diesel::update(
+                        test_result
+                            .filter(test_id.eq(&test_result_db.test_id))
+                            .filter(trace_id.eq(&test_result_db.trace_id)),
+                    )
+                    .set((
+                        date.eq(test_result_db.date),
+                        status.eq(test_result_db.status),
+                        duration.eq(test_result_db.duration),
+                        environment.eq(&test_result_db.environment),
+                        components_called.eq(&test_result_db.components_called),
+                        nb_spans.eq(test_result_db.nb_spans),
+                        build.eq(&test_result_db.build),
+                        steps.eq(&test_result_db.steps),
+                        failure.eq(&test_result_db.failure),
+                        blamed_component.eq(&test_result_db.blamed_component),
+                        run_id.eq(&test_result_db.run_id),
+                        attempt.eq(test_result_db.attempt),
+                    ))
+                    .execute(self.conn())
+                    .map_err(|err| self.reconnect_if_needed(&err))
+                    .ok();
+                } else {
+                    diesel::insert_into(test_result)
+                        .values(&test_result_db)
+                        .execute(self.conn())
+                        .map_err(|err| self.reconnect_if_needed(&err))
+                        .ok();
+                }
+
+                diesel::update(
+                    test_result
+                        .filter(cleanup_status.eq(ResultCleanupStatus::ToKeep.as_i32()))
+                        .filter(test_id.eq(parent_id.clone()))
+                        .filter(date.lt(test_result_date)),
+                )
+                .set(cleanup_status.eq(ResultCleanupStatus::WithData.as_i32()))
+                .execute(self.conn())
+                .ok();
+
+                crate::engine::test_result::TestResult {
+                    test_id: parent_id,
+                    ..test_result_to_save
+                }
+            }
+
+            fn get_test_items(
+                &self,
+                test_item_query: TestItemQuery,
+            ) -> Vec<crate::api::test::TestDetails> {
+                use crate::db::schema::test_item::dsl::*;
+
+                let mut query = test_item.into_boxed();
+
+                if let Some(filter_parent_id) = test_item_query.parent_id.clone() {
+                    query = query.filter(parent_id.eq(filter_parent_id));
+                }
+
+                if let Some(filter_id) = test_item_query.id.clone() {
+                    query = query.filter(id.eq(filter_id));
+                }
+
+                let mut test_item_cache = crate::db::helper::Cacher::new();
+
+                query
+                    .order(name.asc())
+                    .load::<TestItemDb>(self.conn())
+                    .unwrap_or_else(|err| {
+                        error!("error loading test items: {:?}", err);
+                        vec![]
+                    })
+                    .iter()
+                    .map(|ti| {
+                        let mut test_item_to_get = match ti.parent_id.as_ref() {
+                            "root" => None,
+                            item_id => Some(item_id.to_string()),
+                        };
+                        let mut path = vec![];
+                        if test_item_query.with_full_path {
+                            while let Some(test_item_got) = test_item_to_get {
+                                if let Some(test) = test_item_cache
+                                    .get(&test_item_got, |ti_id| {
+                                        use crate::db::schema::test_item::dsl::*;
+                                        test_item
+                                            .filter(id.eq(ti_id))
+                                            .first::<TestItemDb>(self.conn())
+                                            .ok()
+                                    })
+                                    .clone()
+                                {
+                                    test_item_to_get = match test.parent_id.as_ref() {
+                                        "root" => None,
+                                        item_id => Some(item_id.to_string()),
+                                    };
+                                    path.push(crate::api::test::TestItem {
+                                        id: test.id.clone(),
+                                        name: test.name.clone(),
+                                    });
+                                } else {
+                                    test_item_to_get = None;
+                                }
+                            }
+                            path.reverse();
+                        }
+
+                        let children = if test_item_query.with_children {
+                            use crate::db::schema::test_item::dsl::*;
+                            test_item
+                                .filter(parent_id.eq(&ti.id))
+                                .order(name.asc())
+                                .limit(TEST_ITEM_QUERY_LIMIT)
+                                .load::<TestItemDb>(self.conn())
+                                .ok()
+                                .unwrap_or_else(|| vec![])
+                                .iter()
+                                .map(|ti| crate::api::test::TestItem {
+                                    name: ti.name.clone(),
+                                    id: ti.id.clone(),
+                                })
+                                .collect()
+                        } else {
+                            vec![]
+                        };
+
+                        let traces: Vec<crate::engine::test_result::TestResult> = if test_item_query
+                            .with_traces
+                        {
+                            use crate::db::schema::test_result::dsl::*;
+
+                            let query = TestResultDb::belonging_to(ti)
+                                .order(date.desc())
+                                .limit(crate::engine::flaky::FLAKY_WINDOW as i64);
+                            query
+                                .load::<TestResultDb>(self.conn())
+                                .ok()
+                                .unwrap_or_else(|| vec![])
+                                .iter()
+                                .map(|tr| crate::engine::test_result::TestResult {
+                                    test_id: tr.test_id.clone(),
+                                    path: path.iter().map(|ti| ti.name.clone()).collect(),
+                                    name: ti.name.clone(),
+                                    date: (((tr.date.timestamp() * 1000)
+                                        + i64::from(tr.date.timestamp_subsec_millis()))
+                                        * 1000),
+                                    duration: tr.duration,
+                                    environment: tr.environment.clone(),
+                                    build: tr.build.clone(),
+                                    run_id: tr.run_id.clone(),
+                                    attempt: tr.attempt,
+                                    status: tr.status.into(),
+                                    trace_id: tr.trace_id.clone(),
+                                    components_called: serde_json::from_str(&tr.components_called)
+                                        .unwrap(),
+                                    nb_spans: tr.nb_spans,
+                                    steps: serde_json::from_str(&tr.steps).unwrap_or_default(),
+                                    failure: tr
+                                        .failure
+                                        .as_ref()
+                                        .and_then(|saved| serde_json::from_str(saved).ok()),
+                                    blamed_component: tr
+                                        .blamed_component
+                                        .as_ref()
+                                        .and_then(|saved| serde_json::from_str(saved).ok()),
+                                    main_span: None,
+                                })
+                                .collect()
+                        } else {
+                            vec![]
+                        };
+
+                        let flaky = crate::engine::flaky::score(&traces);
+                        crate::api::test::TestDetails {
+                            children,
+                            last_results: traces.into_iter().take(5).collect(),
+                            flaky,
+                            name: ti.name.clone(),
+                            path,
+                            test_id: ti.id.clone(),
+                        }
+                    })
+                    .collect()
+            }
+
+            fn get_test_results(
+                &self,
+                test_result_query: TestResultQuery,
+            ) -> Vec<crate::engine::test_result::TestResult> {
+                use crate::db::schema::test_result::dsl::*;
+
+                let mut query = test_result.into_boxed();
+
+                if let Some(query_trace_id) = test_result_query.trace_id {
+                    query = query.filter(trace_id.eq(query_trace_id));
+                }
+
+                if let Some(query_status) = test_result_query.status {
+                    query = query.filter(status.eq(query_status));
+                }
+
+                if let Some(query_test_id) = test_result_query.test_id {
+                    query = query.filter(test_id.eq(query_test_id));
+                }
+
+                if let Some(query_environment) = test_result_query.environment {
+                    query = query.filter(environment.eq(query_environment));
+                }
+
+                if let Some(query_run_id) = test_result_query.run_id {
+                    query = query.filter(run_id.eq(query_run_id));
+                }
+
+                if let Some(query_max_duration) = test_result_query.max_duration {
+                    query = query.filter(duration.le(query_max_duration));
+                }
+                if let Some(query_min_duration) = test_result_query.min_duration {
+                    query = query.filter(duration.ge(query_min_duration));
+                }
+
+                query = query.filter(date.le(test_result_query.ts));
+                if let Some(query_lookback) = test_result_query.lookback {
+                    query = query.filter(date.ge(test_result_query.ts - query_lookback));
+                }
+
+                let test_results: Vec<TestResultDb> = query
+                    .order(date.desc())
+                    .limit(test_result_query.limit)
+                    .load(self.conn())
+                    .unwrap_or_else(|err| {
+                        error!("error loading test results: {:?}", err);
+                        self.reconnect_if_needed(&err);
+                        vec![]
+                    });
+
+                let mut test_item_cache = crate::db::helper::Cacher::new_with({
+                    //prefetch first level test items in one query
+                    use crate::db::schema::test_item::dsl::*;
+
+                    let mut query = test_item.into_boxed();
+                    let test_ids: std::collections::HashSet<&String> =
+                        test_results.iter().map(|tr| &tr.test_id).collect();
+                    for item_id in test_ids {
+                        query = query.or_filter(id.eq(item_id));
+                    }
+                    query
+                        .load::<TestItemDb>(self.conn())
+                        .ok()
+                        .unwrap_or_else(|| vec![])
+                        .iter()
+                        .map(|item| (item.id.clone(), Some(item.clone())))
+                        .collect()
+                });
+
+                test_results
+                    .iter()
+                    .map(|tr| {
+                        let test = test_item_cache
+                            .get(&tr.test_id, |ti_id| {
+                                use crate::db::schema::test_item::dsl::*;
+
+                                test_item
+                                    .filter(id.eq(ti_id))
+                                    .first::<TestItemDb>(self.conn())
+                                    .ok()
+                            })
+                            .clone();
+
+                        let mut test_item_to_get =
+                            test.clone().and_then(|t| match t.parent_id.as_ref() {
+                                "root" => None,
+                                item_id => Some(item_id.to_string()),
+                            });
+                        let mut path = vec![];
+                        while let Some(test_item_got) = test_item_to_get {
+                            if let Some(test) = test_item_cache
+                                .get(&test_item_got, |ti_id| {
+                                    use crate::db::schema::test_item::dsl::*;
+                                    test_item
+                                        .filter(id.eq(ti_id))
+                                        .first::<TestItemDb>(self.conn())
+                                        .ok()
+                                })
+                                .clone()
+                            {
+                                test_item_to_get = match test.parent_id.as_ref() {
+                                    "root" => None,
+                                    item_id => Some(item_id.to_string()),
+                                };
+                                path.push(test.name);
+                            } else {
+                                test_item_to_get = None;
+                            }
+                        }
+                        path.reverse();
+
+                        crate::engine::test_result::TestResult {
+                            test_id: tr.test_id.clone(),
+                            path,
+                            name: test
+                                .map(|t| t.name)
+                                .unwrap_or_else(|| "missing name".to_string()),
+                            date: (((tr.date.timestamp() * 1000)
+                                + i64::from(tr.date.timestamp_subsec_millis()))
+                                * 1000),
+                            duration: tr.duration,
+                            environment: tr.environment.clone(),
+                            build: tr.build.clone(),
+                            run_id: tr.run_id.clone(),
+                            attempt: tr.attempt,
+                            status: tr.status.into(),
+                            trace_id: tr.trace_id.clone(),
+                            components_called: serde_json::from_str(&tr.components_called).unwrap(),
+                            nb_spans: tr.nb_spans,
+                            steps: serde_json::from_str(&tr.steps).unwrap_or_default(),
+                            failure: tr
+                                .failure
+                                .as_ref()
+                                .and_then(|saved| serde_json::from_str(saved).ok()),
+                            blamed_component: tr
+                                .blamed_component
+                                .as_ref()
+                                .and_then(|saved| serde_json::from_str(saved).ok()),
+                            main_span: None,
+                        }
+                    })
+                    .collect::<Vec<crate::engine::test_result::TestResult>>()
+            }
+
+            fn get_environments(&self) -> Vec<String> {
+                use crate::db::schema::test_result::dsl::*;
+
+                test_result
+                    .select(environment)
+                    .filter(environment.is_not_null())
+                    .distinct()
+                    .load::<Option<String>>(self.conn())
+                    .unwrap_or_else(|err| {
+                        error!("error loading environment from test results: {:?}", err);
+                        vec![]
+                    })
+                    .iter()
+                    .filter_map(std::clone::Clone::clone)
+                    .collect()
+            }
+
+            fn save_duration_regression(&mut self, regression: DurationRegression) {
+                use crate::db::schema::duration_regression::dsl::*;
+
+                let regression_db = DurationRegressionDb::from(regression);
+                let saved = self.conn().transaction(|| {
+                    diesel::delete(
+                        duration_regression
+                            .filter(test_id.eq(&regression_db.test_id))
+                            .filter(trace_id.eq(&regression_db.trace_id)),
+                    )
+                    .execute(self.conn())?;
+                    diesel::insert_into(duration_regression)
+                        .values(&regression_db)
+                        .execute(self.conn())
+                });
+                if let Err(err) = saved {
+                    error!("error saving duration regression: {:?}", err);
+                    self.reconnect_if_needed(&err);
+                }
+            }
+
+            fn get_duration_regressions(
+                &self,
+                regression_query: DurationRegressionQuery,
+            ) -> Vec<DurationRegression> {
+                use crate::db::schema::duration_regression::dsl::*;
+
+                let mut query = duration_regression.into_boxed();
+
+                if let Some(query_test_id) = regression_query.test_id {
+                    query = query.filter(test_id.eq(query_test_id));
+                }
+
+                if let Some(query_environment) = regression_query.environment {
+                    query = query.filter(environment.eq(query_environment));
+                }
+
+                query
+                    .order(date.desc())
+                    .limit(regression_query.limit)
+                    .load::<DurationRegressionDb>(self.conn())
+                    .unwrap_or_else(|err| {
+                        error!("error loading duration regressions: {:?}", err);
+                        self.reconnect_if_needed(&err);
+                        vec![]
+                    })
+                    .into_iter()
+                    .map(DurationRegression::from)
+                    .collect()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "postgres")]
+impl_test_storage!(diesel::PgConnection);
+#[cfg(feature = "sqlite")]
+impl_test_storage!(diesel::SqliteConnection);
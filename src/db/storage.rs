@@ -0,0 +1,121 @@
+use crate::db::read::run::TestRunQuery;
+use crate::db::read::span::SpanQuery;
+use crate::db::read::test::{DurationRegressionQuery, TestItemQuery, TestResultQuery};
+use crate::engine::baseline::DurationRegression;
+use crate::engine::ingestor::Ingest;
+use crate::engine::report::ResultForReport;
+use crate::engine::run::{RunAttempt, TestRun};
+use crate::engine::streams::{Script, ScriptType};
+use crate::engine::test_result::TestResult;
+use crate::opentracing::Span;
+
+#[derive(Fail, Debug)]
+#[fail(display = "storage error: {}", _0)]
+pub struct StorageError(pub String);
+
+pub trait SpanStorage {
+    /// Save spans all at once, updating the ones already known
+    fn save_spans(&mut self, spans: &[Span]) -> Result<(), StorageError>;
+    fn get_services(&self) -> Vec<String>;
+    fn get_remote_services(&self, service_name: &str) -> Vec<String>;
+    fn get_tag_keys(&self) -> Vec<String>;
+    fn get_tag_values(&self, tag_key: &str) -> Vec<String>;
+    fn get_spans(&self, query: SpanQuery) -> Vec<Span>;
+    /// Traces with spans matching the query, from the latest, the limit applying to traces
+    fn get_trace_ids(&self, query: SpanQuery) -> Vec<String>;
+}
+
+pub trait TestStorage {
+    /// Save a test result, creating its test items, and return it with its test ID
+    fn save_test_result(&mut self, test_result: TestResult) -> TestResult;
+    fn get_test_items(&self, query: TestItemQuery) -> Vec<crate::api::test::TestDetails>;
+    fn get_test_results(&self, query: TestResultQuery) -> Vec<TestResult>;
+    fn get_environments(&self) -> Vec<String>;
+    /// Save a duration regression, replacing the one already known for the same result
+    fn save_duration_regression(&mut self, regression: DurationRegression);
+    fn get_duration_regressions(&self, query: DurationRegressionQuery) -> Vec<DurationRegression>;
+}
+
+pub trait RunStorage {
+    /// Save a run, extending the one already known with the same ID
+    fn save_test_run(&mut self, test_run: TestRun);
+    /// Runs, from the latest started
+    fn get_test_runs(&self, query: TestRunQuery) -> Vec<TestRun>;
+    fn get_test_run(&self, run_id: &str) -> Option<TestRun>;
+    /// Attempts of tests in any of these runs
+    fn get_run_attempts(&self, run_ids: &[String]) -> Vec<RunAttempt>;
+}
+
+pub trait ReportStorage {
+    fn save_result_for_report(&mut self, result_for_report: ResultForReport);
+    fn get_reports(&self) -> Vec<crate::api::report::Report>;
+    fn get_report(
+        &self,
+        report_group: &str,
+        report_name: &str,
+        environment: Option<&str>,
+    ) -> Option<crate::api::report::Report>;
+}
+
+pub trait ScriptStorage {
+    fn save_script(&mut self, script: Script);
+    fn update_script(&mut self, script: Script);
+    fn delete_script(&mut self, script_id: &str) -> Option<Script>;
+    fn get_scripts(&self, script_types: Option<Vec<ScriptType>>) -> Vec<Script>;
+    fn get_script(&self, script_id: &str) -> Option<Script>;
+}
+
+pub trait IngestStorage {
+    fn save_ingest(&mut self, ingest: Ingest);
+    fn get_ingest(&self, ingest_id: &str) -> Option<Ingest>;
+    /// Mark ingests still processing as interrupted, their progress was kept in memory
+    fn interrupt_unfinished_ingests(
+        &mut self,
+        at: chrono::NaiveDateTime,
+    ) -> Result<usize, StorageError>;
+}
+
+/// Where spans, test items, test results, runs, reports and scripts are kept
+pub trait Storage:
+    SpanStorage + TestStorage + RunStorage + ReportStorage + ScriptStorage + IngestStorage
+{
+    /// Remove data older than the delays from the cleanup configuration
+    fn clean_up(&mut self);
+    /// A storage no longer connected will be replaced by a new one
+    fn is_connected(&self) -> bool;
+    /// Bring the storage schema up to date, failing if it is newer than this version
+    fn migrate(&mut self) -> Result<(), StorageError>;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ResultCleanupStatus {
+    WithData,
+    Important,
+    ToKeep,
+    Shell,
+}
+impl From<i32> for ResultCleanupStatus {
+    fn from(v: i32) -> Self {
+        match v {
+            1 => ResultCleanupStatus::Important,
+            2 => ResultCleanupStatus::Shell,
+            3 => ResultCleanupStatus::ToKeep,
+            _ => ResultCleanupStatus::WithData,
+        }
+    }
+}
+impl ResultCleanupStatus {
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            ResultCleanupStatus::WithData => 0,
+            ResultCleanupStatus::Important => 1,
+            ResultCleanupStatus::Shell => 2,
+            ResultCleanupStatus::ToKeep => 3,
+        }
+    }
+}
+impl From<ResultCleanupStatus> for i32 {
+    fn from(status: ResultCleanupStatus) -> Self {
+        status.as_i32()
+    }
+}
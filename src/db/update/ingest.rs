@@ -0,0 +1,13 @@
+use actix::prelude::*;
+
+#[derive(Message)]
+pub struct SaveIngest(pub crate::engine::ingestor::Ingest);
+
+impl Handler<SaveIngest> for super::DbExecutor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SaveIngest, ctx: &mut Self::Context) -> Self::Result {
+        self.0.save_ingest(msg.0);
+        self.check_db_connection(ctx);
+    }
+}
@@ -0,0 +1,13 @@
+use actix::prelude::*;
+
+#[derive(Message)]
+pub struct SaveTestRun(pub crate::engine::run::TestRun);
+
+impl Handler<SaveTestRun> for super::DbExecutor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SaveTestRun, ctx: &mut Self::Context) -> Self::Result {
+        self.0.save_test_run(msg.0);
+        self.check_db_connection(ctx);
+    }
+}
@@ -0,0 +1,201 @@
+use actix::prelude::*;
+use futures::{future, Future};
+
+#[cfg(feature = "python")]
+use cpython::{PyDict, Python, ToPyObject};
+
+use crate::engine::test_result::{TestResult, TestStatus};
+
+/// Number of latest successful results of a test in an environment used as its baseline
+pub static BASELINE_WINDOW: i64 = 50;
+/// Minimum number of results before a baseline is trusted
+static BASELINE_MIN_RESULTS: usize = 5;
+/// A result slower than this many times the median is a regression
+static REGRESSION_FACTOR: f64 = 2.0;
+/// Ignore regressions smaller than this, in microseconds, to not report noise on fast tests
+static REGRESSION_MIN_DELTA: i64 = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DurationBaseline {
+    pub nb_results: usize,
+    pub median: i64,
+    pub p90: i64,
+    pub p95: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DurationRegression {
+    pub test_id: String,
+    pub trace_id: String,
+    pub environment: Option<String>,
+    pub date: i64,
+    pub duration: i64,
+    pub baseline: DurationBaseline,
+    pub ratio: f64,
+}
+
+#[cfg(feature = "python")]
+impl ToPyObject for DurationRegression {
+    type ObjectType = PyDict;
+    fn to_py_object(&self, py: Python) -> Self::ObjectType {
+        let object = PyDict::new(py);
+        object
+            .set_item(py, "test_id", self.test_id.clone())
+            .unwrap();
+        object
+            .set_item(py, "trace_id", self.trace_id.clone())
+            .unwrap();
+        if let Some(environment) = self.environment.clone() {
+            object.set_item(py, "environment", environment).unwrap();
+        }
+        object.set_item(py, "date", self.date).unwrap();
+        object.set_item(py, "duration", self.duration).unwrap();
+        object.set_item(py, "median", self.baseline.median).unwrap();
+        object.set_item(py, "p90", self.baseline.p90).unwrap();
+        object.set_item(py, "p95", self.baseline.p95).unwrap();
+        object.set_item(py, "ratio", self.ratio).unwrap();
+        object
+    }
+}
+
+// nearest-rank percentile of sorted durations
+fn percentile(durations: &[i64], percent: usize) -> i64 {
+    let rank = (percent * durations.len()).div_ceil(100);
+    durations[rank.max(1) - 1]
+}
+
+/// Baseline of the successful results in `history`, if there are enough of them
+pub fn baseline<'a, I>(history: I) -> Option<DurationBaseline>
+where
+    I: IntoIterator<Item = &'a TestResult>,
+{
+    let mut durations: Vec<i64> = history
+        .into_iter()
+        .filter(|tr| tr.status == TestStatus::Success)
+        .map(|tr| tr.duration)
+        .collect();
+    if durations.len() < BASELINE_MIN_RESULTS {
+        return None;
+    }
+    durations.sort();
+    Some(DurationBaseline {
+        nb_results: durations.len(),
+        median: percentile(&durations, 50),
+        p90: percentile(&durations, 90),
+        p95: percentile(&durations, 95),
+    })
+}
+
+/// Compare a successful result with the baseline built from the other results of its test
+/// and environment
+pub fn check(test_result: &TestResult, history: &[TestResult]) -> Option<DurationRegression> {
+    if test_result.status != TestStatus::Success {
+        return None;
+    }
+    let baseline = baseline(history.iter().filter(|tr| {
+        tr.trace_id != test_result.trace_id && tr.environment == test_result.environment
+    }))?;
+    let ratio = test_result.duration as f64 / baseline.median.max(1) as f64;
+    if ratio >= REGRESSION_FACTOR
+        && test_result.duration > baseline.p95
+        && test_result.duration - baseline.median >= REGRESSION_MIN_DELTA
+    {
+        Some(DurationRegression {
+            test_id: test_result.test_id.clone(),
+            trace_id: test_result.trace_id.clone(),
+            environment: test_result.environment.clone(),
+            date: test_result.date,
+            duration: test_result.duration,
+            baseline,
+            ratio,
+        })
+    } else {
+        None
+    }
+}
+
+#[derive(Message, Debug)]
+pub struct CheckDuration(pub TestResult);
+
+impl Handler<CheckDuration> for super::test_result::TraceParser {
+    type Result = ();
+
+    fn handle(&mut self, msg: CheckDuration, _ctx: &mut Context<Self>) -> Self::Result {
+        let test_result = msg.0;
+        Arbiter::spawn(
+            crate::DB_READ_EXECUTOR_POOL
+                .send(crate::db::read::test::GetTestResults(
+                    crate::db::read::test::TestResultQuery {
+                        test_id: Some(test_result.test_id.clone()),
+                        environment: test_result.environment.clone(),
+                        status: Some(TestStatus::Success.as_i32()),
+                        limit: BASELINE_WINDOW,
+                        ..Default::default()
+                    },
+                ))
+                .then(move |history| {
+                    if let Ok(history) = history {
+                        if let Some(regression) = check(&test_result, &history) {
+                            info!(
+                                "test {} took {} times its median duration in trace {}",
+                                regression.test_id, regression.ratio, regression.trace_id
+                            );
+                            crate::DB_EXECUTOR_POOL.do_send(
+                                crate::db::update::test::SaveDurationRegression(regression.clone()),
+                            );
+                            actix::System::current()
+                                .registry()
+                                .get::<crate::engine::streams::Streamer>()
+                                .do_send(crate::engine::streams::Regression(regression));
+                        }
+                    }
+                    future::result(Ok(()))
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::test_result::TestResult;
+
+    fn result(trace_id: &str, duration: i64) -> TestResult {
+        TestResult {
+            trace_id: trace_id.to_string(),
+            duration,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn can_compute_baseline() {
+        let history: Vec<TestResult> = (1..=10).map(|i| result(&i.to_string(), i * 1000)).collect();
+        assert_eq!(
+            super::baseline(&history),
+            Some(super::DurationBaseline {
+                nb_results: 10,
+                median: 5000,
+                p90: 9000,
+                p95: 10000,
+            })
+        );
+        assert_eq!(super::baseline(&history[..4]), None);
+    }
+
+    #[test]
+    fn can_detect_duration_regression() {
+        let history: Vec<TestResult> = (1..=10)
+            .map(|i| result(&i.to_string(), 10_000 + i))
+            .collect();
+
+        assert!(super::check(&result("new", 11_000), &history).is_none());
+        let regression = super::check(&result("new", 35_000), &history).unwrap();
+        assert!(regression.ratio > 3.0);
+        assert_eq!(regression.baseline.nb_results, 10);
+
+        // the result itself is not part of its baseline
+        let mut with_itself = history.clone();
+        with_itself.push(result("new", 35_000));
+        assert!(super::check(&result("new", 35_000), &with_itself).is_some());
+    }
+}
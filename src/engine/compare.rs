@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use crate::engine::test_result::{TestResult, TestStatus};
+
+/// A duration changing by at least this factor, either way, is significant
+static DURATION_CHANGE_FACTOR: f64 = 1.5;
+/// Ignore duration changes smaller than this, in microseconds, to not report noise on fast tests
+static DURATION_CHANGE_MIN_DELTA: i64 = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComparedSide {
+    Run(String),
+    Environment(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DurationChange {
+    pub test_id: String,
+    pub path: Vec<String>,
+    pub name: String,
+    pub base_duration: i64,
+    pub duration: i64,
+    pub ratio: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CategoryComparison {
+    /// results failing in the target that did not fail in the base
+    pub newly_failing: Vec<TestResult>,
+    /// results passing in the target that failed in the base
+    pub newly_passing: Vec<TestResult>,
+    /// results of tests only in the target
+    pub appeared: Vec<TestResult>,
+    /// results of tests only in the base
+    pub disappeared: Vec<TestResult>,
+    pub duration_changes: Vec<DurationChange>,
+}
+
+impl CategoryComparison {
+    fn is_empty(&self) -> bool {
+        self.newly_failing.is_empty()
+            && self.newly_passing.is_empty()
+            && self.appeared.is_empty()
+            && self.disappeared.is_empty()
+            && self.duration_changes.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Comparison {
+    pub base: ComparedSide,
+    pub target: ComparedSide,
+    /// changes by report category, categories without changes are omitted
+    pub categories: HashMap<String, CategoryComparison>,
+}
+
+fn is_passing(status: &TestStatus) -> bool {
+    *status == TestStatus::Success || *status == TestStatus::PassedOnRetry
+}
+
+/// Latest result of each test
+fn latest_results(results: &[TestResult]) -> HashMap<&String, &TestResult> {
+    let mut latest: HashMap<&String, &TestResult> = HashMap::new();
+    for tr in results {
+        let latest_result = latest.entry(&tr.test_id).or_insert(tr);
+        if tr.date > latest_result.date {
+            *latest_result = tr;
+        }
+    }
+    latest
+}
+
+/// If going from `base` to `duration`, in microseconds, is worth reporting
+pub fn is_significant_duration_change(base: i64, duration: i64) -> bool {
+    if base <= 0 {
+        return false;
+    }
+    let ratio = duration as f64 / base as f64;
+    (duration - base).abs() >= DURATION_CHANGE_MIN_DELTA
+        && (ratio >= DURATION_CHANGE_FACTOR || ratio <= 1.0 / DURATION_CHANGE_FACTOR)
+}
+
+fn duration_change(base: &TestResult, target: &TestResult) -> Option<DurationChange> {
+    if is_passing(&base.status)
+        && is_passing(&target.status)
+        && is_significant_duration_change(base.duration, target.duration)
+    {
+        Some(DurationChange {
+            test_id: target.test_id.clone(),
+            path: target.path.clone(),
+            name: target.name.clone(),
+            base_duration: base.duration,
+            duration: target.duration,
+            ratio: target.duration as f64 / base.duration as f64,
+        })
+    } else {
+        None
+    }
+}
+
+/// Compare the latest result of each test between `base` and `target`. A test is
+/// counted in every category returned for it by `categories`.
+pub fn compare<F>(
+    base: ComparedSide,
+    base_results: &[TestResult],
+    target: ComparedSide,
+    target_results: &[TestResult],
+    categories: F,
+) -> Comparison
+where
+    F: Fn(&TestResult) -> Vec<String>,
+{
+    let base_latest = latest_results(base_results);
+    let target_latest = latest_results(target_results);
+
+    let mut comparisons: HashMap<String, CategoryComparison> = HashMap::new();
+    let mut add_to_categories = |tr: &TestResult, add: &dyn Fn(&mut CategoryComparison)| {
+        for category in categories(tr) {
+            add(comparisons.entry(category).or_default());
+        }
+    };
+
+    for (test_id, target_tr) in &target_latest {
+        match base_latest.get(test_id) {
+            None => add_to_categories(target_tr, &|comparison| {
+                comparison.appeared.push((*target_tr).clone())
+            }),
+            Some(base_tr) => {
+                if target_tr.status == TestStatus::Failure && base_tr.status != TestStatus::Failure
+                {
+                    add_to_categories(target_tr, &|comparison| {
+                        comparison.newly_failing.push((*target_tr).clone())
+                    });
+                } else if base_tr.status == TestStatus::Failure && is_passing(&target_tr.status) {
+                    add_to_categories(target_tr, &|comparison| {
+                        comparison.newly_passing.push((*target_tr).clone())
+                    });
+                }
+                if let Some(change) = duration_change(base_tr, target_tr) {
+                    add_to_categories(target_tr, &|comparison| {
+                        comparison.duration_changes.push(change.clone())
+                    });
+                }
+            }
+        }
+    }
+    for (test_id, base_tr) in &base_latest {
+        if !target_latest.contains_key(test_id) {
+            add_to_categories(base_tr, &|comparison| {
+                comparison.disappeared.push((*base_tr).clone())
+            });
+        }
+    }
+
+    comparisons.retain(|_, comparison| !comparison.is_empty());
+    for comparison in comparisons.values_mut() {
+        let by_path =
+            |a: &TestResult, b: &TestResult| a.path.cmp(&b.path).then_with(|| a.name.cmp(&b.name));
+        comparison.newly_failing.sort_by(by_path);
+        comparison.newly_passing.sort_by(by_path);
+        comparison.appeared.sort_by(by_path);
+        comparison.disappeared.sort_by(by_path);
+        comparison.duration_changes.sort_by(|a, b| {
+            b.ratio
+                .partial_cmp(&a.ratio)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    Comparison {
+        base,
+        target,
+        categories: comparisons,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ComparedSide;
+    use crate::engine::test_result::{TestResult, TestStatus};
+
+    fn result(test_id: &str, suite: &str, status: TestStatus, duration: i64) -> TestResult {
+        TestResult {
+            test_id: test_id.to_string(),
+            path: vec![suite.to_string()],
+            name: test_id.to_string(),
+            trace_id: test_id.to_string(),
+            status,
+            duration,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn can_compare_results_by_category() {
+        let base = vec![
+            result("a", "suite1", TestStatus::Success, 10_000),
+            result("b", "suite1", TestStatus::Failure, 10_000),
+            result("c", "suite2", TestStatus::Success, 10_000),
+            result("d", "suite2", TestStatus::Success, 10_000),
+            result("e", "suite2", TestStatus::Success, 100),
+        ];
+        let target = vec![
+            result("a", "suite1", TestStatus::Failure, 10_000),
+            result("b", "suite1", TestStatus::Success, 10_000),
+            result("c", "suite2", TestStatus::Success, 30_000),
+            result("e", "suite2", TestStatus::Success, 300),
+            result("f", "suite2", TestStatus::Skipped, 0),
+        ];
+
+        let comparison = super::compare(
+            ComparedSide::Run("base".to_string()),
+            &base,
+            ComparedSide::Run("target".to_string()),
+            &target,
+            |tr| tr.path.clone(),
+        );
+
+        let suite1 = &comparison.categories["suite1"];
+        assert_eq!(suite1.newly_failing.len(), 1);
+        assert_eq!(suite1.newly_failing[0].test_id, "a");
+        assert_eq!(suite1.newly_passing.len(), 1);
+        assert_eq!(suite1.newly_passing[0].test_id, "b");
+        assert!(suite1.duration_changes.is_empty());
+
+        let suite2 = &comparison.categories["suite2"];
+        assert_eq!(suite2.appeared.len(), 1);
+        assert_eq!(suite2.appeared[0].test_id, "f");
+        assert_eq!(suite2.disappeared.len(), 1);
+        assert_eq!(suite2.disappeared[0].test_id, "d");
+        assert_eq!(suite2.duration_changes.len(), 1);
+        assert_eq!(suite2.duration_changes[0].test_id, "c");
+        assert!((suite2.duration_changes[0].ratio - 3.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,532 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "python")]
+use cpython::{PyDict, Python, ToPyObject};
+
+use crate::engine::test_result::{TestResult, TestStatus};
+use crate::opentracing::span::Kind;
+use crate::opentracing::tags::OpenTracingTag;
+use crate::opentracing::Span;
+
+/// Signatures longer than this are truncated
+static SIGNATURE_MAX_LENGTH: usize = 200;
+
+// annotations added by Zipkin instrumentations, they are not messages
+static CORE_ANNOTATIONS: [&str; 6] = ["cs", "cr", "sr", "ss", "ws", "wr"];
+
+// OpenTracing log fields, then OpenTelemetry exception attributes
+static MESSAGE_FIELDS: [&str; 3] = ["error.message", "message", "exception.message"];
+static KIND_FIELDS: [&str; 2] = ["error.kind", "exception.type"];
+static STACK_FIELDS: [&str; 2] = ["stack", "exception.stacktrace"];
+static ERROR_EVENTS: [&str; 2] = ["error", "exception"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TestFailure {
+    pub message: Option<String>,
+    pub kind: Option<String>,
+    pub stack: Option<String>,
+    /// kind and message of the failure, without the numbers, ids and paths that change
+    /// from one run to the other
+    pub signature: String,
+}
+
+#[cfg(feature = "python")]
+impl ToPyObject for TestFailure {
+    type ObjectType = PyDict;
+    fn to_py_object(&self, py: Python) -> Self::ObjectType {
+        let object = PyDict::new(py);
+        if let Some(message) = self.message.clone() {
+            object.set_item(py, "message", message).unwrap();
+        }
+        if let Some(kind) = self.kind.clone() {
+            object.set_item(py, "kind", kind).unwrap();
+        }
+        if let Some(stack) = self.stack.clone() {
+            object.set_item(py, "stack", stack).unwrap();
+        }
+        object
+            .set_item(py, "signature", self.signature.clone())
+            .unwrap();
+        object
+    }
+}
+
+#[derive(Default)]
+struct ErrorFields {
+    message: Option<String>,
+    kind: Option<String>,
+    stack: Option<String>,
+}
+
+impl ErrorFields {
+    fn first_of(fields: &HashMap<String, String>, keys: &[&str]) -> Option<String> {
+        keys.iter()
+            .filter_map(|key| fields.get(*key))
+            .find(|value| !value.trim().is_empty())
+            .cloned()
+    }
+
+    fn merge(&mut self, fields: &HashMap<String, String>) {
+        if self.message.is_none() {
+            self.message = Self::first_of(fields, &MESSAGE_FIELDS);
+        }
+        if self.kind.is_none() {
+            self.kind = Self::first_of(fields, &KIND_FIELDS);
+        }
+        if self.stack.is_none() {
+            self.stack = Self::first_of(fields, &STACK_FIELDS);
+        }
+    }
+
+    // from the tags of the span, then from its error logs
+    fn from_span(span: &Span) -> Self {
+        let mut error_fields = ErrorFields::default();
+        error_fields.merge(&span.tags);
+        for annotation in &span.annotations {
+            match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(
+                &annotation.value,
+            ) {
+                Ok(fields) => {
+                    let fields: HashMap<String, String> = fields
+                        .into_iter()
+                        .map(|(key, value)| match value {
+                            serde_json::Value::String(value) => (key, value),
+                            value => (key, value.to_string()),
+                        })
+                        .collect();
+                    if fields
+                        .get("event")
+                        .map(|event| ERROR_EVENTS.contains(&event.as_str()))
+                        .unwrap_or(true)
+                    {
+                        error_fields.merge(&fields);
+                    }
+                }
+                Err(_) => {
+                    if error_fields.message.is_none()
+                        && !ERROR_EVENTS.contains(&annotation.value.as_str())
+                        && !CORE_ANNOTATIONS.contains(&annotation.value.as_str())
+                        && !annotation.value.trim().is_empty()
+                    {
+                        error_fields.message = Some(annotation.value.clone());
+                    }
+                }
+            }
+        }
+        error_fields
+    }
+}
+
+fn is_failed(span: &Span) -> bool {
+    let error: &str = OpenTracingTag::Error.into();
+    span.tags.get(error).map(|v| v.to_lowercase()) == Some("true".to_string())
+        || span.tags.contains_key("error.kind")
+        || span.tags.contains_key("error.message")
+}
+
+fn is_server_error(span: &Span) -> bool {
+    let status_code: &str = OpenTracingTag::HttpStatusCode.into();
+    span.tags
+        .get(status_code)
+        .and_then(|status_code| status_code.parse::<u16>().ok())
+        .map(|status_code| (500..600).contains(&status_code))
+        .unwrap_or(false)
+}
+
+fn depth(span: &Span, parents: &HashMap<&str, &str>) -> usize {
+    let mut depth = 0;
+    let mut current = span.parent_id.as_deref();
+    while let Some(parent_id) = current {
+        depth += 1;
+        if depth > parents.len() {
+            // loop in the parents of the trace
+            break;
+        }
+        current = parents.get(parent_id).cloned();
+    }
+    depth
+}
+
+// spans matching `filter`, deepest first then in the order they started
+fn deepest_first<F>(spans: &[Span], filter: F) -> Vec<&Span>
+where
+    F: Fn(&Span) -> bool,
+{
+    let parents: HashMap<&str, &str> = spans
+        .iter()
+        .filter_map(|span| {
+            span.parent_id
+                .as_ref()
+                .map(|parent_id| (span.id.as_str(), parent_id.as_str()))
+        })
+        .collect();
+    let mut matching: Vec<(usize, &Span)> = spans
+        .iter()
+        .filter(|span| filter(span))
+        .map(|span| (depth(span, &parents), span))
+        .collect();
+    matching.sort_by(|(depth_a, a), (depth_b, b)| {
+        depth_b
+            .cmp(depth_a)
+            .then_with(|| a.timestamp.cmp(&b.timestamp))
+    });
+    matching.into_iter().map(|(_, span)| span).collect()
+}
+
+impl TestFailure {
+    /// Failure of a test from the deepest failed span of its trace with an error message
+    /// or kind, as it is the closest to the root cause
+    pub fn from_spans(spans: &[Span]) -> Option<Self> {
+        deepest_first(spans, |span| span.parent_id.is_none() || is_failed(span))
+            .into_iter()
+            .map(ErrorFields::from_span)
+            .find(|error_fields| error_fields.message.is_some() || error_fields.kind.is_some())
+            .map(|error_fields| TestFailure {
+                signature: signature(
+                    error_fields.kind.as_deref(),
+                    error_fields.message.as_deref(),
+                ),
+                message: error_fields.message,
+                kind: error_fields.kind,
+                stack: error_fields.stack,
+            })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BlamedComponent {
+    pub service: Option<String>,
+    pub span_name: Option<String>,
+    pub span_id: String,
+}
+
+#[cfg(feature = "python")]
+impl ToPyObject for BlamedComponent {
+    type ObjectType = PyDict;
+    fn to_py_object(&self, py: Python) -> Self::ObjectType {
+        let object = PyDict::new(py);
+        if let Some(service) = self.service.clone() {
+            object.set_item(py, "service", service).unwrap();
+        }
+        if let Some(span_name) = self.span_name.clone() {
+            object.set_item(py, "span_name", span_name).unwrap();
+        }
+        object
+            .set_item(py, "span_id", self.span_id.clone())
+            .unwrap();
+        object
+    }
+}
+
+impl BlamedComponent {
+    /// Component of the deepest span of a trace that failed with an `error` tag or an HTTP
+    /// 5xx status code. A client span blames the service it called.
+    pub fn from_spans(spans: &[Span]) -> Option<Self> {
+        deepest_first(spans, |span| {
+            span.parent_id.is_some() && (is_failed(span) || is_server_error(span))
+        })
+        .first()
+        .map(|span| {
+            let remote = span
+                .remote_endpoint
+                .as_ref()
+                .and_then(|endpoint| endpoint.service_name.clone());
+            let local = span
+                .local_endpoint
+                .as_ref()
+                .and_then(|endpoint| endpoint.service_name.clone());
+            BlamedComponent {
+                service: match span.kind {
+                    Some(Kind::CLIENT) | Some(Kind::PRODUCER) => remote.or(local),
+                    _ => local.or(remote),
+                },
+                span_name: span.name.clone(),
+                span_id: span.id.clone(),
+            }
+        })
+    }
+}
+
+static TRIMMED_PUNCTUATION: &[char] = &[
+    '"', '\'', '`', '(', ')', '[', ']', '{', '}', '<', '>', ',', ';', ':', '.', '!', '?',
+];
+
+fn is_id(word: &str) -> bool {
+    let hex = word.trim_start_matches("0x");
+    (word.starts_with("0x") && !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        || (word.len() >= 8
+            && word.chars().any(|c| c.is_ascii_digit())
+            && word.chars().all(|c| c.is_ascii_hexdigit() || c == '-'))
+}
+
+fn normalize_word(word: &str) -> String {
+    let core = word.trim_matches(TRIMMED_PUNCTUATION);
+    if core.is_empty() {
+        return word.to_string();
+    }
+    let start = word.len() - word.trim_start_matches(TRIMMED_PUNCTUATION).len();
+    let (prefix, suffix) = (&word[..start], &word[start + core.len()..]);
+    let normalized = if core.contains('/') || core.contains('\\') {
+        "<path>".to_string()
+    } else if is_id(core) {
+        "<id>".to_string()
+    } else {
+        let mut normalized = String::new();
+        let mut in_number = false;
+        for c in core.chars() {
+            if c.is_ascii_digit() {
+                if !in_number {
+                    normalized.push_str("<n>");
+                }
+                in_number = true;
+            } else {
+                normalized.push(c);
+                in_number = false;
+            }
+        }
+        normalized
+    };
+    format!("{}{}{}", prefix, normalized, suffix)
+}
+
+/// First line of a message without the numbers, ids and paths it contains
+pub fn normalize(message: &str) -> String {
+    message
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("")
+        .split_whitespace()
+        .map(normalize_word)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub fn signature(kind: Option<&str>, message: Option<&str>) -> String {
+    let signature = match (kind, message.map(normalize)) {
+        (Some(kind), Some(ref message)) if !message.is_empty() => {
+            format!("{}: {}", kind, message)
+        }
+        (Some(kind), _) => kind.to_string(),
+        (None, Some(message)) => message,
+        (None, None) => String::new(),
+    };
+    signature.chars().take(SIGNATURE_MAX_LENGTH).collect()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AffectedTest {
+    pub test_id: String,
+    pub path: Vec<String>,
+    pub name: String,
+    pub nb_failures: usize,
+    pub last_trace_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FailureCluster {
+    pub signature: String,
+    pub kind: Option<String>,
+    /// message of the latest failure
+    pub message: Option<String>,
+    pub nb_failures: usize,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub tests: Vec<AffectedTest>,
+}
+
+/// Group failed results by signature, clusters affecting the most tests first
+pub fn cluster(results: &[TestResult]) -> Vec<FailureCluster> {
+    let mut by_signature: HashMap<&String, Vec<(&TestResult, &TestFailure)>> = HashMap::new();
+    for tr in results.iter().filter(|tr| tr.status == TestStatus::Failure) {
+        if let Some(failure) = tr.failure.as_ref() {
+            by_signature
+                .entry(&failure.signature)
+                .or_default()
+                .push((tr, failure));
+        }
+    }
+
+    let mut clusters: Vec<FailureCluster> = by_signature
+        .into_iter()
+        .map(|(signature, mut failures)| {
+            failures.sort_by_key(|(tr, _)| tr.date);
+            let (first, _) = failures[0];
+            let (last, last_failure) = failures[failures.len() - 1];
+
+            let mut tests: HashMap<&String, AffectedTest> = HashMap::new();
+            for (tr, _) in &failures {
+                let test = tests.entry(&tr.test_id).or_insert_with(|| AffectedTest {
+                    test_id: tr.test_id.clone(),
+                    path: tr.path.clone(),
+                    name: tr.name.clone(),
+                    nb_failures: 0,
+                    last_trace_id: tr.trace_id.clone(),
+                });
+                test.nb_failures += 1;
+                test.last_trace_id = tr.trace_id.clone();
+            }
+            let mut tests: Vec<AffectedTest> = tests.into_values().collect();
+            tests.sort_by(|a, b| {
+                b.nb_failures
+                    .cmp(&a.nb_failures)
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+
+            FailureCluster {
+                signature: signature.clone(),
+                kind: last_failure.kind.clone(),
+                message: last_failure.message.clone(),
+                nb_failures: failures.len(),
+                first_seen: first.date,
+                last_seen: last.date,
+                tests,
+            }
+        })
+        .collect();
+    clusters.sort_by(|a, b| {
+        b.tests
+            .len()
+            .cmp(&a.tests.len())
+            .then_with(|| b.nb_failures.cmp(&a.nb_failures))
+            .then_with(|| b.last_seen.cmp(&a.last_seen))
+    });
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::test_result::{TestResult, TestStatus};
+    use crate::opentracing::span::{Kind, SpanBuilder};
+
+    #[test]
+    fn can_normalize_message() {
+        assert_eq!(
+            super::normalize("Connection refused to 10.0.0.12:5432 (after 3012ms)\n  at db.rs"),
+            "Connection refused to <n>.<n>.<n>.<n>:<n> (after <n>ms)"
+        );
+        assert_eq!(
+            super::normalize(
+                "user 'c0a8f2e1-3b4d-4e5f-8a9b-0c1d2e3f4a5b' not found in /var/lib/db.json"
+            ),
+            "user '<id>' not found in <path>"
+        );
+        assert_eq!(
+            super::signature(Some("Timeout"), Some("after 30s")),
+            super::signature(Some("Timeout"), Some("after 5s"))
+        );
+        assert_eq!(super::signature(Some("Timeout"), None), "Timeout");
+    }
+
+    #[test]
+    fn can_get_failure_from_deepest_failed_span() {
+        let spans = vec![
+            SpanBuilder::new("test")
+                .tag("error", "true")
+                .annotation(70, "test failed")
+                .build(),
+            SpanBuilder::new("step")
+                .child_of("test")
+                .tag("error", "true")
+                .build(),
+            SpanBuilder::new("call")
+                .child_of("step")
+                .tag("error", "true")
+                .tag("error.kind", "HttpError")
+                .annotation(
+                    60,
+                    "{\"event\": \"error\", \"message\": \"503 from http://payment/api\", \"stack\": \"at call\"}",
+                )
+                .build(),
+        ];
+
+        let failure = super::TestFailure::from_spans(&spans).unwrap();
+        assert_eq!(failure.kind, Some("HttpError".to_string()));
+        assert_eq!(
+            failure.message,
+            Some("503 from http://payment/api".to_string())
+        );
+        assert_eq!(failure.stack, Some("at call".to_string()));
+        assert_eq!(failure.signature, "HttpError: <n> from <path>");
+
+        let failure = super::TestFailure::from_spans(&spans[..2]).unwrap();
+        assert_eq!(failure.signature, "test failed");
+
+        assert!(super::TestFailure::from_spans(&[SpanBuilder::new("test").build()]).is_none());
+    }
+
+    #[test]
+    fn can_blame_component_of_deepest_failed_span() {
+        let spans = vec![
+            SpanBuilder::new("test").tag("error", "true").build(),
+            SpanBuilder::new("checkout")
+                .child_of("test")
+                .kind(Kind::SERVER)
+                .service("shop")
+                .tag("http.status_code", "500")
+                .build(),
+            SpanBuilder::new("pay")
+                .child_of("checkout")
+                .kind(Kind::CLIENT)
+                .service("shop")
+                .remote_service("payment")
+                .tag("http.status_code", "503")
+                .build(),
+            SpanBuilder::new("cache")
+                .child_of("checkout")
+                .tag("http.status_code", "404")
+                .build(),
+        ];
+
+        assert_eq!(
+            super::BlamedComponent::from_spans(&spans),
+            Some(super::BlamedComponent {
+                service: Some("payment".to_string()),
+                span_name: Some("pay".to_string()),
+                span_id: "pay".to_string(),
+            })
+        );
+        assert_eq!(
+            super::BlamedComponent::from_spans(&spans[..2])
+                .and_then(|blamed_component| blamed_component.service),
+            Some("shop".to_string())
+        );
+        assert!(super::BlamedComponent::from_spans(&spans[..1]).is_none());
+    }
+
+    #[test]
+    fn can_cluster_failures_across_tests() {
+        let result = |test_id: &str, date: i64, message: &str| TestResult {
+            test_id: test_id.to_string(),
+            name: test_id.to_string(),
+            trace_id: format!("{}-{}", test_id, date),
+            date,
+            status: TestStatus::Failure,
+            failure: Some(super::TestFailure {
+                message: Some(message.to_string()),
+                kind: None,
+                stack: None,
+                signature: super::signature(None, Some(message)),
+            }),
+            ..Default::default()
+        };
+        let results = vec![
+            result("a", 3, "connection refused on port 5432"),
+            result("b", 1, "connection refused on port 5433"),
+            result("a", 2, "connection refused on port 5432"),
+            result("c", 4, "expected 3 items"),
+        ];
+
+        let clusters = super::cluster(&results);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].signature, "connection refused on port <n>");
+        assert_eq!(clusters[0].nb_failures, 3);
+        assert_eq!(clusters[0].first_seen, 1);
+        assert_eq!(clusters[0].last_seen, 3);
+        assert_eq!(clusters[0].tests.len(), 2);
+        assert_eq!(clusters[0].tests[0].test_id, "a");
+        assert_eq!(clusters[0].tests[0].nb_failures, 2);
+        assert_eq!(clusters[0].tests[0].last_trace_id, "a-3");
+        assert_eq!(clusters[1].tests.len(), 1);
+    }
+}
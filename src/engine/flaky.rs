@@ -0,0 +1,223 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::engine::test_result::{TestResult, TestStatus};
+
+/// Number of latest results of a test in an environment used to compute its flakiness
+pub static FLAKY_WINDOW: usize = 20;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlakyTest {
+    pub test_id: String,
+    pub path: Vec<String>,
+    pub name: String,
+    pub environment: Option<String>,
+    pub nb_results: usize,
+    pub nb_flips: usize,
+    pub flip_rate: f64,
+    /// runs in which the test failed before succeeding on a retry
+    pub nb_passed_on_retry: usize,
+    /// builds in which the test both passed and failed
+    pub builds: Vec<String>,
+    pub score: f64,
+}
+
+impl FlakyTest {
+    /// A single flip is a test that broke or was fixed, not a flaky one
+    pub fn is_flaky(&self) -> bool {
+        self.nb_flips > 1 || !self.builds.is_empty() || self.nb_passed_on_retry > 0
+    }
+}
+
+// results must be sorted from the oldest, skipped results are ignored and passing on a
+// retry is a success
+fn flakiness(results: &[&TestResult]) -> FlakyTest {
+    let statuses: Vec<&TestStatus> = results
+        .iter()
+        .map(|tr| match tr.status {
+            TestStatus::PassedOnRetry => &TestStatus::Success,
+            ref status => status,
+        })
+        .filter(|status| **status == TestStatus::Success || **status == TestStatus::Failure)
+        .collect();
+    let nb_flips = statuses
+        .windows(2)
+        .filter(|pair| pair[0] != pair[1])
+        .count();
+    let flip_rate = if statuses.len() > 1 {
+        nb_flips as f64 / (statuses.len() - 1) as f64
+    } else {
+        0.0
+    };
+    let nb_passed_on_retry = results
+        .iter()
+        .filter(|tr| tr.status == TestStatus::PassedOnRetry)
+        .count();
+
+    let mut statuses_by_build: HashMap<&String, BTreeSet<i32>> = HashMap::new();
+    for tr in results {
+        if let Some(build) = tr.build.as_ref() {
+            statuses_by_build
+                .entry(build)
+                .or_default()
+                .insert(tr.status.as_i32());
+        }
+    }
+    let mut builds: Vec<String> = statuses_by_build
+        .into_iter()
+        .filter(|(_, statuses)| {
+            statuses.contains(&TestStatus::Success.as_i32())
+                && statuses.contains(&TestStatus::Failure.as_i32())
+        })
+        .map(|(build, _)| build.clone())
+        .collect();
+    builds.sort();
+
+    let last = results[results.len() - 1];
+    let mut flaky_test = FlakyTest {
+        test_id: last.test_id.clone(),
+        path: last.path.clone(),
+        name: last.name.clone(),
+        environment: last.environment.clone(),
+        nb_results: results.len(),
+        nb_flips,
+        flip_rate,
+        nb_passed_on_retry,
+        builds,
+        score: 0.0,
+    };
+    if flaky_test.is_flaky() {
+        flaky_test.score = flip_rate.max(nb_passed_on_retry as f64 / results.len() as f64);
+    }
+    flaky_test
+}
+
+// retried attempts are replaced by the last attempt of their run
+fn without_retried_attempts(results: Vec<&TestResult>) -> Vec<&TestResult> {
+    let mut last_attempts: HashMap<&String, i32> = HashMap::new();
+    for tr in &results {
+        if let Some(run_id) = tr.run_id.as_ref() {
+            let last_attempt = last_attempts.entry(run_id).or_insert(tr.attempt);
+            *last_attempt = (*last_attempt).max(tr.attempt);
+        }
+    }
+    results
+        .into_iter()
+        .filter(|tr| {
+            tr.run_id
+                .as_ref()
+                .and_then(|run_id| last_attempts.get(run_id))
+                .map(|last_attempt| tr.attempt >= *last_attempt)
+                .unwrap_or(true)
+        })
+        .collect()
+}
+
+/// Flakiness of each test and environment over their `window` latest results
+pub fn compute(results: &[TestResult], window: usize) -> Vec<FlakyTest> {
+    let mut by_test: HashMap<(&String, &Option<String>), Vec<&TestResult>> = HashMap::new();
+    for tr in results {
+        by_test
+            .entry((&tr.test_id, &tr.environment))
+            .or_default()
+            .push(tr);
+    }
+
+    let mut flaky_tests: Vec<FlakyTest> = by_test
+        .into_values()
+        .map(|results| {
+            let mut results = without_retried_attempts(results);
+            results.sort_by_key(|tr| tr.date);
+            let start = results.len().saturating_sub(window);
+            flakiness(&results[start..])
+        })
+        .collect();
+    flaky_tests.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    flaky_tests
+}
+
+/// Highest flakiness score of a test among its environments
+pub fn score(results: &[TestResult]) -> f64 {
+    compute(results, FLAKY_WINDOW)
+        .iter()
+        .map(|flaky_test| flaky_test.score)
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::test_result::{TestResult, TestStatus};
+
+    fn result(date: i64, status: TestStatus, build: &str) -> TestResult {
+        attempt(date, status, build, 1)
+    }
+
+    fn attempt(date: i64, status: TestStatus, build: &str, attempt: i32) -> TestResult {
+        TestResult {
+            trace_id: format!("trace-{}", date),
+            date,
+            status,
+            build: Some(build.to_string()),
+            run_id: Some(build.to_string()),
+            attempt,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn broken_test_is_not_flaky() {
+        let results = vec![
+            result(1, TestStatus::Success, "1"),
+            result(2, TestStatus::Success, "2"),
+            result(3, TestStatus::Failure, "3"),
+            result(4, TestStatus::Failure, "4"),
+        ];
+        let flaky_tests = super::compute(&results, super::FLAKY_WINDOW);
+        assert_eq!(flaky_tests.len(), 1);
+        assert_eq!(flaky_tests[0].nb_flips, 1);
+        assert!(!flaky_tests[0].is_flaky());
+        assert_eq!(super::score(&results), 0.0);
+    }
+
+    #[test]
+    fn can_detect_flaky_test() {
+        let results = vec![
+            result(4, TestStatus::Failure, "2"),
+            result(1, TestStatus::Success, "1"),
+            result(2, TestStatus::Skipped, "1"),
+            result(3, TestStatus::Success, "2"),
+            result(5, TestStatus::Success, "3"),
+        ];
+        let flaky_tests = super::compute(&results, super::FLAKY_WINDOW);
+        assert_eq!(flaky_tests[0].nb_results, 5);
+        assert_eq!(flaky_tests[0].nb_flips, 2);
+        assert_eq!(flaky_tests[0].builds, vec!["2".to_string()]);
+        assert!(flaky_tests[0].is_flaky());
+        assert!((flaky_tests[0].score - 2.0 / 3.0).abs() < f64::EPSILON);
+
+        let flaky_tests = super::compute(&results, 2);
+        assert_eq!(flaky_tests[0].nb_results, 2);
+        assert!(flaky_tests[0].builds.is_empty());
+    }
+
+    #[test]
+    fn retried_test_is_flaky() {
+        let results = vec![
+            result(1, TestStatus::Success, "1"),
+            attempt(2, TestStatus::Failure, "2", 1),
+            attempt(3, TestStatus::PassedOnRetry, "2", 2),
+            result(4, TestStatus::Success, "3"),
+        ];
+        let flaky_tests = super::compute(&results, super::FLAKY_WINDOW);
+        assert_eq!(flaky_tests[0].nb_results, 3);
+        assert_eq!(flaky_tests[0].nb_flips, 0);
+        assert_eq!(flaky_tests[0].nb_passed_on_retry, 1);
+        assert!(flaky_tests[0].builds.is_empty());
+        assert!(flaky_tests[0].is_flaky());
+        assert!((flaky_tests[0].score - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+}
@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
 use actix::registry::SystemService;
 use actix::*;
 use chrono;
 
 #[derive(Default)]
-pub struct Ingestor;
+pub struct Ingestor {
+    in_progress: HashMap<String, IngestProgress>,
+}
 
 impl Actor for Ingestor {
     type Context = Context<Self>;
@@ -36,3 +40,121 @@ impl<T> IngestEvents<T> {
 impl<T> Message for IngestEvents<T> {
     type Result = ();
 }
+
+/// Processing status of an ingest batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ingest {
+    pub ingest_id: super::IngestId,
+    pub created_at: chrono::NaiveDateTime,
+    pub processed_at: Option<chrono::NaiveDateTime>,
+    pub nb_received: i32,
+    pub nb_stored: i32,
+    pub nb_failed: i32,
+    pub nb_test_results: i32,
+    /// the server stopped before processing was done, events and test results can be missing
+    pub interrupted: bool,
+}
+
+#[derive(Debug)]
+struct IngestProgress {
+    ingest: Ingest,
+    pending_events: usize,
+    pending_traces: usize,
+}
+
+impl Ingestor {
+    pub(super) fn start_ingest<T>(&mut self, msg: &IngestEvents<T>) {
+        let ingest = Ingest {
+            ingest_id: msg.ingest_id.clone(),
+            created_at: msg.created_at,
+            processed_at: None,
+            nb_received: msg.events.len() as i32,
+            nb_stored: 0,
+            nb_failed: 0,
+            nb_test_results: 0,
+            interrupted: false,
+        };
+        crate::DB_EXECUTOR_POOL.do_send(crate::db::update::ingest::SaveIngest(ingest.clone()));
+        self.in_progress.insert(
+            msg.ingest_id.0.clone(),
+            IngestProgress {
+                ingest,
+                pending_events: msg.events.len(),
+                pending_traces: 0,
+            },
+        );
+        self.finish_if_done(&msg.ingest_id);
+    }
+
+    fn finish_if_done(&mut self, ingest_id: &super::IngestId) {
+        let done = self
+            .in_progress
+            .get(&ingest_id.0)
+            .map(|progress| progress.pending_events == 0 && progress.pending_traces == 0)
+            .unwrap_or(false);
+        if done {
+            if let Some(progress) = self.in_progress.remove(&ingest_id.0) {
+                debug!("finished processing {}", ingest_id);
+                crate::DB_EXECUTOR_POOL.do_send(crate::db::update::ingest::SaveIngest(Ingest {
+                    processed_at: Some(chrono::Utc::now().naive_utc()),
+                    ..progress.ingest
+                }));
+            }
+        }
+    }
+}
+
+/// Events of an ingest batch went through the DB, updating some traces
+#[derive(Message)]
+pub struct EventsProcessed {
+    pub ingest_id: super::IngestId,
+    pub nb_stored: usize,
+    pub nb_failed: usize,
+    pub trace_ids: Vec<String>,
+}
+impl Handler<EventsProcessed> for Ingestor {
+    type Result = ();
+
+    fn handle(&mut self, msg: EventsProcessed, _ctx: &mut Context<Self>) -> Self::Result {
+        if let Some(progress) = self.in_progress.get_mut(&msg.ingest_id.0) {
+            progress.pending_events -= msg.nb_stored + msg.nb_failed;
+            progress.ingest.nb_stored += msg.nb_stored as i32;
+            progress.ingest.nb_failed += msg.nb_failed as i32;
+            progress.pending_traces += msg.trace_ids.len();
+            // persist the counts so they can be followed, and survive a restart
+            crate::DB_EXECUTOR_POOL.do_send(crate::db::update::ingest::SaveIngest(
+                progress.ingest.clone(),
+            ));
+            if !msg.trace_ids.is_empty() {
+                actix::System::current()
+                    .registry()
+                    .get::<super::trace_assembler::TraceAssembler>()
+                    .do_send(super::trace_assembler::TracesUpdated {
+                        ingest_id: msg.ingest_id.clone(),
+                        trace_ids: msg.trace_ids,
+                    });
+            }
+        }
+        self.finish_if_done(&msg.ingest_id);
+    }
+}
+
+/// A trace updated by an ingest batch was parsed, and may have produced a test result
+#[derive(Message)]
+pub struct TraceProcessed {
+    pub ingest_id: super::IngestId,
+    pub test_result: bool,
+}
+impl Handler<TraceProcessed> for Ingestor {
+    type Result = ();
+
+    fn handle(&mut self, msg: TraceProcessed, _ctx: &mut Context<Self>) -> Self::Result {
+        if let Some(progress) = self.in_progress.get_mut(&msg.ingest_id.0) {
+            progress.pending_traces -= 1;
+            if msg.test_result {
+                progress.ingest.nb_test_results += 1;
+            }
+        }
+        self.finish_if_done(&msg.ingest_id);
+    }
+}
@@ -2,11 +2,18 @@ use serde;
 use std::fmt;
 use uuid;
 
+pub mod baseline;
+pub mod compare;
+pub mod failure;
+pub mod flaky;
 pub mod ingestor;
 pub mod report;
+pub mod run;
 pub mod span;
 pub mod streams;
 pub mod test_result;
+pub mod trace_assembler;
+pub mod trace_diff;
 
 pub fn hello() -> &'static str {
     "I am i'Krelln"
@@ -53,7 +53,7 @@ impl Handler<ComputeReportsForResult> for Reporter {
                 ))
                 .then(move |spans| {
                     if let Ok(spans) = spans {
-                        let reports_to_send: HashSet<Report> = spans
+                        let mut reports_to_send: HashSet<Report> = spans
                             .iter()
                             .filter(|span| span.remote_endpoint.is_some())
                             .map(|span| Report {
@@ -66,6 +66,16 @@ impl Handler<ComputeReportsForResult> for Reporter {
                                 category: span.name.clone(),
                             })
                             .collect();
+                        // failures caused by a service, by the span that failed
+                        if let Some(blamed_component) = msg.0.blamed_component.clone() {
+                            reports_to_send.insert(Report {
+                                name: blamed_component
+                                    .service
+                                    .unwrap_or_else(|| "service".to_string()),
+                                group: "failures".to_string(),
+                                category: blamed_component.span_name,
+                            });
+                        }
                         reports_to_send.iter().for_each(|report| {
                             actix::System::current()
                                 .registry()
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use crate::engine::test_result::{TestResult, TestStatus};
+use crate::opentracing::tags::IkrellnTags;
+
+/// Number of slowest tests shown with a run
+pub static SLOWEST_TESTS: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TestRun {
+    pub id: String,
+    pub commit: Option<String>,
+    pub branch: Option<String>,
+    pub ci_url: Option<String>,
+    /// when the first test of the run started
+    pub start: i64,
+    /// when the last test of the run ended
+    pub end: i64,
+    pub duration: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<HashMap<TestStatus, usize>>,
+    /// previous run on the same branch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_run_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slowest_tests: Option<Vec<TestResult>>,
+    /// tests failing in this run that did not fail in the previous run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_failures: Option<Vec<TestResult>>,
+}
+
+impl TestRun {
+    pub fn new(
+        id: String,
+        commit: Option<String>,
+        branch: Option<String>,
+        ci_url: Option<String>,
+        start: i64,
+        end: i64,
+    ) -> Self {
+        TestRun {
+            id,
+            commit,
+            branch,
+            ci_url,
+            start,
+            end,
+            duration: end - start,
+            summary: None,
+            previous_run_id: None,
+            slowest_tests: None,
+            new_failures: None,
+        }
+    }
+
+    /// Run of a test result just parsed, from the tags of its main span
+    pub fn from_result(test_result: &TestResult) -> Option<Self> {
+        let tag = |tag: IkrellnTags| {
+            let tag: &str = tag.into();
+            test_result
+                .main_span
+                .as_ref()
+                .and_then(|span| span.tags.get(tag).cloned())
+        };
+        test_result.run_id.clone().map(|id| {
+            TestRun::new(
+                id,
+                tag(IkrellnTags::Commit),
+                tag(IkrellnTags::Branch),
+                tag(IkrellnTags::CiUrl),
+                test_result.date,
+                test_result.date + test_result.duration,
+            )
+        })
+    }
+
+    /// Extend a run with another part of it, keeping the details already known
+    pub fn merge(self, other: TestRun) -> Self {
+        TestRun::new(
+            self.id,
+            self.commit.or(other.commit),
+            self.branch.or(other.branch),
+            self.ci_url.or(other.ci_url),
+            self.start.min(other.start),
+            self.end.max(other.end),
+        )
+    }
+
+    pub fn with_summary(self, results: &[TestResult]) -> Self {
+        TestRun {
+            summary: Some(summary(results)),
+            ..self
+        }
+    }
+
+    /// Take the summary of this run, a run without attempts has an empty summary
+    pub fn with_summary_from(
+        self,
+        summaries: &mut HashMap<String, HashMap<TestStatus, usize>>,
+    ) -> Self {
+        TestRun {
+            summary: Some(summaries.remove(&self.id).unwrap_or_else(empty_summary)),
+            ..self
+        }
+    }
+
+    pub fn with_details(
+        self,
+        results: &[TestResult],
+        previous_run: Option<(String, Vec<TestResult>)>,
+    ) -> Self {
+        let mut slowest_tests: Vec<TestResult> =
+            last_attempts(results).into_iter().cloned().collect();
+        slowest_tests.sort_by_key(|tr| std::cmp::Reverse(tr.duration));
+        slowest_tests.truncate(SLOWEST_TESTS);
+
+        let (previous_run_id, new_failures) = match previous_run {
+            Some((previous_run_id, previous_results)) => {
+                let failed_before: Vec<(&String, &Option<String>)> =
+                    last_attempts(&previous_results)
+                        .into_iter()
+                        .filter(|tr| tr.status == TestStatus::Failure)
+                        .map(|tr| (&tr.test_id, &tr.environment))
+                        .collect();
+                let new_failures = last_attempts(results)
+                    .into_iter()
+                    .filter(|tr| {
+                        tr.status == TestStatus::Failure
+                            && !failed_before.contains(&(&tr.test_id, &tr.environment))
+                    })
+                    .cloned()
+                    .collect();
+                (Some(previous_run_id), Some(new_failures))
+            }
+            None => (None, None),
+        };
+
+        TestRun {
+            previous_run_id,
+            slowest_tests: Some(slowest_tests),
+            new_failures,
+            ..self.with_summary(results)
+        }
+    }
+}
+
+/// Final result of each test and environment in a run, from their last attempt
+pub fn last_attempts(results: &[TestResult]) -> Vec<&TestResult> {
+    let mut last_attempts: HashMap<(&String, &Option<String>), &TestResult> = HashMap::new();
+    for tr in results {
+        let last_attempt = last_attempts
+            .entry((&tr.test_id, &tr.environment))
+            .or_insert(tr);
+        if (tr.attempt, tr.date) > (last_attempt.attempt, last_attempt.date) {
+            *last_attempt = tr;
+        }
+    }
+    let mut last_attempts: Vec<&TestResult> = last_attempts.into_values().collect();
+    last_attempts.sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.name.cmp(&b.name)));
+    last_attempts
+}
+
+/// Attempt of a test in a run, enough to summarize runs without loading their results
+#[derive(Debug, Clone)]
+pub struct RunAttempt {
+    pub run_id: String,
+    pub test_id: String,
+    pub environment: Option<String>,
+    pub attempt: i32,
+    pub date: i64,
+    pub status: TestStatus,
+}
+
+fn empty_summary() -> HashMap<TestStatus, usize> {
+    [
+        TestStatus::Success,
+        TestStatus::Failure,
+        TestStatus::Skipped,
+        TestStatus::PassedOnRetry,
+    ]
+    .iter()
+    .map(|status| (status.clone(), 0))
+    .collect()
+}
+
+/// Number of tests of a run by final status
+pub fn summary(results: &[TestResult]) -> HashMap<TestStatus, usize> {
+    let mut summary = empty_summary();
+    for tr in last_attempts(results) {
+        *summary.entry(tr.status.clone()).or_default() += 1;
+    }
+    summary
+}
+
+/// Number of tests by final status of each run with attempts
+pub fn summaries(attempts: &[RunAttempt]) -> HashMap<String, HashMap<TestStatus, usize>> {
+    let mut last_attempts: HashMap<(&String, &String, &Option<String>), &RunAttempt> =
+        HashMap::new();
+    for run_attempt in attempts {
+        let last_attempt = last_attempts
+            .entry((
+                &run_attempt.run_id,
+                &run_attempt.test_id,
+                &run_attempt.environment,
+            ))
+            .or_insert(run_attempt);
+        if (run_attempt.attempt, run_attempt.date) > (last_attempt.attempt, last_attempt.date) {
+            *last_attempt = run_attempt;
+        }
+    }
+    let mut summaries: HashMap<String, HashMap<TestStatus, usize>> = HashMap::new();
+    for run_attempt in last_attempts.values() {
+        *summaries
+            .entry(run_attempt.run_id.clone())
+            .or_insert_with(empty_summary)
+            .entry(run_attempt.status.clone())
+            .or_default() += 1;
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::test_result::{TestResult, TestStatus};
+
+    fn result(test_id: &str, status: TestStatus, attempt: i32, duration: i64) -> TestResult {
+        TestResult {
+            test_id: test_id.to_string(),
+            name: test_id.to_string(),
+            trace_id: format!("{}-{}", test_id, attempt),
+            date: i64::from(attempt),
+            status,
+            duration,
+            run_id: Some("run".to_string()),
+            attempt,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn can_summarize_run_from_last_attempts() {
+        let results = vec![
+            result("a", TestStatus::Failure, 1, 10),
+            result("a", TestStatus::PassedOnRetry, 2, 20),
+            result("b", TestStatus::Success, 1, 30),
+            result("c", TestStatus::Failure, 1, 5),
+            result("d", TestStatus::Failure, 1, 5),
+        ];
+        let previous_results = vec![
+            result("c", TestStatus::Success, 1, 5),
+            result("d", TestStatus::Failure, 1, 5),
+        ];
+
+        let run = super::TestRun::new("run".to_string(), None, None, None, 0, 100)
+            .with_details(&results, Some(("previous".to_string(), previous_results)));
+        let summary = run.summary.unwrap();
+        assert_eq!(summary[&TestStatus::Success], 1);
+        assert_eq!(summary[&TestStatus::Failure], 2);
+        assert_eq!(summary[&TestStatus::PassedOnRetry], 1);
+        assert_eq!(summary[&TestStatus::Skipped], 0);
+
+        let slowest_tests = run.slowest_tests.unwrap();
+        assert_eq!(slowest_tests.len(), 4);
+        assert_eq!(slowest_tests[0].test_id, "b");
+        assert_eq!(slowest_tests[1].test_id, "a");
+
+        let new_failures = run.new_failures.unwrap();
+        assert_eq!(new_failures.len(), 1);
+        assert_eq!(new_failures[0].test_id, "c");
+        assert_eq!(run.previous_run_id, Some("previous".to_string()));
+    }
+
+    #[test]
+    fn can_summarize_runs_from_their_attempts() {
+        let attempt =
+            |run_id: &str, test_id: &str, attempt: i32, status: TestStatus| super::RunAttempt {
+                run_id: run_id.to_string(),
+                test_id: test_id.to_string(),
+                environment: None,
+                attempt,
+                date: i64::from(attempt),
+                status,
+            };
+        let attempts = vec![
+            attempt("run1", "a", 2, TestStatus::PassedOnRetry),
+            attempt("run1", "a", 1, TestStatus::Failure),
+            attempt("run1", "b", 1, TestStatus::Success),
+            attempt("run2", "a", 1, TestStatus::Failure),
+        ];
+
+        let mut summaries = super::summaries(&attempts);
+        assert_eq!(summaries["run1"][&TestStatus::PassedOnRetry], 1);
+        assert_eq!(summaries["run1"][&TestStatus::Success], 1);
+        assert_eq!(summaries["run1"][&TestStatus::Failure], 0);
+        assert_eq!(summaries["run2"][&TestStatus::Failure], 1);
+
+        let run = super::TestRun::new("run3".to_string(), None, None, None, 0, 100)
+            .with_summary_from(&mut summaries);
+        assert_eq!(run.summary.unwrap()[&TestStatus::Success], 0);
+    }
+}
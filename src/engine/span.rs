@@ -11,20 +11,44 @@ impl Handler<super::ingestor::IngestEvents<Span>> for super::ingestor::Ingestor
         msg: super::ingestor::IngestEvents<Span>,
         _ctx: &mut Context<Self>,
     ) -> Self::Result {
-        for event in &msg.events {
-            let event = event.clone();
-            Arbiter::spawn(crate::DB_EXECUTOR_POOL.send(event.clone()).then(|span| {
-                if let Ok(span) = span {
-                    if let (Some(_), None) = (span.duration, span.parent_id.clone()) {
-                        actix::System::current()
-                            .registry()
-                            .get::<super::test_result::TraceParser>()
-                            .do_send(super::test_result::TraceDone(span.trace_id.clone()));
-                    }
-                }
-                result(Ok(()))
-            }));
+        self.start_ingest(&msg);
+        if msg.events.is_empty() {
+            return;
         }
+        let ingest_id = msg.ingest_id;
+        let nb_events = msg.events.len();
+        let events = msg.events.into_iter().flat_map(Span::into_v2).collect();
+        Arbiter::spawn(
+            crate::DB_EXECUTOR_POOL
+                .send(crate::db::update::span::SaveSpans(events))
+                .then(move |spans| {
+                    let processed = match spans {
+                        Ok(Ok(spans)) => {
+                            let mut trace_ids: Vec<String> =
+                                spans.into_iter().map(|span| span.trace_id).collect();
+                            trace_ids.sort();
+                            trace_ids.dedup();
+                            super::ingestor::EventsProcessed {
+                                ingest_id,
+                                nb_stored: nb_events,
+                                nb_failed: 0,
+                                trace_ids,
+                            }
+                        }
+                        _ => super::ingestor::EventsProcessed {
+                            ingest_id,
+                            nb_stored: 0,
+                            nb_failed: nb_events,
+                            trace_ids: vec![],
+                        },
+                    };
+                    actix::System::current()
+                        .registry()
+                        .get::<super::ingestor::Ingestor>()
+                        .do_send(processed);
+                    result(Ok(()))
+                }),
+        );
     }
 }
 
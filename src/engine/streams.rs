@@ -15,6 +15,9 @@ pub enum ScriptType {
     //     import requests
     //     import json
     //     requests.post("https://requestb.in/XXXXXXX", data=json.dumps(test))
+    // It can also act on a test that got slower than its baseline
+    // def on_duration_regression(regression):
+    //     print(regression['test_id'], regression['ratio'])
     StreamTest,
 
     // Python function that can act on a test
@@ -47,9 +50,9 @@ impl From<i32> for ScriptType {
         }
     }
 }
-impl Into<i32> for ScriptType {
-    fn into(self) -> i32 {
-        match self {
+impl From<ScriptType> for i32 {
+    fn from(script_type: ScriptType) -> Self {
+        match script_type {
             ScriptType::StreamSpan => 0,
             ScriptType::StreamTest => 1,
             ScriptType::UITest => 2,
@@ -58,9 +61,9 @@ impl Into<i32> for ScriptType {
         }
     }
 }
-impl Into<String> for ScriptType {
-    fn into(self) -> String {
-        match self {
+impl From<ScriptType> for String {
+    fn from(script_type: ScriptType) -> Self {
+        match script_type {
             ScriptType::StreamSpan => "StreamSpan".to_string(),
             ScriptType::StreamTest => "StreamTest".to_string(),
             ScriptType::UITest => "UITest".to_string(),
@@ -291,3 +294,43 @@ impl Handler<Test> for Streamer {
     #[cfg(not(feature = "python"))]
     fn handle(&mut self, _msg: Test, _ctx: &mut Context<Self>) -> Self::Result {}
 }
+
+#[derive(Message, Debug)]
+pub struct Regression(pub crate::engine::baseline::DurationRegression);
+impl Handler<Regression> for Streamer {
+    type Result = ();
+
+    #[cfg(feature = "python")]
+    fn handle(&mut self, msg: Regression, _ctx: &mut Context<Self>) -> Self::Result {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let locals = PyDict::new(py);
+        locals.set_item(py, "regression", msg.0).unwrap();
+
+        for script in self
+            .scripts
+            .iter()
+            .filter(|script| match script.script_type {
+                ScriptType::StreamTest => true,
+                _ => false,
+            })
+        {
+            if py.run(script.source.as_ref(), None, Some(&locals)).is_ok()
+                && locals.get_item(py, "on_duration_regression").is_some()
+            {
+                if let Err(err) = py.eval("on_duration_regression(regression)", None, Some(&locals))
+                {
+                    warn!(
+                        "error executing python script {}: {:?}",
+                        script.id.clone().unwrap(),
+                        err
+                    )
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "python"))]
+    fn handle(&mut self, _msg: Regression, _ctx: &mut Context<Self>) -> Self::Result {}
+}
@@ -20,19 +20,23 @@ impl actix::SystemService for TraceParser {
 }
 
 #[derive(Message)]
-pub struct TraceDone(pub String);
+pub struct TraceDone {
+    pub trace_id: String,
+    pub ingest_ids: Vec<super::IngestId>,
+}
 impl Handler<TraceDone> for TraceParser {
     type Result = ();
 
     fn handle(&mut self, msg: TraceDone, _ctx: &mut Context<Self>) -> Self::Result {
+        let ingest_ids = msg.ingest_ids;
         Arbiter::spawn(
             crate::DB_READ_EXECUTOR_POOL
-                .send(crate::db::read::span::GetSpans(
-                    crate::db::read::span::SpanQuery::default()
-                        .with_trace_id(msg.0)
-                        .with_limit(1000),
-                ))
+                .send(crate::db::read::span::GetTrace(msg.trace_id))
                 .map(|spans| {
+                    if spans.iter().all(|span| span.parent_id.is_some()) {
+                        // root span of the trace not received yet
+                        return None;
+                    }
                     let te = TestResult::try_from(&spans);
                     match te {
                         Ok(te) => Some(te),
@@ -50,7 +54,17 @@ impl Handler<TraceDone> for TraceParser {
                         actix::System::current()
                             .registry()
                             .get::<super::test_result::TraceParser>()
-                            .do_send(TestExecutionToSave(test_exec));
+                            .do_send(TestExecutionToSave(test_exec, ingest_ids));
+                    } else {
+                        for ingest_id in ingest_ids {
+                            actix::System::current()
+                                .registry()
+                                .get::<super::ingestor::Ingestor>()
+                                .do_send(super::ingestor::TraceProcessed {
+                                    ingest_id,
+                                    test_result: false,
+                                });
+                        }
                     }
                     future::result(Ok(()))
                 }),
@@ -59,21 +73,47 @@ impl Handler<TraceDone> for TraceParser {
 }
 
 #[derive(Message, Debug)]
-pub struct TestExecutionToSave(TestResult);
+pub struct TestExecutionToSave(TestResult, Vec<super::IngestId>);
 
 impl Handler<TestExecutionToSave> for TraceParser {
     type Result = ();
 
     fn handle(&mut self, msg: TestExecutionToSave, _ctx: &mut Context<Self>) -> Self::Result {
+        let ingest_ids = msg.1;
         Arbiter::spawn(
             crate::DB_EXECUTOR_POOL
                 .send(msg.0.clone())
                 .then(|test_result| {
-                    if let Ok(test_result) = test_result {
+                    for ingest_id in ingest_ids {
                         actix::System::current()
                             .registry()
-                            .get::<crate::engine::streams::Streamer>()
-                            .do_send(crate::engine::streams::Test(test_result.clone()));
+                            .get::<super::ingestor::Ingestor>()
+                            .do_send(super::ingestor::TraceProcessed {
+                                ingest_id,
+                                test_result: test_result.is_ok(),
+                            });
+                    }
+                    if let Ok(saved) = test_result {
+                        let test_result = saved.test_result;
+                        if let Some(test_run) =
+                            crate::engine::run::TestRun::from_result(&test_result)
+                        {
+                            crate::DB_EXECUTOR_POOL
+                                .do_send(crate::db::update::run::SaveTestRun(test_run));
+                        }
+                        // late spans re-save the result, only notify when it changed
+                        if saved.changed {
+                            actix::System::current()
+                                .registry()
+                                .get::<crate::engine::streams::Streamer>()
+                                .do_send(crate::engine::streams::Test(test_result.clone()));
+                            actix::System::current()
+                                .registry()
+                                .get::<TraceParser>()
+                                .do_send(crate::engine::baseline::CheckDuration(
+                                    test_result.clone(),
+                                ));
+                        }
                         actix::System::current()
                             .registry()
                             .get::<crate::engine::report::Reporter>()
@@ -91,6 +131,8 @@ pub enum TestStatus {
     Failure,
     Skipped,
     Any,
+    /// succeeded after failed attempts in the same run
+    PassedOnRetry,
 }
 impl Eq for TestStatus {}
 impl TestStatus {
@@ -109,6 +151,7 @@ impl From<i32> for TestStatus {
             0 => crate::engine::test_result::TestStatus::Success,
             1 => crate::engine::test_result::TestStatus::Failure,
             2 => crate::engine::test_result::TestStatus::Skipped,
+            4 => crate::engine::test_result::TestStatus::PassedOnRetry,
             _ => crate::engine::test_result::TestStatus::Failure,
         }
     }
@@ -120,6 +163,7 @@ impl TestStatus {
             crate::engine::test_result::TestStatus::Failure => 1,
             crate::engine::test_result::TestStatus::Skipped => 2,
             crate::engine::test_result::TestStatus::Any => 3,
+            crate::engine::test_result::TestStatus::PassedOnRetry => 4,
         }
     }
     pub fn as_str(&self) -> &'static str {
@@ -128,17 +172,102 @@ impl TestStatus {
             crate::engine::test_result::TestStatus::Failure => "Failure",
             crate::engine::test_result::TestStatus::Skipped => "Skipped",
             crate::engine::test_result::TestStatus::Any => "Any",
+            crate::engine::test_result::TestStatus::PassedOnRetry => "PassedOnRetry",
+        }
+    }
+    /// Status of an attempt, a success passing on retry only when an earlier attempt of the
+    /// same run failed
+    pub fn of_attempt(self, earlier_attempt_failed: bool) -> Self {
+        match self {
+            TestStatus::Success | TestStatus::PassedOnRetry if earlier_attempt_failed => {
+                TestStatus::PassedOnRetry
+            }
+            TestStatus::PassedOnRetry => TestStatus::Success,
+            status => status,
         }
     }
 }
-impl Into<i32> for TestStatus {
-    fn into(self) -> i32 {
-        self.as_i32()
+impl From<TestStatus> for i32 {
+    fn from(status: TestStatus) -> Self {
+        status.as_i32()
     }
 }
-impl Into<&'static str> for TestStatus {
-    fn into(self) -> &'static str {
-        self.as_str()
+impl From<TestStatus> for &'static str {
+    fn from(status: TestStatus) -> Self {
+        status.as_str()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum StepType {
+    Setup,
+    Action,
+    Assert,
+    Teardown,
+}
+impl StepType {
+    // also understands the keywords of Gherkin steps and hooks
+    fn from_tag(s: &str) -> Self {
+        match s.to_lowercase().as_ref() {
+            "setup" | "before" | "given" | "background" => StepType::Setup,
+            "assert" | "then" => StepType::Assert,
+            "teardown" | "after" => StepType::Teardown,
+            _ => StepType::Action,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TestStep {
+    pub span_id: String,
+    pub name: String,
+    pub step_type: StepType,
+    pub status: TestStatus,
+    pub timestamp: Option<i64>,
+    pub duration: Option<i64>,
+    pub parameters: Option<String>,
+}
+impl TestStep {
+    fn step_status(span: &crate::opentracing::Span) -> TestStatus {
+        let status: &str = IkrellnTags::StepStatus.into();
+        let error: &str = OpenTracingTag::Error.into();
+        match span.tags.get(status).map(|status| status.to_lowercase()) {
+            Some(ref status) if status == "success" || status == "passed" || status == "ok" => {
+                TestStatus::Success
+            }
+            Some(ref status) if status == "skipped" || status == "pending" => TestStatus::Skipped,
+            Some(_) => TestStatus::Failure,
+            None if span.tags.get(error).map(String::as_str) == Some("true") => TestStatus::Failure,
+            None => TestStatus::Success,
+        }
+    }
+
+    /// Steps of a test are the spans of its trace with a `test.step_type` tag, in the
+    /// order they started
+    fn from_spans(spans: &[crate::opentracing::Span]) -> Vec<Self> {
+        let step_type: &str = IkrellnTags::StepType.into();
+        let step_parameters: &str = IkrellnTags::StepParameters.into();
+        let mut steps: Vec<TestStep> = spans
+            .iter()
+            .filter(|span| span.parent_id.is_some())
+            .filter_map(|span| {
+                span.tags.get(step_type).map(|tag| TestStep {
+                    span_id: span.id.clone(),
+                    name: span.name.clone().unwrap_or_else(|| tag.clone()),
+                    step_type: StepType::from_tag(tag),
+                    status: Self::step_status(span),
+                    timestamp: span.timestamp,
+                    duration: span.duration,
+                    parameters: span.tags.get(step_parameters).cloned(),
+                })
+            })
+            .collect();
+        steps.sort_by(|a, b| {
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then_with(|| a.span_id.cmp(&b.span_id))
+        });
+        steps
     }
 }
 
@@ -152,12 +281,51 @@ pub struct TestResult {
     pub status: TestStatus,
     pub duration: i64,
     pub environment: Option<String>,
+    /// CI build or commit the test ran for, from the `ci.build_id` or `ci.commit` tags
+    pub build: Option<String>,
+    /// run the test was executed in, attempts of a test in the same run are retries, from
+    /// the `test.run_id` or `ci.build_id` tags
+    pub run_id: Option<String>,
+    /// attempt number of the test in its run, from the `test.attempt` tag, starting at 1
+    pub attempt: i32,
     pub components_called: HashMap<String, i32>,
     pub nb_spans: i32,
+    #[serde(default)]
+    pub steps: Vec<TestStep>,
+    /// what made the test fail, from the error tags and logs of its spans
+    pub failure: Option<super::failure::TestFailure>,
+    /// component of the deepest failed span of the trace
+    pub blamed_component: Option<super::failure::BlamedComponent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub main_span: Option<crate::opentracing::Span>,
 }
 
+/// Successful first attempt of a test, for unit tests to change only what they check
+#[cfg(test)]
+impl Default for TestResult {
+    fn default() -> Self {
+        TestResult {
+            test_id: "test".to_string(),
+            path: vec![],
+            name: "test".to_string(),
+            trace_id: "trace".to_string(),
+            date: 0,
+            status: TestStatus::Success,
+            duration: 10,
+            environment: None,
+            build: None,
+            run_id: None,
+            attempt: 1,
+            components_called: HashMap::new(),
+            nb_spans: 1,
+            steps: vec![],
+            failure: None,
+            blamed_component: None,
+            main_span: None,
+        }
+    }
+}
+
 #[cfg(feature = "python")]
 impl ToPyObject for TestResult {
     type ObjectType = PyDict;
@@ -177,6 +345,22 @@ impl ToPyObject for TestResult {
         if let Some(environment) = self.environment.clone() {
             object.set_item(py, "environment", environment).unwrap();
         }
+        if let Some(build) = self.build.clone() {
+            object.set_item(py, "build", build).unwrap();
+        }
+        if let Some(run_id) = self.run_id.clone() {
+            object.set_item(py, "run_id", run_id).unwrap();
+        }
+        object.set_item(py, "attempt", self.attempt).unwrap();
+        object.set_item(py, "steps", self.steps.clone()).unwrap();
+        if let Some(failure) = self.failure.clone() {
+            object.set_item(py, "failure", failure).unwrap();
+        }
+        if let Some(blamed_component) = self.blamed_component.clone() {
+            object
+                .set_item(py, "blamed_component", blamed_component)
+                .unwrap();
+        }
         if let Some(main_span) = self.main_span.clone() {
             object.set_item(py, "main_span", main_span).unwrap();
         }
@@ -184,7 +368,39 @@ impl ToPyObject for TestResult {
     }
 }
 
+#[cfg(feature = "python")]
+impl ToPyObject for TestStep {
+    type ObjectType = PyDict;
+    fn to_py_object(&self, py: Python) -> Self::ObjectType {
+        let object = PyDict::new(py);
+        object.set_item(py, "name", self.name.clone()).unwrap();
+        object
+            .set_item(py, "step_type", format!("{:?}", self.step_type))
+            .unwrap();
+        object.set_item(py, "status", self.status.as_str()).unwrap();
+        if let Some(duration) = self.duration {
+            object.set_item(py, "duration", duration).unwrap();
+        }
+        if let Some(parameters) = self.parameters.clone() {
+            object.set_item(py, "parameters", parameters).unwrap();
+        }
+        object
+    }
+}
+
 impl TestResult {
+    /// If this is an attempt of the same run made before `attempt`
+    pub fn is_earlier_attempt(&self, run_id: &Option<String>, attempt: i32) -> bool {
+        self.run_id.is_some() && self.run_id == *run_id && self.attempt < attempt
+    }
+
+    /// If this saved result is new, or its status or duration changed since `previous`
+    pub fn changed_from(&self, previous: Option<&TestResult>) -> bool {
+        previous
+            .map(|previous| previous.status != self.status || previous.duration != self.duration)
+            .unwrap_or(true)
+    }
+
     fn value_from_tag<T>(tags: &HashMap<String, String>, tag: T) -> Result<String, KnownTag>
     where
         T: Clone,
@@ -238,6 +454,24 @@ impl TestResult {
             }
         }
 
+        let status = TestStatus::try_from(&Self::value_from_tag_or(
+            main_span,
+            IkrellnTags::Result,
+            |span| {
+                Self::value_from_tag(&span.tags, OpenTracingTag::Error)
+                    .ok()
+                    .map(|v| match v.to_lowercase().as_ref() {
+                        "true" => "failure".to_string(),
+                        other => other.to_string(),
+                    })
+            },
+        )?)?;
+        let attempt = Self::value_from_tag(&main_span.tags, IkrellnTags::Attempt)
+            .ok()
+            .and_then(|attempt| attempt.parse::<i32>().ok())
+            .unwrap_or(1)
+            .max(1);
+
         Ok(TestResult {
             test_id: "n/a".to_string(),
             path: vec![suite, class],
@@ -246,24 +480,29 @@ impl TestResult {
             date: main_span.timestamp.ok_or(KnownTag {
                 tag: "ts".to_string(),
             })?,
-            status: TestStatus::try_from(&Self::value_from_tag_or(
-                main_span,
-                IkrellnTags::Result,
-                |span| {
-                    Self::value_from_tag(&span.tags, OpenTracingTag::Error)
-                        .ok()
-                        .map(|v| match v.to_lowercase().as_ref() {
-                            "true" => "failure".to_string(),
-                            other => other.to_string(),
-                        })
-                },
-            )?)?,
+            failure: match status {
+                TestStatus::Failure => super::failure::TestFailure::from_spans(spans),
+                _ => None,
+            },
+            blamed_component: match status {
+                TestStatus::Failure => super::failure::BlamedComponent::from_spans(spans),
+                _ => None,
+            },
+            status,
             duration: main_span.duration.ok_or(KnownTag {
                 tag: "duration".to_string(),
             })?,
             environment: Self::value_from_tag(&main_span.tags, IkrellnTags::Environment).ok(),
+            build: Self::value_from_tag(&main_span.tags, IkrellnTags::BuildId)
+                .or_else(|_| Self::value_from_tag(&main_span.tags, IkrellnTags::Commit))
+                .ok(),
+            run_id: Self::value_from_tag(&main_span.tags, IkrellnTags::RunId)
+                .or_else(|_| Self::value_from_tag(&main_span.tags, IkrellnTags::BuildId))
+                .ok(),
+            attempt,
             components_called: call_by_remote_endpoint,
             nb_spans: spans.len() as i32,
+            steps: TestStep::from_spans(spans),
             main_span: Some(main_span.clone()),
         })
     }
@@ -274,7 +513,7 @@ mod tests {
     use std::collections::HashMap;
     use uuid;
 
-    use crate::opentracing::span::Kind;
+    use crate::opentracing::span::{Kind, SpanBuilder};
     use crate::opentracing::tags::IkrellnTags;
     use crate::opentracing::Span;
 
@@ -327,4 +566,61 @@ mod tests {
         let tr = TestResult::try_from(&spans);
         assert!(tr.is_ok());
     }
+
+    #[test]
+    fn can_get_steps_from_child_spans() {
+        let trace_id = uuid::Uuid::new_v4().to_string();
+
+        let span =
+            |id: &str, timestamp: i64| SpanBuilder::new(id).trace(&trace_id).timestamp(timestamp);
+        let spans = vec![
+            span("test", 50)
+                .tag(IkrellnTags::Suite, "test_suite")
+                .tag(IkrellnTags::Class, "test_class")
+                .tag(IkrellnTags::Result, "failure")
+                .build(),
+            span("check", 70)
+                .child_of("test")
+                .tag(IkrellnTags::StepType, "Then")
+                .tag(IkrellnTags::StepStatus, "failed")
+                .build(),
+            span("http call", 60).child_of("test").build(),
+            span("open page", 55)
+                .child_of("test")
+                .tag(IkrellnTags::StepType, "action")
+                .tag(IkrellnTags::StepParameters, "{\"page\": \"home\"}")
+                .build(),
+        ];
+
+        let tr = TestResult::try_from(&spans).unwrap();
+        assert_eq!(tr.steps.len(), 2);
+        assert_eq!(tr.steps[0].name, "open page");
+        assert_eq!(tr.steps[0].step_type, StepType::Action);
+        assert_eq!(tr.steps[0].status, TestStatus::Success);
+        assert_eq!(
+            tr.steps[0].parameters,
+            Some("{\"page\": \"home\"}".to_string())
+        );
+        assert_eq!(tr.steps[1].step_type, StepType::Assert);
+        assert_eq!(tr.steps[1].status, TestStatus::Failure);
+    }
+
+    #[test]
+    fn should_change_only_on_new_status_or_duration() {
+        let saved = TestResult::default();
+
+        assert!(saved.changed_from(None));
+        assert!(!saved.changed_from(Some(&TestResult {
+            nb_spans: 3,
+            ..saved.clone()
+        })));
+        assert!(saved.changed_from(Some(&TestResult {
+            duration: 20,
+            ..saved.clone()
+        })));
+        assert!(saved.changed_from(Some(&TestResult {
+            status: TestStatus::Failure,
+            ..saved.clone()
+        })));
+    }
 }
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+
+/// Wait for traces to be quiet for a while before parsing them, so that spans
+/// reported late are part of the test result
+pub struct TraceAssembler {
+    quiet_period: Duration,
+    pending: HashMap<String, PendingTrace>,
+}
+impl Default for TraceAssembler {
+    fn default() -> Self {
+        TraceAssembler {
+            quiet_period: Duration::from_millis(u64::from(crate::CONFIG.trace_quiet_period)),
+            pending: HashMap::new(),
+        }
+    }
+}
+impl Actor for TraceAssembler {
+    type Context = Context<Self>;
+}
+impl actix::Supervised for TraceAssembler {}
+
+impl actix::SystemService for TraceAssembler {
+    fn service_started(&mut self, _ctx: &mut Context<Self>) {}
+}
+
+struct PendingTrace {
+    last_update: Instant,
+    ingest_ids: Vec<super::IngestId>,
+}
+
+impl TraceAssembler {
+    fn send_quiet_traces(&mut self) {
+        let now = Instant::now();
+        let quiet_period = self.quiet_period;
+        let quiet_traces: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, trace)| now.duration_since(trace.last_update) >= quiet_period)
+            .map(|(trace_id, _)| trace_id.clone())
+            .collect();
+        for trace_id in quiet_traces {
+            if let Some(trace) = self.pending.remove(&trace_id) {
+                actix::System::current()
+                    .registry()
+                    .get::<super::test_result::TraceParser>()
+                    .do_send(super::test_result::TraceDone {
+                        trace_id,
+                        ingest_ids: trace.ingest_ids,
+                    });
+            }
+        }
+    }
+}
+
+/// Spans of those traces were saved by an ingest
+#[derive(Message)]
+pub struct TracesUpdated {
+    pub ingest_id: super::IngestId,
+    pub trace_ids: Vec<String>,
+}
+impl Handler<TracesUpdated> for TraceAssembler {
+    type Result = ();
+
+    fn handle(&mut self, msg: TracesUpdated, ctx: &mut Context<Self>) -> Self::Result {
+        let now = Instant::now();
+        let ingest_id = msg.ingest_id;
+        for trace_id in msg.trace_ids {
+            let trace = self
+                .pending
+                .entry(trace_id)
+                .or_insert_with(|| PendingTrace {
+                    last_update: now,
+                    ingest_ids: vec![],
+                });
+            trace.last_update = now;
+            if trace
+                .ingest_ids
+                .iter()
+                .all(|pending_ingest_id| pending_ingest_id.0 != ingest_id.0)
+            {
+                trace.ingest_ids.push(ingest_id.clone());
+            }
+        }
+        ctx.run_later(self.quiet_period, |act, _ctx| act.send_quiet_traces());
+    }
+}
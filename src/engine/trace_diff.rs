@@ -0,0 +1,309 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::opentracing::tags::OpenTracingTag;
+use crate::opentracing::Span;
+
+/// Tags telling how a call behaved, compared between the two executions
+static COMPARED_TAGS: [&str; 5] = [
+    "error",
+    "error.kind",
+    "http.status_code",
+    "rpc.grpc.status_code",
+    "otel.status_code",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanChange {
+    /// call only made by the failing execution
+    Added,
+    /// call only made by the successful execution
+    Missing,
+    /// call made by both executions that behaved differently
+    Changed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TagChange {
+    pub tag: String,
+    pub success: Option<String>,
+    pub failure: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SpanDiff {
+    /// `service:span name` of the span and its parents, from the root span
+    pub path: Vec<String>,
+    pub change: SpanChange,
+    pub success_span_id: Option<String>,
+    pub failure_span_id: Option<String>,
+    pub success_duration: Option<i64>,
+    pub failure_duration: Option<i64>,
+    pub changed_tags: Vec<TagChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TraceDiff {
+    pub test_id: String,
+    pub success_trace_id: String,
+    pub failure_trace_id: String,
+    pub spans: Vec<SpanDiff>,
+}
+
+fn label(span: &Span) -> String {
+    let service = span
+        .local_endpoint
+        .as_ref()
+        .and_then(|endpoint| endpoint.service_name.clone())
+        .or_else(|| {
+            span.remote_endpoint
+                .as_ref()
+                .and_then(|endpoint| endpoint.service_name.clone())
+        })
+        .unwrap_or_default();
+    format!("{}:{}", service, span.name.clone().unwrap_or_default())
+}
+
+/// Spans of a trace by their `service:span name` path, in the order they started
+fn by_path(spans: &[Span]) -> BTreeMap<Vec<String>, Vec<&Span>> {
+    let spans_by_id: HashMap<(&str, bool), &Span> = spans
+        .iter()
+        .map(|span| ((span.id.as_str(), span.shared), span))
+        .collect();
+    let mut by_path: BTreeMap<Vec<String>, Vec<&Span>> = BTreeMap::new();
+    for span in spans {
+        let mut path = vec![label(span)];
+        let mut current = span.parent_id.as_deref();
+        // children of a shared span were made by the server side
+        while let Some(parent) = current.and_then(|parent_id| {
+            spans_by_id
+                .get(&(parent_id, true))
+                .or_else(|| spans_by_id.get(&(parent_id, false)))
+        }) {
+            if path.len() > spans.len() {
+                // loop in the parents of the trace
+                break;
+            }
+            path.push(label(parent));
+            current = parent.parent_id.as_deref();
+        }
+        path.reverse();
+        by_path.entry(path).or_default().push(span);
+    }
+    for spans in by_path.values_mut() {
+        spans.sort_by_key(|span| span.timestamp);
+    }
+    by_path
+}
+
+fn changed_tags(success: &Span, failure: &Span) -> Vec<TagChange> {
+    let error: &str = OpenTracingTag::Error.into();
+    let is_error =
+        |span: &Span| span.tags.get(error).map(|v| v.to_lowercase()) == Some("true".to_string());
+    COMPARED_TAGS
+        .iter()
+        .filter_map(|tag| {
+            let (success_value, failure_value) = (success.tags.get(*tag), failure.tags.get(*tag));
+            let unchanged = if *tag == error {
+                // a missing error flag is the same as a false one
+                is_error(success) == is_error(failure)
+            } else {
+                success_value == failure_value
+            };
+            if unchanged {
+                None
+            } else {
+                Some(TagChange {
+                    tag: tag.to_string(),
+                    success: success_value.cloned(),
+                    failure: failure_value.cloned(),
+                })
+            }
+        })
+        .collect()
+}
+
+/// Align spans of both traces by path and keep those that differ. Spans with the same
+/// path are paired in the order they started.
+pub fn diff(
+    test_id: String,
+    success_trace_id: String,
+    success_spans: &[Span],
+    failure_trace_id: String,
+    failure_spans: &[Span],
+) -> TraceDiff {
+    let success_by_path = by_path(success_spans);
+    let mut failure_by_path = by_path(failure_spans);
+
+    let mut diffs = vec![];
+    for (path, success_spans) in success_by_path {
+        let failure_spans = failure_by_path.remove(&path).unwrap_or_default();
+        for i in 0..success_spans.len().max(failure_spans.len()) {
+            let (success, failure) = (success_spans.get(i), failure_spans.get(i));
+            let (change, changed_tags) = match (success, failure) {
+                (Some(success), Some(failure)) => {
+                    let changed_tags = changed_tags(success, failure);
+                    let duration_changed = match (success.duration, failure.duration) {
+                        (Some(success), Some(failure)) => {
+                            crate::engine::compare::is_significant_duration_change(success, failure)
+                        }
+                        _ => false,
+                    };
+                    if changed_tags.is_empty() && !duration_changed {
+                        continue;
+                    }
+                    (SpanChange::Changed, changed_tags)
+                }
+                (Some(_), None) => (SpanChange::Missing, vec![]),
+                (None, _) => (SpanChange::Added, vec![]),
+            };
+            diffs.push(SpanDiff {
+                path: path.clone(),
+                change,
+                success_span_id: success.map(|span| span.id.clone()),
+                failure_span_id: failure.map(|span| span.id.clone()),
+                success_duration: success.and_then(|span| span.duration),
+                failure_duration: failure.and_then(|span| span.duration),
+                changed_tags,
+            });
+        }
+    }
+    for (path, failure_spans) in failure_by_path {
+        for failure in failure_spans {
+            diffs.push(SpanDiff {
+                path: path.clone(),
+                change: SpanChange::Added,
+                success_span_id: None,
+                failure_span_id: Some(failure.id.clone()),
+                success_duration: None,
+                failure_duration: failure.duration,
+                changed_tags: vec![],
+            });
+        }
+    }
+    diffs.sort_by(|a, b| a.path.cmp(&b.path));
+
+    TraceDiff {
+        test_id,
+        success_trace_id,
+        failure_trace_id,
+        spans: diffs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanChange;
+    use crate::opentracing::span::{Kind, SpanBuilder};
+    use crate::opentracing::Span;
+
+    #[test]
+    fn can_diff_traces_by_span_path() {
+        let span = |id: &str, parent_id: &str, duration: i64| {
+            SpanBuilder::new(id)
+                .child_of(parent_id)
+                .service("shop")
+                .duration(Some(duration))
+        };
+        let test = || {
+            SpanBuilder::new("test")
+                .service("tests")
+                .duration(Some(100_000))
+                .build()
+        };
+        let success = vec![
+            test(),
+            span("checkout", "test", 50_000).build(),
+            span("stock", "checkout", 10_000)
+                .tag("http.status_code", "200")
+                .build(),
+            span("cache", "checkout", 5_000).build(),
+        ];
+        let failure = vec![
+            test(),
+            span("checkout", "test", 50_000)
+                .tag("error", "true")
+                .build(),
+            span("stock", "checkout", 40_000)
+                .tag("http.status_code", "503")
+                .build(),
+            span("retry", "checkout", 1_000)
+                .tag("error", "false")
+                .build(),
+        ];
+
+        let diff = super::diff(
+            "test_id".to_string(),
+            "success".to_string(),
+            &success,
+            "failure".to_string(),
+            &failure,
+        );
+
+        assert_eq!(diff.spans.len(), 4);
+        assert_eq!(diff.spans[0].path, vec!["tests:test", "shop:checkout"]);
+        assert_eq!(diff.spans[0].change, SpanChange::Changed);
+        assert_eq!(diff.spans[0].changed_tags[0].tag, "error");
+        assert_eq!(diff.spans[0].changed_tags[0].success, None);
+
+        assert_eq!(diff.spans[1].path[2], "shop:cache");
+        assert_eq!(diff.spans[1].change, SpanChange::Missing);
+        assert_eq!(diff.spans[2].path[2], "shop:retry");
+        assert_eq!(diff.spans[2].change, SpanChange::Added);
+
+        assert_eq!(diff.spans[3].path[2], "shop:stock");
+        assert_eq!(diff.spans[3].change, SpanChange::Changed);
+        assert_eq!(
+            diff.spans[3].changed_tags[0].failure,
+            Some("503".to_string())
+        );
+        assert_eq!(diff.spans[3].success_duration, Some(10_000));
+        assert_eq!(diff.spans[3].failure_duration, Some(40_000));
+    }
+
+    #[test]
+    fn can_diff_children_of_shared_spans() {
+        let client = SpanBuilder::new("call")
+            .child_of("test")
+            .service("front")
+            .kind(Kind::CLIENT)
+            .build();
+        let server = Span {
+            shared: true,
+            ..SpanBuilder::new("call")
+                .child_of("test")
+                .service("back")
+                .kind(Kind::SERVER)
+                .build()
+        };
+        let trace = |shared_copies: Vec<Span>, duration: i64| {
+            let mut spans = vec![SpanBuilder::new("test").service("tests").build()];
+            spans.extend(shared_copies);
+            spans.push(
+                SpanBuilder::new("query")
+                    .child_of("call")
+                    .service("back")
+                    .duration(Some(duration))
+                    .build(),
+            );
+            spans
+        };
+        let success = trace(vec![client.clone(), server.clone()], 10_000);
+        let failure = trace(vec![server, client], 40_000);
+
+        let diff = super::diff(
+            "test_id".to_string(),
+            "success".to_string(),
+            &success,
+            "failure".to_string(),
+            &failure,
+        );
+
+        assert_eq!(diff.spans.len(), 1);
+        assert_eq!(
+            diff.spans[0].path,
+            vec!["tests:test", "back:call", "back:query"]
+        );
+        assert_eq!(diff.spans[0].change, SpanChange::Changed);
+    }
+}
@@ -0,0 +1,386 @@
+use chrono::DateTime;
+
+use crate::opentracing::tags::{IkrellnTags, OpenTracingTag};
+use crate::opentracing::Span;
+
+#[derive(Debug, Deserialize)]
+struct Feature {
+    #[serde(default)]
+    uri: Option<String>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    elements: Vec<Element>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Element {
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    element_type: Option<String>,
+    #[serde(default)]
+    start_timestamp: Option<String>,
+    #[serde(default)]
+    before: Vec<Step>,
+    #[serde(default)]
+    steps: Vec<Step>,
+    #[serde(default)]
+    after: Vec<Step>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StepResult {
+    #[serde(default)]
+    status: Option<String>,
+    // in nanoseconds
+    #[serde(default)]
+    duration: Option<f64>,
+    #[serde(default)]
+    error_message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    cells: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DocString {
+    value: String,
+}
+
+// cucumber-js reports data tables and doc strings as step arguments
+#[derive(Debug, Deserialize)]
+struct Argument {
+    #[serde(default)]
+    rows: Option<Vec<Row>>,
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Step {
+    #[serde(default)]
+    keyword: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    result: StepResult,
+    #[serde(default)]
+    rows: Option<Vec<Row>>,
+    #[serde(default)]
+    doc_string: Option<DocString>,
+    #[serde(default)]
+    arguments: Vec<Argument>,
+}
+
+impl Step {
+    fn status(&self) -> &str {
+        self.result
+            .status
+            .as_ref()
+            .map_or("undefined", String::as_str)
+    }
+
+    fn duration(&self) -> i64 {
+        self.result
+            .duration
+            .map(|duration| (duration / 1000.) as i64)
+            .unwrap_or(0)
+    }
+
+    fn parameters(&self) -> Option<String> {
+        let rows_to_json = |rows: &[Row]| {
+            serde_json::to_string(&rows.iter().map(|row| &row.cells).collect::<Vec<_>>()).ok()
+        };
+        if let Some(rows) = &self.rows {
+            return rows_to_json(rows);
+        }
+        if let Some(doc_string) = &self.doc_string {
+            return Some(doc_string.value.clone());
+        }
+        self.arguments.iter().find_map(|argument| match argument {
+            Argument {
+                rows: Some(rows), ..
+            } => rows_to_json(rows),
+            Argument {
+                content: Some(content),
+                ..
+            } => Some(content.clone()),
+            _ => None,
+        })
+    }
+}
+
+fn parse_time(time: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|time| time.timestamp() * 1000 * 1000 + i64::from(time.timestamp_subsec_micros()))
+}
+
+fn scenario_result<'a>(steps: impl Iterator<Item = &'a Step>) -> &'static str {
+    let mut result = "success";
+    for step in steps {
+        match step.status() {
+            "passed" => (),
+            "skipped" | "pending" => result = "skipped",
+            _ => return "failure",
+        }
+    }
+    result
+}
+
+fn step_span(
+    step: &Step,
+    step_type: &str,
+    scenario: &Span,
+    service_name: &str,
+    timestamp: i64,
+) -> Span {
+    let name = match (&step.keyword, &step.name) {
+        (Some(keyword), Some(name)) => format!("{} {}", keyword.trim(), name),
+        (None, Some(name)) => name.clone(),
+        _ => step_type.to_string(),
+    };
+    let duration = step.duration();
+    let mut span = super::test_span(
+        &scenario.trace_id,
+        &super::new_id(),
+        Some(&scenario.id),
+        service_name,
+        &name,
+        timestamp,
+        duration,
+    )
+    .with_tag(IkrellnTags::StepType, step_type)
+    .with_tag(IkrellnTags::StepStatus, step.status());
+    if let Some(parameters) = step.parameters() {
+        span = span.with_tag(IkrellnTags::StepParameters, &parameters);
+    }
+    if step.status() == "failed" {
+        let error: &'static str = OpenTracingTag::Error.into();
+        span.tags.insert(error.to_string(), "true".to_string());
+        if let Some(message) = &step.result.error_message {
+            span = span.with_annotation(timestamp + duration, message.trim());
+        }
+    }
+    span
+}
+
+/// Build one synthetic trace per scenario of a Cucumber JSON report, with a child
+/// span for each step and hook. Background steps are run before the steps of the
+/// following scenario, and scenarios without a `start_timestamp` are considered to
+/// run one after the other starting at `default_timestamp`.
+pub fn parse(
+    report: &str,
+    environment: Option<&str>,
+    default_timestamp: i64,
+) -> Result<Vec<Span>, serde_json::Error> {
+    let features: Vec<Feature> = serde_json::from_str(report)?;
+
+    let mut timestamp = default_timestamp;
+    let mut spans = vec![];
+    for feature in features {
+        let class = feature.uri.as_ref().unwrap_or(&feature.name);
+        let mut background: Vec<Step> = vec![];
+        for element in feature.elements {
+            if element.element_type.as_deref() == Some("background") {
+                background.extend(element.steps);
+                continue;
+            }
+            let steps: Vec<(&Step, &str)> = {
+                let mut previous_keyword = "Given";
+                element
+                    .before
+                    .iter()
+                    .map(|hook| (hook, "Before"))
+                    .chain(background.iter().chain(element.steps.iter()).map(|step| {
+                        let keyword = step.keyword.as_ref().map_or("*", |keyword| keyword.trim());
+                        match keyword {
+                            "And" | "But" | "*" => (),
+                            keyword => previous_keyword = keyword,
+                        }
+                        (step, previous_keyword)
+                    }))
+                    .chain(element.after.iter().map(|hook| (hook, "After")))
+                    .collect()
+            };
+
+            let start = element
+                .start_timestamp
+                .as_ref()
+                .and_then(|time| parse_time(time))
+                .unwrap_or(timestamp);
+            let duration = steps.iter().map(|(step, _)| step.duration()).sum();
+            let result = scenario_result(steps.iter().map(|(step, _)| *step));
+
+            let trace_id = super::new_id();
+            let mut scenario = super::test_span(
+                &trace_id,
+                &trace_id,
+                None,
+                &feature.name,
+                &element.name,
+                start,
+                duration,
+            )
+            .with_tag(IkrellnTags::Suite, &feature.name)
+            .with_tag(IkrellnTags::Class, class)
+            .with_tag(IkrellnTags::Name, &element.name)
+            .with_tag(IkrellnTags::Result, result);
+            if let Some(environment) = environment {
+                scenario = scenario.with_tag(IkrellnTags::Environment, environment);
+            }
+            if result == "failure" {
+                let error: &'static str = OpenTracingTag::Error.into();
+                scenario.tags.insert(error.to_string(), "true".to_string());
+            }
+
+            let mut step_timestamp = start;
+            for (step, step_type) in steps {
+                let span = step_span(step, step_type, &scenario, &feature.name, step_timestamp);
+                step_timestamp += span.duration.unwrap_or(0);
+                spans.push(span);
+            }
+            if element.start_timestamp.is_none() {
+                timestamp += duration;
+            }
+            // the scenario is ingested after its steps so that its trace is complete
+            // once it is saved
+            spans.push(scenario);
+            background.clear();
+        }
+    }
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUCUMBER: &str = r#"[
+  {
+    "uri": "features/login.feature",
+    "id": "login",
+    "keyword": "Feature",
+    "name": "Login",
+    "elements": [
+      {
+        "keyword": "Background",
+        "name": "",
+        "type": "background",
+        "steps": [
+          {
+            "keyword": "Given ",
+            "name": "the users",
+            "result": { "status": "passed", "duration": 1000000 },
+            "rows": [{ "cells": ["name", "password"] }, { "cells": ["bob", "secret"] }]
+          }
+        ]
+      },
+      {
+        "id": "login;wrong-password",
+        "keyword": "Scenario",
+        "name": "Wrong password",
+        "type": "scenario",
+        "steps": [
+          {
+            "keyword": "And ",
+            "name": "bob is on the login page",
+            "result": { "status": "passed", "duration": 2000000 }
+          },
+          {
+            "keyword": "When ",
+            "name": "bob logs in with",
+            "result": { "status": "passed", "duration": 3000000 },
+            "doc_string": { "value": "wrong", "content_type": "" }
+          },
+          {
+            "keyword": "Then ",
+            "name": "bob is refused",
+            "result": { "status": "failed", "duration": 4000000, "error_message": "expected 401" }
+          },
+          {
+            "keyword": "But ",
+            "name": "bob can retry",
+            "result": { "status": "skipped" }
+          }
+        ],
+        "after": [
+          { "result": { "status": "passed", "duration": 500000 } }
+        ]
+      },
+      {
+        "id": "login;logout",
+        "keyword": "Scenario",
+        "name": "Logout",
+        "type": "scenario",
+        "steps": [
+          { "keyword": "Given ", "name": "bob is logged in", "result": { "status": "pending" } }
+        ]
+      }
+    ]
+  }
+]"#;
+
+    fn tag(span: &Span, tag: IkrellnTags) -> Option<&str> {
+        let tag: &'static str = tag.into();
+        span.tags.get(tag).map(String::as_str)
+    }
+
+    #[test]
+    fn can_parse_cucumber_report() {
+        let spans = parse(CUCUMBER, Some("ci"), 1000).unwrap();
+        assert_eq!(spans.len(), 9);
+
+        let scenario = &spans[6];
+        assert_eq!(scenario.parent_id, None);
+        assert_eq!(tag(scenario, IkrellnTags::Suite), Some("Login"));
+        assert_eq!(
+            tag(scenario, IkrellnTags::Class),
+            Some("features/login.feature")
+        );
+        assert_eq!(tag(scenario, IkrellnTags::Name), Some("Wrong password"));
+        assert_eq!(tag(scenario, IkrellnTags::Result), Some("failure"));
+        assert_eq!(tag(scenario, IkrellnTags::Environment), Some("ci"));
+        assert_eq!(scenario.timestamp, Some(1000));
+        assert_eq!(scenario.duration, Some(10_500));
+
+        let steps = &spans[..6];
+        assert!(steps
+            .iter()
+            .all(|step| step.parent_id == Some(scenario.id.clone())
+                && step.trace_id == scenario.trace_id));
+        assert_eq!(
+            steps
+                .iter()
+                .map(|step| tag(step, IkrellnTags::StepType).unwrap())
+                .collect::<Vec<_>>(),
+            vec!["Given", "Given", "When", "Then", "Then", "After"]
+        );
+        assert_eq!(
+            tag(&steps[0], IkrellnTags::StepParameters),
+            Some(r#"[["name","password"],["bob","secret"]]"#)
+        );
+        assert_eq!(
+            steps[1].name,
+            Some("And bob is on the login page".to_string())
+        );
+        assert_eq!(steps[1].timestamp, Some(2000));
+        assert_eq!(tag(&steps[2], IkrellnTags::StepParameters), Some("wrong"));
+        assert_eq!(tag(&steps[3], IkrellnTags::StepStatus), Some("failed"));
+        assert_eq!(steps[3].annotations[0].value, "expected 401");
+        assert_eq!(tag(&steps[4], IkrellnTags::StepStatus), Some("skipped"));
+
+        let logout = &spans[8];
+        assert_eq!(tag(logout, IkrellnTags::Result), Some("skipped"));
+        assert_eq!(logout.timestamp, Some(11_500));
+        assert_eq!(tag(&spans[7], IkrellnTags::StepType), Some("Given"));
+    }
+
+    #[test]
+    fn should_fail_on_invalid_report() {
+        assert!(parse("{ \"name\": \"not a list of features\" }", None, 0).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use chrono::DateTime;
+
+use crate::opentracing::tags::{IkrellnTags, OpenTracingTag};
+use crate::opentracing::Span;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TestEvent {
+    time: Option<String>,
+    action: String,
+    package: Option<String>,
+    test: Option<String>,
+    elapsed: Option<f64>,
+    output: Option<String>,
+}
+
+#[derive(Debug)]
+struct TestRun {
+    package: String,
+    test: String,
+    start: Option<i64>,
+    duration: i64,
+    result: Option<&'static str>,
+    output: Vec<String>,
+}
+
+const SUBTEST_STEP_TYPE: &str = "subtest";
+
+fn parse_time(time: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|time| time.timestamp() * 1000 * 1000 + i64::from(time.timestamp_subsec_micros()))
+}
+
+// lines added by go test around the output of the test itself
+fn is_framework_output(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("=== ") || line.starts_with("--- ")
+}
+
+fn read_test_runs(events: &str) -> Result<Vec<TestRun>, serde_json::Error> {
+    let mut runs: Vec<TestRun> = vec![];
+    let mut index_by_test = HashMap::new();
+    for line in events
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('{'))
+    {
+        let event: TestEvent = serde_json::from_str(line)?;
+        let (package, test) = match (event.package, event.test) {
+            (Some(package), Some(test)) => (package, test),
+            // package level events
+            _ => continue,
+        };
+        let index = *index_by_test
+            .entry((package.clone(), test.clone()))
+            .or_insert_with(|| {
+                runs.push(TestRun {
+                    package,
+                    test,
+                    start: None,
+                    duration: 0,
+                    result: None,
+                    output: vec![],
+                });
+                runs.len() - 1
+            });
+        let run = &mut runs[index];
+        match event.action.as_ref() {
+            "run" => run.start = event.time.as_ref().and_then(|time| parse_time(time)),
+            "output" => {
+                if let Some(output) = event.output.filter(|output| !is_framework_output(output)) {
+                    run.output.push(output);
+                }
+            }
+            "pass" | "fail" | "skip" => {
+                run.result = Some(match event.action.as_ref() {
+                    "pass" => "success",
+                    "skip" => "skipped",
+                    _ => "failure",
+                });
+                run.duration = event
+                    .elapsed
+                    .map(|elapsed| (elapsed * 1000. * 1000.) as i64)
+                    .unwrap_or(0);
+            }
+            _ => (),
+        }
+    }
+    Ok(runs)
+}
+
+/// Build one synthetic trace per top level test of a `go test -json` output, with
+/// a child span for each subtest. Tests without a start time are considered to run
+/// one after the other starting at `default_timestamp`.
+pub fn parse(
+    events: &str,
+    environment: Option<&str>,
+    default_timestamp: i64,
+) -> Result<Vec<Span>, serde_json::Error> {
+    let runs = read_test_runs(events)?;
+
+    let mut timestamp = default_timestamp;
+    // trace id and span id of each test, by package and test name
+    let mut ids: HashMap<(String, String), (String, String)> = HashMap::new();
+    let mut roots = vec![];
+    let mut subtests = vec![];
+    for run in runs {
+        let start = run.start.unwrap_or(timestamp);
+        if run.start.is_none() && !run.test.contains('/') {
+            timestamp += run.duration;
+        }
+        // a test that didn't finish crashed its package
+        let result = run.result.unwrap_or("failure");
+        let key = (run.package.clone(), run.test.clone());
+
+        let span = match run.test.rfind('/') {
+            None => {
+                let trace_id = super::new_id();
+                ids.insert(key, (trace_id.clone(), trace_id.clone()));
+                let mut span = super::test_span(
+                    &trace_id,
+                    &trace_id,
+                    None,
+                    &run.package,
+                    &run.test,
+                    start,
+                    run.duration,
+                )
+                .with_tag(IkrellnTags::Suite, &run.package)
+                .with_tag(IkrellnTags::Class, &run.test)
+                .with_tag(IkrellnTags::Name, &run.test)
+                .with_tag(IkrellnTags::Result, result);
+                if let Some(environment) = environment {
+                    span = span.with_tag(IkrellnTags::Environment, environment);
+                }
+                span
+            }
+            Some(_) => {
+                // subtests are reported after their parent was started, use the closest
+                // known parent
+                let parent = run
+                    .test
+                    .match_indices('/')
+                    .rev()
+                    .filter_map(|(index, _)| {
+                        ids.get(&(run.package.clone(), run.test[..index].to_string()))
+                    })
+                    .next()
+                    .cloned();
+                let (trace_id, parent_id) = match parent {
+                    Some(parent) => parent,
+                    None => continue,
+                };
+                let id = super::new_id();
+                ids.insert(key, (trace_id.clone(), id.clone()));
+                super::test_span(
+                    &trace_id,
+                    &id,
+                    Some(&parent_id),
+                    &run.package,
+                    &run.test,
+                    start,
+                    run.duration,
+                )
+                .with_tag(IkrellnTags::StepType, SUBTEST_STEP_TYPE)
+                .with_tag(IkrellnTags::StepStatus, result)
+            }
+        };
+
+        let mut span = span;
+        if result == "failure" {
+            let error: &'static str = OpenTracingTag::Error.into();
+            span.tags.insert(error.to_string(), "true".to_string());
+            let output = run.output.concat();
+            if !output.trim().is_empty() {
+                span = span.with_annotation(start + run.duration, output.trim());
+            }
+        }
+        if span.parent_id.is_none() {
+            roots.push(span);
+        } else {
+            subtests.push(span);
+        }
+    }
+
+    // root spans are ingested last so that their trace is complete once they are saved
+    subtests.extend(roots);
+    Ok(subtests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GO_TEST: &str = r#"
+{"Time":"2019-05-06T10:20:30.000001Z","Action":"run","Package":"example.com/auth","Test":"TestLogin"}
+{"Time":"2019-05-06T10:20:30.000002Z","Action":"output","Package":"example.com/auth","Test":"TestLogin","Output":"=== RUN   TestLogin\n"}
+{"Time":"2019-05-06T10:20:30.1Z","Action":"run","Package":"example.com/auth","Test":"TestLogin/wrong_password"}
+{"Time":"2019-05-06T10:20:30.2Z","Action":"output","Package":"example.com/auth","Test":"TestLogin/wrong_password","Output":"    auth_test.go:42: expected 401, got 200\n"}
+{"Time":"2019-05-06T10:20:30.3Z","Action":"fail","Package":"example.com/auth","Test":"TestLogin/wrong_password","Elapsed":0.2}
+{"Time":"2019-05-06T10:20:30.4Z","Action":"run","Package":"example.com/auth","Test":"TestLogin/ok"}
+{"Time":"2019-05-06T10:20:30.5Z","Action":"pass","Package":"example.com/auth","Test":"TestLogin/ok","Elapsed":0.1}
+{"Time":"2019-05-06T10:20:30.6Z","Action":"fail","Package":"example.com/auth","Test":"TestLogin","Elapsed":0.6}
+{"Time":"2019-05-06T10:20:30.7Z","Action":"run","Package":"example.com/auth","Test":"TestLogout"}
+{"Time":"2019-05-06T10:20:30.8Z","Action":"skip","Package":"example.com/auth","Test":"TestLogout","Elapsed":0}
+{"Time":"2019-05-06T10:20:30.9Z","Action":"fail","Package":"example.com/auth","Elapsed":0.9}
+"#;
+
+    fn tag(span: &Span, tag: IkrellnTags) -> Option<&str> {
+        let tag: &'static str = tag.into();
+        span.tags.get(tag).map(String::as_str)
+    }
+
+    #[test]
+    fn can_parse_go_test_events() {
+        let spans = parse(GO_TEST, None, 0).unwrap();
+        assert_eq!(spans.len(), 4);
+
+        // subtests first
+        let wrong_password = &spans[0];
+        let ok = &spans[1];
+        let login = &spans[2];
+        let logout = &spans[3];
+
+        assert_eq!(login.parent_id, None);
+        assert_eq!(login.timestamp, Some(1_557_138_030_000_001));
+        assert_eq!(login.duration, Some(600_000));
+        assert_eq!(tag(login, IkrellnTags::Suite), Some("example.com/auth"));
+        assert_eq!(tag(login, IkrellnTags::Class), Some("TestLogin"));
+        assert_eq!(tag(login, IkrellnTags::Result), Some("failure"));
+        // only the output of the test itself is kept
+        assert!(login.annotations.is_empty());
+
+        assert_eq!(wrong_password.trace_id, login.trace_id);
+        assert_eq!(wrong_password.parent_id, Some(login.id.clone()));
+        assert_eq!(
+            tag(wrong_password, IkrellnTags::StepType),
+            Some(SUBTEST_STEP_TYPE)
+        );
+        assert_eq!(
+            tag(wrong_password, IkrellnTags::StepStatus),
+            Some("failure")
+        );
+        assert_eq!(
+            wrong_password.annotations[0].value,
+            "auth_test.go:42: expected 401, got 200"
+        );
+        assert_eq!(tag(ok, IkrellnTags::StepStatus), Some("success"));
+        assert_eq!(ok.parent_id, Some(login.id.clone()));
+
+        assert_eq!(tag(logout, IkrellnTags::Result), Some("skipped"));
+        assert_ne!(logout.trace_id, login.trace_id);
+    }
+}
@@ -0,0 +1,181 @@
+use chrono::{DateTime, NaiveDateTime};
+use roxmltree::{Document, Node};
+
+use crate::opentracing::tags::{IkrellnTags, OpenTracingTag};
+use crate::opentracing::Span;
+
+fn parse_timestamp(timestamp: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|ts| ts.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%.f"))
+        .ok()
+        .map(|ts| ts.timestamp() * 1000 * 1000 + i64::from(ts.timestamp_subsec_micros()))
+}
+
+// time is in seconds, some tools add thousands separators
+fn parse_duration(time: Option<&str>) -> i64 {
+    time.and_then(|time| time.replace(",", "").parse::<f64>().ok())
+        .map(|time| (time * 1000. * 1000.) as i64)
+        .unwrap_or(0)
+}
+
+fn text_of(node: Node) -> Option<String> {
+    node.text()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(std::string::ToString::to_string)
+}
+
+fn test_case_to_span(
+    test_case: Node,
+    suite: &str,
+    environment: Option<&str>,
+    timestamp: i64,
+) -> Span {
+    let name = test_case.attribute("name").unwrap_or("unnamed");
+    let class = test_case.attribute("classname").unwrap_or(suite);
+    let duration = parse_duration(test_case.attribute("time"));
+
+    let trace_id = super::new_id();
+    let mut span = super::test_span(&trace_id, &trace_id, None, suite, name, timestamp, duration)
+        .with_tag(IkrellnTags::Suite, suite)
+        .with_tag(IkrellnTags::Class, class)
+        .with_tag(IkrellnTags::Name, name);
+    if let Some(environment) = environment {
+        span = span.with_tag(IkrellnTags::Environment, environment);
+    }
+
+    let mut result = "success";
+    for child in test_case.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "failure" | "error" => {
+                result = "failure";
+                let message = child
+                    .attribute("message")
+                    .map(std::string::ToString::to_string)
+                    .or_else(|| text_of(child))
+                    .or_else(|| {
+                        child
+                            .attribute("type")
+                            .map(std::string::ToString::to_string)
+                    })
+                    .unwrap_or_else(|| child.tag_name().name().to_string());
+                span = span.with_annotation(timestamp + duration, &message);
+            }
+            "skipped" => result = "skipped",
+            "system-out" => {
+                if let Some(output) = text_of(child) {
+                    span = span.with_annotation(timestamp + duration, &output);
+                }
+            }
+            _ => (),
+        }
+    }
+    if result == "failure" {
+        let error: &'static str = OpenTracingTag::Error.into();
+        span.tags.insert(error.to_string(), "true".to_string());
+    }
+    span.with_tag(IkrellnTags::Result, result)
+}
+
+/// Build one synthetic trace per `testcase` of a JUnit XML report. Test cases of a
+/// suite are considered to run one after the other starting at the suite `timestamp`,
+/// or at `default_timestamp` when the suite doesn't have one.
+pub fn parse(
+    xml: &str,
+    environment: Option<&str>,
+    default_timestamp: i64,
+) -> Result<Vec<Span>, roxmltree::Error> {
+    let document = Document::parse(xml)?;
+    let mut spans = vec![];
+    for test_suite in document
+        .descendants()
+        .filter(|node| node.has_tag_name("testsuite"))
+    {
+        let suite = test_suite.attribute("name").unwrap_or("junit");
+        let mut timestamp = test_suite
+            .attribute("timestamp")
+            .and_then(parse_timestamp)
+            .unwrap_or(default_timestamp);
+        for test_case in test_suite
+            .children()
+            .filter(|node| node.has_tag_name("testcase"))
+        {
+            let span = test_case_to_span(test_case, suite, environment, timestamp);
+            timestamp += span.duration.unwrap_or(0);
+            spans.push(span);
+        }
+    }
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JUNIT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="auth" tests="3" timestamp="2019-05-06T10:20:30">
+    <testcase classname="tests.LoginTest" name="can_login" time="1.5">
+      <system-out>logged in</system-out>
+    </testcase>
+    <testcase classname="tests.LoginTest" name="cannot_login" time="0.25">
+      <failure message="expected 401" type="AssertionError">stack trace</failure>
+    </testcase>
+    <testcase classname="tests.LoginTest" name="can_logout">
+      <skipped/>
+    </testcase>
+  </testsuite>
+</testsuites>"#;
+
+    fn tag(span: &Span, tag: IkrellnTags) -> Option<String> {
+        let tag: &'static str = tag.into();
+        span.tags.get(tag).cloned()
+    }
+
+    #[test]
+    fn can_parse_junit_report() {
+        let spans = parse(JUNIT, Some("ci"), 0).unwrap();
+        assert_eq!(spans.len(), 3);
+
+        assert_eq!(spans[0].timestamp, Some(1_557_138_030_000_000));
+        assert_eq!(spans[0].duration, Some(1_500_000));
+        assert_eq!(tag(&spans[0], IkrellnTags::Suite), Some("auth".to_string()));
+        assert_eq!(
+            tag(&spans[0], IkrellnTags::Class),
+            Some("tests.LoginTest".to_string())
+        );
+        assert_eq!(
+            tag(&spans[0], IkrellnTags::Name),
+            Some("can_login".to_string())
+        );
+        assert_eq!(
+            tag(&spans[0], IkrellnTags::Result),
+            Some("success".to_string())
+        );
+        assert_eq!(
+            tag(&spans[0], IkrellnTags::Environment),
+            Some("ci".to_string())
+        );
+        assert_eq!(spans[0].annotations[0].value, "logged in");
+
+        assert_eq!(spans[1].timestamp, Some(1_557_138_031_500_000));
+        assert_eq!(
+            tag(&spans[1], IkrellnTags::Result),
+            Some("failure".to_string())
+        );
+        assert_eq!(spans[1].annotations[0].value, "expected 401");
+
+        assert_eq!(
+            tag(&spans[2], IkrellnTags::Result),
+            Some("skipped".to_string())
+        );
+        assert_eq!(spans[2].duration, Some(0));
+        assert!(spans.iter().all(|span| span.parent_id.is_none()));
+    }
+
+    #[test]
+    fn should_fail_on_invalid_xml() {
+        assert!(parse("<testsuite><testcase></testsuite>", None, 0).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use crate::opentracing::tags::{IkrellnTags, OpenTracingTag};
+use crate::opentracing::Span;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Event {
+    Test {
+        event: String,
+        name: String,
+        #[serde(default)]
+        exec_time: Option<serde_json::Value>,
+        #[serde(default)]
+        stdout: Option<String>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+// exec_time is a number of seconds, or a string like "0.001s" in older toolchains
+fn parse_exec_time(exec_time: Option<serde_json::Value>) -> i64 {
+    let seconds = match exec_time {
+        Some(serde_json::Value::Number(seconds)) => seconds.as_f64(),
+        Some(serde_json::Value::String(seconds)) => seconds.trim_end_matches('s').parse().ok(),
+        _ => None,
+    };
+    seconds
+        .map(|seconds| (seconds * 1000. * 1000.) as i64)
+        .unwrap_or(0)
+}
+
+/// Build one synthetic trace per finished test of a libtest json event stream
+/// (`cargo test -- -Z unstable-options --format json --report-time`). Test path
+/// `module::test` gives the class and the name of the test, and tests are
+/// considered to run one after the other starting at `default_timestamp`.
+pub fn parse(
+    events: &str,
+    crate_name: &str,
+    environment: Option<&str>,
+    default_timestamp: i64,
+) -> Result<Vec<Span>, serde_json::Error> {
+    let mut timestamp = default_timestamp;
+    let mut spans = vec![];
+    for line in events
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('{'))
+    {
+        let (event, name, exec_time, stdout) = match serde_json::from_str(line)? {
+            Event::Test {
+                event,
+                name,
+                exec_time,
+                stdout,
+            } => (event, name, exec_time, stdout),
+            Event::Other => continue,
+        };
+        let result = match event.as_ref() {
+            "ok" => "success",
+            "failed" => "failure",
+            "ignored" => "skipped",
+            // "timeout" only warns that a test runs for long, it is followed by its result
+            _ => continue,
+        };
+
+        let (class, test_name) = match name.rfind("::") {
+            Some(index) => (&name[..index], &name[index + 2..]),
+            None => (crate_name, name.as_ref()),
+        };
+        let duration = parse_exec_time(exec_time);
+        let trace_id = super::new_id();
+        let mut span = super::test_span(
+            &trace_id, &trace_id, None, crate_name, test_name, timestamp, duration,
+        )
+        .with_tag(IkrellnTags::Suite, crate_name)
+        .with_tag(IkrellnTags::Class, class)
+        .with_tag(IkrellnTags::Name, test_name)
+        .with_tag(IkrellnTags::Result, result);
+        if let Some(environment) = environment {
+            span = span.with_tag(IkrellnTags::Environment, environment);
+        }
+        if result == "failure" {
+            let error: &'static str = OpenTracingTag::Error.into();
+            span.tags.insert(error.to_string(), "true".to_string());
+            if let Some(stdout) = stdout.filter(|stdout| !stdout.trim().is_empty()) {
+                span = span.with_annotation(timestamp + duration, stdout.trim());
+            }
+        }
+        timestamp += duration;
+        spans.push(span);
+    }
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBTEST: &str = r#"
+{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "engine::span::tests::can_deserialize" }
+{ "type": "test", "event": "started", "name": "can_fail" }
+{ "type": "test", "name": "engine::span::tests::can_deserialize", "event": "ok", "exec_time": 0.25 }
+{ "type": "test", "name": "can_fail", "event": "failed", "exec_time": "0.5s", "stdout": "thread 'can_fail' panicked at 'boom'\n" }
+{ "type": "test", "event": "ignored", "name": "engine::slow" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1, "measured": 0, "filtered_out": 0, "exec_time": 0.75 }
+"#;
+
+    fn tag(span: &Span, tag: IkrellnTags) -> Option<&str> {
+        let tag: &'static str = tag.into();
+        span.tags.get(tag).map(String::as_str)
+    }
+
+    #[test]
+    fn can_parse_libtest_events() {
+        let spans = parse(LIBTEST, "ikrelln", None, 1000).unwrap();
+        assert_eq!(spans.len(), 3);
+
+        assert_eq!(tag(&spans[0], IkrellnTags::Suite), Some("ikrelln"));
+        assert_eq!(
+            tag(&spans[0], IkrellnTags::Class),
+            Some("engine::span::tests")
+        );
+        assert_eq!(tag(&spans[0], IkrellnTags::Name), Some("can_deserialize"));
+        assert_eq!(tag(&spans[0], IkrellnTags::Result), Some("success"));
+        assert_eq!(spans[0].timestamp, Some(1000));
+        assert_eq!(spans[0].duration, Some(250_000));
+
+        assert_eq!(tag(&spans[1], IkrellnTags::Class), Some("ikrelln"));
+        assert_eq!(tag(&spans[1], IkrellnTags::Result), Some("failure"));
+        assert_eq!(spans[1].timestamp, Some(251_000));
+        assert_eq!(spans[1].duration, Some(500_000));
+        assert_eq!(
+            spans[1].annotations[0].value,
+            "thread 'can_fail' panicked at 'boom'"
+        );
+
+        assert_eq!(tag(&spans[2], IkrellnTags::Result), Some("skipped"));
+    }
+
+    #[test]
+    fn should_ignore_timeout_warning() {
+        let events = r#"
+{ "type": "test", "event": "started", "name": "slow" }
+{ "type": "test", "event": "timeout", "name": "slow" }
+{ "type": "test", "name": "slow", "event": "ok", "exec_time": 75.0 }
+"#;
+        let spans = parse(events, "ikrelln", None, 0).unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(tag(&spans[0], IkrellnTags::Result), Some("success"));
+        assert_eq!(spans[0].duration, Some(75_000_000));
+    }
+
+    #[test]
+    fn should_fail_on_invalid_event() {
+        assert!(parse("{ \"type\": \"test\" ", "ikrelln", None, 0).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use crate::opentracing::span::{Annotation, Endpoint};
+use crate::opentracing::tags::IkrellnTags;
+use crate::opentracing::Span;
+
+pub mod cucumber;
+pub mod gotest;
+pub mod junit;
+pub mod libtest;
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().to_simple().to_string()[..16].to_string()
+}
+
+/// Build a span for a test or a test step, identified by `id`, that will go through
+/// the `Ingestor` like any traced test
+fn test_span(
+    trace_id: &str,
+    id: &str,
+    parent_id: Option<&str>,
+    service_name: &str,
+    name: &str,
+    timestamp: i64,
+    duration: i64,
+) -> Span {
+    Span {
+        trace_id: trace_id.to_string(),
+        id: id.to_string(),
+        parent_id: parent_id.map(std::string::ToString::to_string),
+        name: Some(name.to_string()),
+        kind: None,
+        duration: Some(duration),
+        timestamp: Some(timestamp),
+        debug: false,
+        shared: false,
+        local_endpoint: Some(Endpoint {
+            service_name: Some(service_name.to_string()),
+            ..Default::default()
+        }),
+        remote_endpoint: None,
+        annotations: vec![],
+        tags: HashMap::new(),
+        binary_annotations: vec![],
+    }
+}
+
+impl Span {
+    fn with_tag(mut self, tag: IkrellnTags, value: &str) -> Span {
+        let tag: &'static str = tag.into();
+        self.tags.insert(tag.to_string(), value.to_string());
+        self
+    }
+
+    fn with_annotation(mut self, timestamp: i64, value: &str) -> Span {
+        self.annotations.push(Annotation {
+            timestamp,
+            value: value.to_string(),
+            endpoint: None,
+        });
+        self
+    }
+}
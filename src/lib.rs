@@ -15,6 +15,9 @@ extern crate failure;
 #[macro_use]
 extern crate diesel;
 
+#[macro_use]
+extern crate diesel_migrations;
+
 #[cfg(feature = "python")]
 extern crate cpython;
 
@@ -25,18 +28,15 @@ mod build_info;
 mod config;
 mod db;
 pub mod engine;
+mod import;
 pub mod opentracing;
 
 lazy_static! {
     static ref DB_EXECUTOR_POOL: actix::Addr<db::update::DbExecutor> = {
         let config = crate::config::Config::load();
         actix::SyncArbiter::start(1, move || {
-            if let Ok(connection) = db::update::establish_connection(&config.db_url) {
-                db::update::DbExecutor(Some(connection))
-            } else {
-                error!("error opening connection to DB");
-                db::update::DbExecutor(None)
-            }
+            // the URL was checked when migrating at startup
+            db::update::DbExecutor(db::open_storage(&config.db_url).expect("invalid DB URL"))
         })
     };
 }
@@ -45,12 +45,7 @@ lazy_static! {
     static ref DB_READ_EXECUTOR_POOL: actix::Addr<db::read::DbReadExecutor> = {
         let config = crate::config::Config::load();
         actix::SyncArbiter::start(3, move || {
-            if let Ok(connection) = db::read::establish_connection(&config.db_url) {
-                db::read::DbReadExecutor(Some(connection))
-            } else {
-                error!("error opening read connection to DB");
-                db::read::DbReadExecutor(None)
-            }
+            db::read::DbReadExecutor(db::open_storage(&config.db_url).expect("invalid DB URL"))
         })
     };
 }
@@ -60,9 +55,33 @@ lazy_static! {
     static ref CONFIG: config::Config = config::Config::load();
 }
 
+/// Apply the embedded migrations to the configured database
+pub fn migrate() -> Result<(), failure::Error> {
+    db::migrate(&CONFIG.db_url).map_err(Into::into)
+}
+
 pub fn start_server() {
     info!("Starting i'Krelln with config: {:?}", *CONFIG);
 
+    // executors must not start on a schema they don't know
+    if let Err(err) = migrate() {
+        error!("{}", err);
+        std::process::exit(1);
+    }
+    if CONFIG.migrate_only {
+        info!("DB migrated, exiting");
+        return;
+    }
+    // ingests in flight when the server stopped will never finish
+    match db::interrupt_unfinished_ingests(&CONFIG.db_url) {
+        Ok(0) => (),
+        Ok(nb_interrupted) => warn!(
+            "marked {} unfinished ingests as interrupted",
+            nb_interrupted
+        ),
+        Err(err) => error!("{}", err),
+    }
+
     let system = actix::System::new("i'Krelln");
 
     match std::env::var("LISTEN_FD") {
@@ -70,6 +89,10 @@ pub fn start_server() {
         _ => api::serve(&CONFIG.host, CONFIG.port),
     }
 
+    if let Some(jaeger_agent) = &CONFIG.jaeger_agent {
+        api::jaeger_agent::serve(&jaeger_agent.host, jaeger_agent.port);
+    }
+
     //actix::System::current().registry()
     actix::System::current()
         .registry()
@@ -0,0 +1,528 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use thrift::protocol::{TCompactInputProtocol, TInputProtocol, TType};
+
+use super::span::{Annotation, Endpoint, Kind, Span};
+use super::zipkin_thrift::{
+    id_to_hex, read_list, read_struct, BoundedInputProtocol, LengthEncoding,
+};
+
+// Structs from jaeger.thrift and agent.thrift
+// https://github.com/jaegertracing/jaeger-idl/tree/master/thrift
+
+// TagType enum of a Tag
+const TAG_TYPE_DOUBLE: i32 = 1;
+const TAG_TYPE_BOOL: i32 = 2;
+const TAG_TYPE_LONG: i32 = 3;
+const TAG_TYPE_BINARY: i32 = 4;
+
+// SpanRefType enum of a SpanRef
+const SPAN_REF_CHILD_OF: i32 = 0;
+
+const FLAG_DEBUG: i32 = 2;
+
+const TAG_SPAN_KIND: &str = "span.kind";
+const TAG_PEER_SERVICE: &str = "peer.service";
+const PROCESS_TAG_IP: &str = "ip";
+
+#[derive(Default)]
+struct Tag {
+    key: String,
+    value: String,
+}
+
+struct SpanRef {
+    ref_type: i32,
+    span_id: i64,
+}
+
+struct Process {
+    service_name: String,
+    tags: Vec<Tag>,
+}
+
+fn read_tag(i_prot: &mut dyn TInputProtocol) -> thrift::Result<Tag> {
+    let mut tag = Tag::default();
+    let mut tag_type = 0;
+    let mut v_str = String::new();
+    let mut v_double = 0.0;
+    let mut v_bool = false;
+    let mut v_long = 0;
+    let mut v_binary = vec![];
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::String) => tag.key = i_prot.read_string()?,
+            (2, TType::I32) => tag_type = i_prot.read_i32()?,
+            (3, TType::String) => v_str = i_prot.read_string()?,
+            (4, TType::Double) => v_double = i_prot.read_double()?,
+            (5, TType::Bool) => v_bool = i_prot.read_bool()?,
+            (6, TType::I64) => v_long = i_prot.read_i64()?,
+            (7, TType::String) => v_binary = i_prot.read_bytes()?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    tag.value = match tag_type {
+        TAG_TYPE_DOUBLE => v_double.to_string(),
+        TAG_TYPE_BOOL => v_bool.to_string(),
+        TAG_TYPE_LONG => v_long.to_string(),
+        TAG_TYPE_BINARY => base64::encode(&v_binary),
+        _ => v_str,
+    };
+    Ok(tag)
+}
+
+fn read_span_ref(i_prot: &mut dyn TInputProtocol) -> thrift::Result<SpanRef> {
+    let mut span_ref = SpanRef {
+        ref_type: SPAN_REF_CHILD_OF,
+        span_id: 0,
+    };
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::I32) => span_ref.ref_type = i_prot.read_i32()?,
+            (4, TType::I64) => span_ref.span_id = i_prot.read_i64()?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(span_ref)
+}
+
+fn read_log(i_prot: &mut dyn TInputProtocol) -> thrift::Result<Annotation> {
+    let mut timestamp = 0;
+    let mut fields = vec![];
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::I64) => timestamp = i_prot.read_i64()?,
+            (2, TType::List) => fields = read_list(i_prot, read_tag)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    // a log with only an event is kept as is, other logs are serialized as json
+    let value = match fields.as_slice() {
+        [Tag { key, value }] if key == "event" => value.clone(),
+        _ => serde_json::to_string(
+            &fields
+                .into_iter()
+                .map(|tag| (tag.key, tag.value))
+                .collect::<HashMap<String, String>>(),
+        )
+        .unwrap_or_default(),
+    };
+    Ok(Annotation {
+        timestamp,
+        value,
+        endpoint: None,
+    })
+}
+
+fn read_process(i_prot: &mut dyn TInputProtocol) -> thrift::Result<Process> {
+    let mut process = Process {
+        service_name: String::new(),
+        tags: vec![],
+    };
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::String) => process.service_name = i_prot.read_string()?,
+            (2, TType::List) => process.tags = read_list(i_prot, read_tag)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(process)
+}
+
+fn read_span(i_prot: &mut dyn TInputProtocol) -> thrift::Result<Span> {
+    let mut trace_id_low = 0;
+    let mut trace_id_high = 0;
+    let mut parent_span_id = 0;
+    let mut references = vec![];
+    let mut flags = 0;
+    let mut tags = vec![];
+    let mut span = Span {
+        trace_id: String::new(),
+        id: String::new(),
+        parent_id: None,
+        name: None,
+        kind: None,
+        duration: None,
+        timestamp: None,
+        debug: false,
+        shared: false,
+        local_endpoint: None,
+        remote_endpoint: None,
+        annotations: vec![],
+        tags: HashMap::new(),
+        binary_annotations: vec![],
+    };
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::I64) => trace_id_low = i_prot.read_i64()?,
+            (2, TType::I64) => trace_id_high = i_prot.read_i64()?,
+            (3, TType::I64) => span.id = id_to_hex(i_prot.read_i64()?),
+            (4, TType::I64) => parent_span_id = i_prot.read_i64()?,
+            (5, TType::String) => span.name = Some(i_prot.read_string()?),
+            (6, TType::List) => references = read_list(i_prot, read_span_ref)?,
+            (7, TType::I32) => flags = i_prot.read_i32()?,
+            (8, TType::I64) => span.timestamp = Some(i_prot.read_i64()?),
+            (9, TType::I64) => span.duration = Some(i_prot.read_i64()?),
+            (10, TType::List) => tags = read_list(i_prot, read_tag)?,
+            (11, TType::List) => span.annotations = read_list(i_prot, read_log)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+
+    span.trace_id = match trace_id_high {
+        0 => id_to_hex(trace_id_low),
+        high => format!("{}{}", id_to_hex(high), id_to_hex(trace_id_low)),
+    };
+    // parent span id is deprecated in favor of references
+    span.parent_id = match parent_span_id {
+        0 => references
+            .iter()
+            .find(|span_ref| span_ref.ref_type == SPAN_REF_CHILD_OF)
+            .or_else(|| references.first())
+            .map(|span_ref| id_to_hex(span_ref.span_id)),
+        parent_span_id => Some(id_to_hex(parent_span_id)),
+    };
+    span.debug = flags & FLAG_DEBUG != 0;
+    for tag in tags {
+        match tag.key.as_ref() {
+            TAG_SPAN_KIND => {
+                span.kind = match tag.value.as_ref() {
+                    "client" => Some(Kind::CLIENT),
+                    "server" => Some(Kind::SERVER),
+                    "producer" => Some(Kind::PRODUCER),
+                    "consumer" => Some(Kind::CONSUMER),
+                    _ => None,
+                }
+            }
+            TAG_PEER_SERVICE => {
+                span.remote_endpoint = Some(Endpoint {
+                    service_name: Some(tag.value),
+                    ..Default::default()
+                })
+            }
+            _ => {
+                span.tags.insert(tag.key, tag.value);
+            }
+        }
+    }
+    Ok(span)
+}
+
+fn read_batch(i_prot: &mut dyn TInputProtocol) -> thrift::Result<Vec<Span>> {
+    let mut process = None;
+    let mut spans = vec![];
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::Struct) => process = Some(read_process(i_prot)?),
+            (2, TType::List) => spans = read_list(i_prot, read_span)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+
+    if let Some(process) = process {
+        let local_endpoint = Endpoint {
+            service_name: Some(process.service_name).filter(|name| !name.is_empty()),
+            ipv4: process
+                .tags
+                .iter()
+                .find(|tag| tag.key == PROCESS_TAG_IP)
+                .and_then(|tag| {
+                    tag.value.parse::<Ipv4Addr>().ok().or_else(|| {
+                        tag.value
+                            .parse::<i64>()
+                            .ok()
+                            .map(|ip| Ipv4Addr::from(ip as u32))
+                    })
+                })
+                .map(|ip| ip.to_string()),
+            ..Default::default()
+        };
+        for span in &mut spans {
+            span.local_endpoint = Some(local_endpoint.clone());
+            for tag in &process.tags {
+                if tag.key != PROCESS_TAG_IP && !span.tags.contains_key(&tag.key) {
+                    span.tags.insert(tag.key.clone(), tag.value.clone());
+                }
+            }
+        }
+    }
+    Ok(spans)
+}
+
+/// Decode an `emitBatch` call from a Jaeger client, encoded with TCompactProtocol
+pub fn decode_emit_batch(body: &[u8]) -> thrift::Result<Vec<Span>> {
+    let mut i_prot =
+        BoundedInputProtocol::new(body, LengthEncoding::Varint, TCompactInputProtocol::new);
+    let message = i_prot.read_message_begin()?;
+    if message.name != "emitBatch" {
+        return Err(thrift::Error::Protocol(thrift::ProtocolError::new(
+            thrift::ProtocolErrorKind::InvalidData,
+            format!("unsupported method {}", message.name),
+        )));
+    }
+    let mut spans = vec![];
+    read_struct(&mut i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::Struct) => spans = read_batch(i_prot)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    i_prot.read_message_end()?;
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use thrift::protocol::{
+        TCompactOutputProtocol, TFieldIdentifier, TListIdentifier, TMessageIdentifier,
+        TMessageType, TOutputProtocol, TStructIdentifier,
+    };
+
+    use super::*;
+
+    fn write_field<F>(o_prot: &mut dyn TOutputProtocol, id: i16, field_type: TType, write: F)
+    where
+        F: FnOnce(&mut dyn TOutputProtocol),
+    {
+        o_prot
+            .write_field_begin(&TFieldIdentifier::new::<_, String, _>(None, field_type, id))
+            .unwrap();
+        write(o_prot);
+        o_prot.write_field_end().unwrap();
+    }
+
+    fn write_string_tag(o_prot: &mut dyn TOutputProtocol, key: &str, value: &str) {
+        o_prot
+            .write_struct_begin(&TStructIdentifier::new("Tag"))
+            .unwrap();
+        write_field(o_prot, 1, TType::String, |o| o.write_string(key).unwrap());
+        write_field(o_prot, 2, TType::I32, |o| o.write_i32(0).unwrap());
+        write_field(o_prot, 3, TType::String, |o| o.write_string(value).unwrap());
+        o_prot.write_field_stop().unwrap();
+        o_prot.write_struct_end().unwrap();
+    }
+
+    fn encode_emit_batch() -> Vec<u8> {
+        let mut body = vec![];
+        {
+            let mut o_prot = TCompactOutputProtocol::new(&mut body);
+            let o_prot: &mut dyn TOutputProtocol = &mut o_prot;
+            o_prot
+                .write_message_begin(&TMessageIdentifier::new(
+                    "emitBatch",
+                    TMessageType::OneWay,
+                    1,
+                ))
+                .unwrap();
+            o_prot
+                .write_struct_begin(&TStructIdentifier::new("emitBatch_args"))
+                .unwrap();
+            write_field(o_prot, 1, TType::Struct, |o| {
+                o.write_struct_begin(&TStructIdentifier::new("Batch"))
+                    .unwrap();
+                write_field(o, 1, TType::Struct, |o| {
+                    o.write_struct_begin(&TStructIdentifier::new("Process"))
+                        .unwrap();
+                    write_field(o, 1, TType::String, |o| {
+                        o.write_string("test-runner").unwrap()
+                    });
+                    write_field(o, 2, TType::List, |o| {
+                        o.write_list_begin(&TListIdentifier::new(TType::Struct, 2))
+                            .unwrap();
+                        write_string_tag(o, "ip", "10.0.0.1");
+                        write_string_tag(o, "jaeger.version", "Go-2.20.1");
+                        o.write_list_end().unwrap();
+                    });
+                    o.write_field_stop().unwrap();
+                    o.write_struct_end().unwrap();
+                });
+                write_field(o, 2, TType::List, |o| {
+                    o.write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                        .unwrap();
+                    o.write_struct_begin(&TStructIdentifier::new("Span"))
+                        .unwrap();
+                    write_field(o, 1, TType::I64, |o| o.write_i64(0x5af7).unwrap());
+                    write_field(o, 2, TType::I64, |o| o.write_i64(0).unwrap());
+                    write_field(o, 3, TType::I64, |o| o.write_i64(0x352b).unwrap());
+                    write_field(o, 4, TType::I64, |o| o.write_i64(0).unwrap());
+                    write_field(o, 5, TType::String, |o| {
+                        o.write_string("test_jaeger").unwrap()
+                    });
+                    write_field(o, 6, TType::List, |o| {
+                        o.write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                            .unwrap();
+                        o.write_struct_begin(&TStructIdentifier::new("SpanRef"))
+                            .unwrap();
+                        write_field(o, 1, TType::I32, |o| {
+                            o.write_i32(SPAN_REF_CHILD_OF).unwrap()
+                        });
+                        write_field(o, 2, TType::I64, |o| o.write_i64(0x5af7).unwrap());
+                        write_field(o, 3, TType::I64, |o| o.write_i64(0).unwrap());
+                        write_field(o, 4, TType::I64, |o| o.write_i64(0x5af7).unwrap());
+                        o.write_field_stop().unwrap();
+                        o.write_struct_end().unwrap();
+                        o.write_list_end().unwrap();
+                    });
+                    write_field(o, 7, TType::I32, |o| o.write_i32(1).unwrap());
+                    write_field(o, 8, TType::I64, |o| o.write_i64(1_000_000).unwrap());
+                    write_field(o, 9, TType::I64, |o| o.write_i64(1500).unwrap());
+                    write_field(o, 10, TType::List, |o| {
+                        o.write_list_begin(&TListIdentifier::new(TType::Struct, 3))
+                            .unwrap();
+                        write_string_tag(o, "span.kind", "client");
+                        write_string_tag(o, "test.class", "JaegerTest");
+                        o.write_struct_begin(&TStructIdentifier::new("Tag"))
+                            .unwrap();
+                        write_field(o, 1, TType::String, |o| o.write_string("error").unwrap());
+                        write_field(o, 2, TType::I32, |o| o.write_i32(TAG_TYPE_BOOL).unwrap());
+                        write_field(o, 5, TType::Bool, |o| o.write_bool(true).unwrap());
+                        o.write_field_stop().unwrap();
+                        o.write_struct_end().unwrap();
+                        o.write_list_end().unwrap();
+                    });
+                    write_field(o, 11, TType::List, |o| {
+                        o.write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                            .unwrap();
+                        o.write_struct_begin(&TStructIdentifier::new("Log"))
+                            .unwrap();
+                        write_field(o, 1, TType::I64, |o| o.write_i64(1_000_100).unwrap());
+                        write_field(o, 2, TType::List, |o| {
+                            o.write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                                .unwrap();
+                            write_string_tag(o, "event", "assertion failed");
+                            o.write_list_end().unwrap();
+                        });
+                        o.write_field_stop().unwrap();
+                        o.write_struct_end().unwrap();
+                        o.write_list_end().unwrap();
+                    });
+                    o.write_field_stop().unwrap();
+                    o.write_struct_end().unwrap();
+                    o.write_list_end().unwrap();
+                });
+                o.write_field_stop().unwrap();
+                o.write_struct_end().unwrap();
+            });
+            o_prot.write_field_stop().unwrap();
+            o_prot.write_struct_end().unwrap();
+            o_prot.write_message_end().unwrap();
+        }
+        body
+    }
+
+    #[test]
+    fn can_decode_emit_batch() {
+        let spans = decode_emit_batch(&encode_emit_batch()).unwrap();
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(span.trace_id, "0000000000005af7");
+        assert_eq!(span.id, "000000000000352b");
+        assert_eq!(span.parent_id, Some("0000000000005af7".to_string()));
+        assert_eq!(span.name, Some("test_jaeger".to_string()));
+        match span.kind {
+            Some(Kind::CLIENT) => (),
+            _ => panic!("span should be a client span"),
+        }
+        assert_eq!(span.timestamp, Some(1_000_000));
+        assert_eq!(span.duration, Some(1500));
+        let local_endpoint = span.local_endpoint.clone().unwrap();
+        assert_eq!(local_endpoint.service_name, Some("test-runner".to_string()));
+        assert_eq!(local_endpoint.ipv4, Some("10.0.0.1".to_string()));
+        assert_eq!(span.tags.get("test.class"), Some(&"JaegerTest".to_string()));
+        assert_eq!(span.tags.get("error"), Some(&"true".to_string()));
+        assert_eq!(
+            span.tags.get("jaeger.version"),
+            Some(&"Go-2.20.1".to_string())
+        );
+        assert!(!span.tags.contains_key("span.kind"));
+        assert_eq!(span.annotations.len(), 1);
+        assert_eq!(span.annotations[0].value, "assertion failed");
+    }
+
+    #[test]
+    fn should_refuse_list_larger_than_datagram() {
+        let mut body = vec![];
+        {
+            let mut o_prot = TCompactOutputProtocol::new(&mut body);
+            let o_prot: &mut dyn TOutputProtocol = &mut o_prot;
+            o_prot
+                .write_message_begin(&TMessageIdentifier::new(
+                    "emitBatch",
+                    TMessageType::OneWay,
+                    1,
+                ))
+                .unwrap();
+            o_prot
+                .write_struct_begin(&TStructIdentifier::new("emitBatch_args"))
+                .unwrap();
+            write_field(o_prot, 1, TType::Struct, |o| {
+                o.write_struct_begin(&TStructIdentifier::new("Batch"))
+                    .unwrap();
+                write_field(o, 2, TType::List, |o| {
+                    o.write_list_begin(&TListIdentifier::new(TType::Struct, i32::MAX))
+                        .unwrap();
+                });
+            });
+        }
+        assert!(decode_emit_batch(&body).is_err());
+    }
+
+    #[test]
+    fn should_refuse_string_larger_than_datagram() {
+        let mut body = vec![];
+        {
+            let mut o_prot = TCompactOutputProtocol::new(&mut body);
+            let o_prot: &mut dyn TOutputProtocol = &mut o_prot;
+            o_prot
+                .write_message_begin(&TMessageIdentifier::new(
+                    "emitBatch",
+                    TMessageType::OneWay,
+                    1,
+                ))
+                .unwrap();
+            o_prot
+                .write_struct_begin(&TStructIdentifier::new("emitBatch_args"))
+                .unwrap();
+            write_field(o_prot, 1, TType::Struct, |o| {
+                o.write_struct_begin(&TStructIdentifier::new("Batch"))
+                    .unwrap();
+                write_field(o, 1, TType::Struct, |o| {
+                    o.write_struct_begin(&TStructIdentifier::new("Process"))
+                        .unwrap();
+                    // a 4 GiB service name
+                    write_field(o, 1, TType::String, |o| {
+                        o.write_bytes(&[]).unwrap();
+                    });
+                });
+            });
+        }
+        // replace the empty length varint with 0xffffffff
+        body.pop();
+        body.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0x0f]);
+        assert!(decode_emit_batch(&body).is_err());
+    }
+
+    #[test]
+    fn should_refuse_other_methods() {
+        let mut body = vec![];
+        {
+            let mut o_prot = TCompactOutputProtocol::new(&mut body);
+            o_prot
+                .write_message_begin(&TMessageIdentifier::new(
+                    "emitZipkinBatch",
+                    TMessageType::OneWay,
+                    1,
+                ))
+                .unwrap();
+        }
+        assert!(decode_emit_batch(&body).is_err());
+    }
+}
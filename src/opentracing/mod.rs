@@ -1,4 +1,9 @@
+pub mod jaeger_thrift;
+pub mod otlp;
 pub mod span;
 pub mod tags;
+pub mod zipkin_proto3;
+pub mod zipkin_thrift;
+pub mod zipkin_v1;
 
 pub use self::span::Span;
@@ -0,0 +1,492 @@
+use std::collections::{BTreeMap, HashMap};
+
+use prost::{Message, Oneof};
+use serde::de::{self, Deserializer};
+
+use super::span::{Annotation, Endpoint, Kind};
+use super::tags::{IkrellnTags, OpenTracingTag};
+use super::zipkin_proto3::hex_id;
+
+// Messages from OTLP trace service, with their OTLP/JSON mapping
+// https://github.com/open-telemetry/opentelemetry-proto/tree/main/opentelemetry/proto
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportTraceServiceRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub resource_spans: Vec<ResourceSpans>,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ResourceSpans {
+    #[prost(message, optional, tag = "1")]
+    pub resource: Option<Resource>,
+    #[prost(message, repeated, tag = "2")]
+    pub scope_spans: Vec<ScopeSpans>,
+    // deprecated before OTLP 1.0, same layout as scope spans
+    #[prost(message, repeated, tag = "1000")]
+    pub instrumentation_library_spans: Vec<ScopeSpans>,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Resource {
+    #[prost(message, repeated, tag = "1")]
+    pub attributes: Vec<KeyValue>,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScopeSpans {
+    #[prost(message, repeated, tag = "2")]
+    pub spans: Vec<Span>,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Span {
+    #[prost(bytes, tag = "1")]
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub trace_id: Vec<u8>,
+    #[prost(bytes, tag = "2")]
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub span_id: Vec<u8>,
+    #[prost(bytes, tag = "4")]
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub parent_span_id: Vec<u8>,
+    #[prost(string, tag = "5")]
+    pub name: String,
+    #[prost(enumeration = "SpanKind", tag = "6")]
+    pub kind: i32,
+    #[prost(fixed64, tag = "7")]
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub start_time_unix_nano: u64,
+    #[prost(fixed64, tag = "8")]
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub end_time_unix_nano: u64,
+    #[prost(message, repeated, tag = "9")]
+    pub attributes: Vec<KeyValue>,
+    #[prost(message, repeated, tag = "11")]
+    pub events: Vec<Event>,
+    #[prost(message, optional, tag = "15")]
+    pub status: Option<Status>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+pub enum SpanKind {
+    Unspecified = 0,
+    Internal = 1,
+    Server = 2,
+    Client = 3,
+    Producer = 4,
+    Consumer = 5,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Event {
+    #[prost(fixed64, tag = "1")]
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub time_unix_nano: u64,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(message, repeated, tag = "3")]
+    pub attributes: Vec<KeyValue>,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Status {
+    #[prost(string, tag = "2")]
+    pub message: String,
+    #[prost(enumeration = "StatusCode", tag = "3")]
+    pub code: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+pub enum StatusCode {
+    Unset = 0,
+    Ok = 1,
+    Error = 2,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct KeyValue {
+    #[prost(string, tag = "1")]
+    pub key: String,
+    #[prost(message, optional, tag = "2")]
+    pub value: Option<AnyValue>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct AnyValue {
+    #[prost(oneof = "Value", tags = "1, 2, 3, 4, 5, 6, 7")]
+    pub value: Option<Value>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+pub enum Value {
+    #[prost(string, tag = "1")]
+    StringValue(String),
+    #[prost(bool, tag = "2")]
+    BoolValue(bool),
+    #[prost(int64, tag = "3")]
+    IntValue(i64),
+    #[prost(double, tag = "4")]
+    DoubleValue(f64),
+    #[prost(message, tag = "5")]
+    ArrayValue(ArrayValue),
+    #[prost(message, tag = "6")]
+    KvlistValue(KeyValueList),
+    #[prost(bytes, tag = "7")]
+    BytesValue(Vec<u8>),
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ArrayValue {
+    #[prost(message, repeated, tag = "1")]
+    pub values: Vec<AnyValue>,
+}
+
+#[derive(Clone, PartialEq, Message, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct KeyValueList {
+    #[prost(message, repeated, tag = "1")]
+    pub values: Vec<KeyValue>,
+}
+
+fn deserialize_hex<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hex = <String as serde::Deserialize>::deserialize(deserializer)?;
+    if hex.len() % 2 != 0 {
+        return Err(de::Error::custom(format!("invalid hex id {}", hex)));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| de::Error::custom(format!("invalid hex id {}", hex)))
+        })
+        .collect()
+}
+
+fn number_from_json<T: std::str::FromStr>(value: &serde_json::Value) -> Option<T> {
+    match value {
+        serde_json::Value::String(value) => value.parse().ok(),
+        serde_json::Value::Number(value) => value.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+// 64 bits integers are encoded as strings in OTLP/JSON
+fn deserialize_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+    number_from_json(&value).ok_or_else(|| de::Error::custom(format!("invalid number {}", value)))
+}
+
+fn value_from_json(key: &str, value: serde_json::Value) -> Option<Value> {
+    match key {
+        "stringValue" => serde_json::from_value(value).ok().map(Value::StringValue),
+        "boolValue" => serde_json::from_value(value).ok().map(Value::BoolValue),
+        "intValue" => number_from_json(&value).map(Value::IntValue),
+        "doubleValue" => number_from_json(&value).map(Value::DoubleValue),
+        "arrayValue" => serde_json::from_value(value).ok().map(Value::ArrayValue),
+        "kvlistValue" => serde_json::from_value(value).ok().map(Value::KvlistValue),
+        "bytesValue" => serde_json::from_value::<String>(value)
+            .ok()
+            .and_then(|value| base64::decode(&value).ok())
+            .map(Value::BytesValue),
+        _ => None,
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for AnyValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let object =
+            <HashMap<String, serde_json::Value> as serde::Deserialize>::deserialize(deserializer)?;
+        let value = match object.into_iter().next() {
+            Some((key, value)) => Some(
+                value_from_json(&key, value)
+                    .ok_or_else(|| de::Error::custom(format!("invalid value {}", key)))?,
+            ),
+            None => None,
+        };
+        Ok(AnyValue { value })
+    }
+}
+
+impl AnyValue {
+    fn as_tag_value(&self) -> String {
+        match &self.value {
+            None => String::new(),
+            Some(Value::StringValue(value)) => value.clone(),
+            Some(Value::BoolValue(value)) => value.to_string(),
+            Some(Value::IntValue(value)) => value.to_string(),
+            Some(Value::DoubleValue(value)) => value.to_string(),
+            Some(Value::ArrayValue(array)) => serde_json::to_string(
+                &array
+                    .values
+                    .iter()
+                    .map(AnyValue::as_tag_value)
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap_or_default(),
+            Some(Value::KvlistValue(list)) => {
+                serde_json::to_string(&attributes_as_tags(&list.values)).unwrap_or_default()
+            }
+            Some(Value::BytesValue(value)) => base64::encode(value),
+        }
+    }
+}
+
+fn attributes_as_tags(attributes: &[KeyValue]) -> HashMap<String, String> {
+    attributes
+        .iter()
+        .map(|attribute| {
+            (
+                attribute.key.clone(),
+                attribute
+                    .value
+                    .as_ref()
+                    .map(AnyValue::as_tag_value)
+                    .unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+// OpenTelemetry semantic conventions
+// https://opentelemetry.io/docs/specs/semconv/
+const SERVICE_NAME: &str = "service.name";
+const STATUS_DESCRIPTION: &str = "otel.status_description";
+const TEST_SUITE_NAME: &str = "test.suite.name";
+const TEST_CASE_NAME: &str = "test.case.name";
+const TEST_CASE_RESULT_STATUS: &str = "test.case.result.status";
+const CODE_NAMESPACE: &str = "code.namespace";
+const DEPLOYMENT_ENVIRONMENT: &str = "deployment.environment";
+const DEPLOYMENT_ENVIRONMENT_NAME: &str = "deployment.environment.name";
+
+/// Copy OpenTelemetry test attributes to the i'Krelln tags used to build test results,
+/// unless those tags are already set
+fn map_semantic_attributes(tags: &mut HashMap<String, String>) {
+    let mappings: Vec<(&str, IkrellnTags)> = vec![
+        (TEST_SUITE_NAME, IkrellnTags::Suite),
+        (TEST_CASE_NAME, IkrellnTags::Name),
+        (CODE_NAMESPACE, IkrellnTags::Class),
+        (DEPLOYMENT_ENVIRONMENT_NAME, IkrellnTags::Environment),
+        (DEPLOYMENT_ENVIRONMENT, IkrellnTags::Environment),
+        (TEST_CASE_RESULT_STATUS, IkrellnTags::Result),
+    ];
+    for (attribute, tag) in mappings {
+        let tag: &'static str = tag.into();
+        if tags.contains_key(tag) {
+            continue;
+        }
+        if let Some(value) = tags.get(attribute).cloned() {
+            let value = if attribute == TEST_CASE_RESULT_STATUS {
+                match value.to_lowercase().as_ref() {
+                    "pass" => "success".to_string(),
+                    "fail" => "failure".to_string(),
+                    "skip" => "skipped".to_string(),
+                    _ => value,
+                }
+            } else {
+                value
+            };
+            tags.insert(tag.to_string(), value);
+        }
+    }
+}
+
+fn into_span(
+    span: Span,
+    service_name: Option<String>,
+    resource_tags: &HashMap<String, String>,
+) -> super::Span {
+    let mut tags = resource_tags.clone();
+    tags.extend(attributes_as_tags(&span.attributes));
+    if let Some(status) = &span.status {
+        if status.code == StatusCode::Error as i32 {
+            tags.insert(
+                <&str>::from(OpenTracingTag::Error).to_string(),
+                "true".to_string(),
+            );
+            if !status.message.is_empty() {
+                tags.insert(STATUS_DESCRIPTION.to_string(), status.message.clone());
+            }
+        }
+    }
+    map_semantic_attributes(&mut tags);
+
+    let timestamp = span.start_time_unix_nano / 1000;
+    super::Span {
+        trace_id: hex_id(&span.trace_id),
+        id: hex_id(&span.span_id),
+        parent_id: Some(hex_id(&span.parent_span_id)).filter(|id| !id.is_empty()),
+        name: Some(span.name).filter(|name| !name.is_empty()),
+        kind: match SpanKind::from_i32(span.kind) {
+            Some(SpanKind::Server) => Some(Kind::SERVER),
+            Some(SpanKind::Client) => Some(Kind::CLIENT),
+            Some(SpanKind::Producer) => Some(Kind::PRODUCER),
+            Some(SpanKind::Consumer) => Some(Kind::CONSUMER),
+            _ => None,
+        },
+        duration: match span.end_time_unix_nano {
+            0 => None,
+            end => Some((end.saturating_sub(span.start_time_unix_nano) / 1000) as i64),
+        },
+        timestamp: Some(timestamp as i64).filter(|ts| *ts != 0),
+        debug: false,
+        shared: false,
+        local_endpoint: service_name.map(|service_name| Endpoint {
+            service_name: Some(service_name),
+            ..Default::default()
+        }),
+        remote_endpoint: tags
+            .get(<&str>::from(OpenTracingTag::PeerService))
+            .map(|service_name| Endpoint {
+                service_name: Some(service_name.clone()),
+                ..Default::default()
+            }),
+        // an event with attributes is serialized as json
+        annotations: span
+            .events
+            .into_iter()
+            .map(|event| Annotation {
+                timestamp: (event.time_unix_nano / 1000) as i64,
+                value: if event.attributes.is_empty() {
+                    event.name
+                } else {
+                    let mut value: BTreeMap<_, _> =
+                        attributes_as_tags(&event.attributes).into_iter().collect();
+                    value.insert("event".to_string(), event.name);
+                    serde_json::to_string(&value).unwrap_or_default()
+                },
+                endpoint: None,
+            })
+            .collect(),
+        tags,
+        binary_annotations: vec![],
+    }
+}
+
+impl From<ExportTraceServiceRequest> for Vec<super::Span> {
+    fn from(request: ExportTraceServiceRequest) -> Self {
+        request
+            .resource_spans
+            .into_iter()
+            .flat_map(|resource_spans| {
+                let mut resource_tags = resource_spans
+                    .resource
+                    .map(|resource| attributes_as_tags(&resource.attributes))
+                    .unwrap_or_default();
+                let service_name = resource_tags.remove(SERVICE_NAME);
+                resource_spans
+                    .scope_spans
+                    .into_iter()
+                    .chain(resource_spans.instrumentation_library_spans)
+                    .flat_map(|scope_spans| scope_spans.spans)
+                    .map(|span| into_span(span, service_name.clone(), &resource_tags))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+pub fn decode_protobuf(body: &[u8]) -> Result<Vec<super::Span>, prost::DecodeError> {
+    ExportTraceServiceRequest::decode(body).map(std::convert::Into::into)
+}
+
+pub fn decode_json(body: &[u8]) -> Result<Vec<super::Span>, serde_json::Error> {
+    serde_json::from_slice::<ExportTraceServiceRequest>(body).map(std::convert::Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OTLP_JSON: &str = r#"{
+  "resourceSpans": [{
+    "resource": {"attributes": [
+      {"key": "service.name", "value": {"stringValue": "test-runner"}},
+      {"key": "deployment.environment", "value": {"stringValue": "ci"}}
+    ]},
+    "scopeSpans": [{
+      "scope": {"name": "pytest"},
+      "spans": [{
+        "traceId": "5b8efff798038103d269b633813fc60c",
+        "spanId": "eee19b7ec3c1b174",
+        "name": "test_login",
+        "kind": 1,
+        "startTimeUnixNano": "1544712660000000000",
+        "endTimeUnixNano": "1544712661000000000",
+        "attributes": [
+          {"key": "test.suite.name", "value": {"stringValue": "auth"}},
+          {"key": "code.namespace", "value": {"stringValue": "tests.test_auth"}},
+          {"key": "test.case.result.status", "value": {"stringValue": "fail"}},
+          {"key": "retries", "value": {"intValue": "2"}}
+        ],
+        "events": [
+          {"timeUnixNano": "1544712660500000000", "name": "exception",
+           "attributes": [{"key": "exception.message", "value": {"stringValue": "boom"}}]}
+        ],
+        "status": {"code": 2, "message": "assertion failed"}
+      }]
+    }]
+  }]
+}"#;
+
+    #[test]
+    fn can_decode_otlp_json() {
+        let spans = decode_json(OTLP_JSON.as_bytes()).unwrap();
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(span.trace_id, "5b8efff798038103d269b633813fc60c");
+        assert_eq!(span.id, "eee19b7ec3c1b174");
+        assert!(span.parent_id.is_none());
+        assert!(span.kind.is_none());
+        assert_eq!(span.timestamp, Some(1_544_712_660_000_000));
+        assert_eq!(span.duration, Some(1_000_000));
+        assert_eq!(
+            span.local_endpoint.clone().and_then(|ep| ep.service_name),
+            Some("test-runner".to_string())
+        );
+        assert_eq!(span.tags.get("test.suite"), Some(&"auth".to_string()));
+        assert_eq!(
+            span.tags.get("test.class"),
+            Some(&"tests.test_auth".to_string())
+        );
+        assert_eq!(span.tags.get("test.result"), Some(&"failure".to_string()));
+        assert_eq!(span.tags.get("test.environment"), Some(&"ci".to_string()));
+        assert_eq!(span.tags.get("error"), Some(&"true".to_string()));
+        assert_eq!(span.tags.get("retries"), Some(&"2".to_string()));
+        assert!(!span.tags.contains_key("service.name"));
+        assert_eq!(span.annotations.len(), 1);
+        assert_eq!(span.annotations[0].timestamp, 1_544_712_660_500_000);
+    }
+
+    #[test]
+    fn can_decode_otlp_protobuf() {
+        let request: ExportTraceServiceRequest = serde_json::from_str(OTLP_JSON).unwrap();
+        let mut body = vec![];
+        request.encode(&mut body).unwrap();
+
+        let spans = decode_protobuf(&body).unwrap();
+        assert_eq!(
+            serde_json::to_value(spans).unwrap(),
+            serde_json::to_value(decode_json(OTLP_JSON.as_bytes()).unwrap()).unwrap()
+        );
+    }
+}
@@ -33,22 +33,29 @@ impl From<String> for Kind {
 pub struct Span {
     pub trace_id: String,
     pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub kind: Option<Kind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<i64>,
     #[serde(default)]
     pub debug: bool,
     #[serde(default)]
     pub shared: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub local_endpoint: Option<Endpoint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub remote_endpoint: Option<Endpoint>,
     #[serde(default)]
     pub annotations: Vec<Annotation>,
     #[serde(default)]
     pub tags: HashMap<String, String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub binary_annotations: Vec<BinaryTag>,
 }
 
@@ -95,12 +102,121 @@ pub struct Endpoint {
 pub struct Annotation {
     pub value: String,
     pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<Endpoint>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BinaryTag {
     pub key: String,
+    #[serde(deserialize_with = "deserialize_binary_value")]
     pub value: String,
     pub endpoint: Option<Endpoint>,
 }
+
+// Zipkin v1 binary annotations can have string, boolean or number values
+fn deserialize_binary_value<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match <serde_json::Value as serde::Deserialize>::deserialize(deserializer)? {
+        serde_json::Value::String(value) => Ok(value),
+        serde_json::Value::Null => Ok("".to_string()),
+        other => Ok(other.to_string()),
+    }
+}
+
+/// Builds spans for unit tests, a root span of trace `trace` unless changed
+#[cfg(test)]
+pub(crate) struct SpanBuilder(Span);
+
+#[cfg(test)]
+impl SpanBuilder {
+    pub(crate) fn new(id: &str) -> Self {
+        SpanBuilder(Span {
+            trace_id: "trace".to_string(),
+            id: id.to_string(),
+            parent_id: None,
+            name: Some(id.to_string()),
+            kind: None,
+            duration: Some(10),
+            timestamp: Some(50),
+            debug: false,
+            shared: false,
+            local_endpoint: None,
+            remote_endpoint: None,
+            annotations: vec![],
+            tags: HashMap::new(),
+            binary_annotations: vec![],
+        })
+    }
+
+    pub(crate) fn trace(mut self, trace_id: &str) -> Self {
+        self.0.trace_id = trace_id.to_string();
+        self
+    }
+
+    pub(crate) fn child_of(mut self, parent_id: &str) -> Self {
+        self.0.parent_id = Some(parent_id.to_string());
+        self
+    }
+
+    pub(crate) fn name(mut self, name: &str) -> Self {
+        self.0.name = Some(name.to_string());
+        self
+    }
+
+    pub(crate) fn kind(mut self, kind: Kind) -> Self {
+        self.0.kind = Some(kind);
+        self
+    }
+
+    pub(crate) fn duration(mut self, duration: Option<i64>) -> Self {
+        self.0.duration = duration;
+        self
+    }
+
+    pub(crate) fn timestamp(mut self, timestamp: i64) -> Self {
+        self.0.timestamp = Some(timestamp);
+        self
+    }
+
+    pub(crate) fn service(mut self, service_name: &str) -> Self {
+        self.0.local_endpoint = Some(Endpoint {
+            service_name: Some(service_name.to_string()),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub(crate) fn remote_service(mut self, service_name: &str) -> Self {
+        self.0.remote_endpoint = Some(Endpoint {
+            service_name: Some(service_name.to_string()),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub(crate) fn tag<T>(mut self, tag: T, value: &str) -> Self
+    where
+        &'static str: From<T>,
+    {
+        let tag: &str = tag.into();
+        self.0.tags.insert(tag.to_string(), value.to_string());
+        self
+    }
+
+    pub(crate) fn annotation(mut self, timestamp: i64, value: &str) -> Self {
+        self.0.annotations.push(Annotation {
+            timestamp,
+            value: value.to_string(),
+            endpoint: None,
+        });
+        self
+    }
+
+    pub(crate) fn build(self) -> Span {
+        self.0
+    }
+}
@@ -98,10 +98,16 @@ impl FromStr for OpenTracingTag {
 
 #[derive(Clone)]
 pub enum IkrellnTags {
+    Attempt,
+    Branch,
+    BuildId,
+    CiUrl,
     Class,
+    Commit,
     Environment,
     Name,
     Result,
+    RunId,
     StepParameters,
     StepStatus,
     StepType,
@@ -110,10 +116,16 @@ pub enum IkrellnTags {
 impl From<IkrellnTags> for &'static str {
     fn from(tag: IkrellnTags) -> &'static str {
         match tag {
+            IkrellnTags::Attempt => "test.attempt",
+            IkrellnTags::Branch => "ci.branch",
+            IkrellnTags::BuildId => "ci.build_id",
+            IkrellnTags::CiUrl => "ci.url",
             IkrellnTags::Class => "test.class",
+            IkrellnTags::Commit => "ci.commit",
             IkrellnTags::Environment => "test.environment",
             IkrellnTags::Name => "test.name",
             IkrellnTags::Result => "test.result",
+            IkrellnTags::RunId => "test.run_id",
             IkrellnTags::StepParameters => "test.step_parameters",
             IkrellnTags::StepStatus => "test.step_status",
             IkrellnTags::StepType => "test.step_type",
@@ -126,10 +138,16 @@ impl FromStr for IkrellnTags {
     type Err = NonIkrellnTag;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
+            "test.attempt" => Ok(IkrellnTags::Attempt),
+            "ci.branch" => Ok(IkrellnTags::Branch),
+            "ci.build_id" => Ok(IkrellnTags::BuildId),
+            "ci.url" => Ok(IkrellnTags::CiUrl),
             "test.class" => Ok(IkrellnTags::Class),
+            "ci.commit" => Ok(IkrellnTags::Commit),
             "test.environment" => Ok(IkrellnTags::Environment),
             "test.name" => Ok(IkrellnTags::Name),
             "test.result" => Ok(IkrellnTags::Result),
+            "test.run_id" => Ok(IkrellnTags::RunId),
             "test.step_parameters" => Ok(IkrellnTags::StepParameters),
             "test.step_status" => Ok(IkrellnTags::StepStatus),
             "test.step_type" => Ok(IkrellnTags::StepType),
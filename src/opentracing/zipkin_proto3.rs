@@ -0,0 +1,225 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use prost::Message;
+
+// Messages from zipkin.proto3
+// https://github.com/openzipkin/zipkin-api/blob/master/zipkin.proto
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ListOfSpans {
+    #[prost(message, repeated, tag = "1")]
+    pub spans: Vec<Span>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Span {
+    #[prost(bytes, tag = "1")]
+    pub trace_id: Vec<u8>,
+    #[prost(bytes, tag = "2")]
+    pub parent_id: Vec<u8>,
+    #[prost(bytes, tag = "3")]
+    pub id: Vec<u8>,
+    #[prost(enumeration = "Kind", tag = "4")]
+    pub kind: i32,
+    #[prost(string, tag = "5")]
+    pub name: String,
+    #[prost(fixed64, tag = "6")]
+    pub timestamp: u64,
+    #[prost(uint64, tag = "7")]
+    pub duration: u64,
+    #[prost(message, optional, tag = "8")]
+    pub local_endpoint: Option<Endpoint>,
+    #[prost(message, optional, tag = "9")]
+    pub remote_endpoint: Option<Endpoint>,
+    #[prost(message, repeated, tag = "10")]
+    pub annotations: Vec<Annotation>,
+    #[prost(map = "string, string", tag = "11")]
+    pub tags: ::std::collections::HashMap<String, String>,
+    #[prost(bool, tag = "12")]
+    pub debug: bool,
+    #[prost(bool, tag = "13")]
+    pub shared: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+pub enum Kind {
+    SpanKindUnspecified = 0,
+    Client = 1,
+    Server = 2,
+    Producer = 3,
+    Consumer = 4,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Endpoint {
+    #[prost(string, tag = "1")]
+    pub service_name: String,
+    #[prost(bytes, tag = "2")]
+    pub ipv4: Vec<u8>,
+    #[prost(bytes, tag = "3")]
+    pub ipv6: Vec<u8>,
+    #[prost(int32, tag = "4")]
+    pub port: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Annotation {
+    #[prost(fixed64, tag = "1")]
+    pub timestamp: u64,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+pub(crate) fn hex_id(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn non_zero(value: u64) -> Option<i64> {
+    if value == 0 {
+        None
+    } else {
+        Some(value as i64)
+    }
+}
+
+impl From<Endpoint> for super::span::Endpoint {
+    fn from(endpoint: Endpoint) -> Self {
+        let mut ipv4 = [0; 4];
+        let mut ipv6 = [0; 16];
+        super::span::Endpoint {
+            service_name: non_empty(endpoint.service_name),
+            ipv4: match endpoint.ipv4.len() {
+                4 => {
+                    ipv4.copy_from_slice(&endpoint.ipv4);
+                    Some(Ipv4Addr::from(ipv4).to_string())
+                }
+                _ => None,
+            },
+            ipv6: match endpoint.ipv6.len() {
+                16 => {
+                    ipv6.copy_from_slice(&endpoint.ipv6);
+                    Some(Ipv6Addr::from(ipv6).to_string())
+                }
+                _ => None,
+            },
+            port: match endpoint.port {
+                0 => None,
+                port => Some(port),
+            },
+        }
+    }
+}
+
+impl From<Span> for super::Span {
+    fn from(span: Span) -> Self {
+        super::Span {
+            trace_id: hex_id(&span.trace_id),
+            id: hex_id(&span.id),
+            parent_id: non_empty(hex_id(&span.parent_id)),
+            name: non_empty(span.name),
+            kind: match Kind::from_i32(span.kind) {
+                Some(Kind::Client) => Some(super::span::Kind::CLIENT),
+                Some(Kind::Server) => Some(super::span::Kind::SERVER),
+                Some(Kind::Producer) => Some(super::span::Kind::PRODUCER),
+                Some(Kind::Consumer) => Some(super::span::Kind::CONSUMER),
+                _ => None,
+            },
+            duration: non_zero(span.duration),
+            timestamp: non_zero(span.timestamp),
+            debug: span.debug,
+            shared: span.shared,
+            local_endpoint: span.local_endpoint.map(std::convert::Into::into),
+            remote_endpoint: span.remote_endpoint.map(std::convert::Into::into),
+            annotations: span
+                .annotations
+                .into_iter()
+                .map(|annotation| super::span::Annotation {
+                    value: annotation.value,
+                    timestamp: annotation.timestamp as i64,
+                    endpoint: None,
+                })
+                .collect(),
+            tags: span.tags,
+            binary_annotations: vec![],
+        }
+    }
+}
+
+pub fn decode(body: &[u8]) -> Result<Vec<super::Span>, prost::DecodeError> {
+    ListOfSpans::decode(body).map(|list| {
+        list.spans
+            .into_iter()
+            .map(std::convert::Into::into)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_decode_list_of_spans() {
+        let mut tags = ::std::collections::HashMap::new();
+        tags.insert("test.class".to_string(), "ProtoTest".to_string());
+        let list = ListOfSpans {
+            spans: vec![Span {
+                trace_id: vec![0x5a, 0xf7, 0x18, 0x3f, 0xb1, 0xd4, 0xcf, 0x5f],
+                parent_id: vec![],
+                id: vec![0x35, 0x2b, 0xff, 0x9a, 0x74, 0xca, 0x9a, 0xd2],
+                kind: Kind::Server as i32,
+                name: "get /api".to_string(),
+                timestamp: 1_472_470_996_199_000,
+                duration: 207_000,
+                local_endpoint: Some(Endpoint {
+                    service_name: "backend".to_string(),
+                    ipv4: vec![192, 168, 99, 101],
+                    ipv6: vec![],
+                    port: 9000,
+                }),
+                remote_endpoint: None,
+                annotations: vec![Annotation {
+                    timestamp: 1_472_470_996_238_000,
+                    value: "ws".to_string(),
+                }],
+                tags,
+                debug: false,
+                shared: true,
+            }],
+        };
+        let mut body = vec![];
+        list.encode(&mut body).unwrap();
+
+        let spans = decode(&body).unwrap();
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(span.trace_id, "5af7183fb1d4cf5f");
+        assert_eq!(span.id, "352bff9a74ca9ad2");
+        assert!(span.parent_id.is_none());
+        match span.kind {
+            Some(super::super::span::Kind::SERVER) => (),
+            _ => panic!("span should be a server span"),
+        }
+        assert_eq!(span.duration, Some(207_000));
+        let local_endpoint = span.local_endpoint.clone().unwrap();
+        assert_eq!(local_endpoint.service_name, Some("backend".to_string()));
+        assert_eq!(local_endpoint.ipv4, Some("192.168.99.101".to_string()));
+        assert_eq!(local_endpoint.port, Some(9000));
+        assert_eq!(span.annotations.len(), 1);
+        assert_eq!(span.tags.get("test.class"), Some(&"ProtoTest".to_string()));
+        assert!(span.shared);
+    }
+
+    #[test]
+    fn should_fail_on_invalid_message() {
+        assert!(decode(&[0x0a, 0xff, 0xff]).is_err());
+    }
+}
@@ -0,0 +1,529 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::rc::Rc;
+
+use thrift::protocol::{
+    TBinaryInputProtocol, TFieldIdentifier, TInputProtocol, TListIdentifier, TMapIdentifier,
+    TMessageIdentifier, TSetIdentifier, TStructIdentifier, TType,
+};
+
+use super::span::{Annotation, BinaryTag, Endpoint, Span};
+
+// Structs from zipkinCore.thrift
+// https://github.com/openzipkin/zipkin-api/blob/master/thrift/zipkinCore.thrift
+
+// AnnotationType enum of a BinaryAnnotation
+const ANNOTATION_TYPE_BOOL: i32 = 0;
+const ANNOTATION_TYPE_BYTES: i32 = 1;
+const ANNOTATION_TYPE_I16: i32 = 2;
+const ANNOTATION_TYPE_I32: i32 = 3;
+const ANNOTATION_TYPE_I64: i32 = 4;
+const ANNOTATION_TYPE_DOUBLE: i32 = 5;
+
+// Lists sizes come from untrusted input, don't allocate more than this before reading elements
+const LIST_PREALLOCATION_MAX: usize = 64;
+
+/// Read all fields of a struct, calling `read_field` with the field id and type.
+/// `read_field` must return `false` for fields it doesn't know so that they are skipped.
+pub(crate) fn read_struct<F>(
+    i_prot: &mut dyn TInputProtocol,
+    mut read_field: F,
+) -> thrift::Result<()>
+where
+    F: FnMut(&mut dyn TInputProtocol, i16, TType) -> thrift::Result<bool>,
+{
+    i_prot.read_struct_begin()?;
+    loop {
+        let field = i_prot.read_field_begin()?;
+        if field.field_type == TType::Stop {
+            break;
+        }
+        let known = match field.id {
+            Some(id) => read_field(i_prot, id, field.field_type)?,
+            None => false,
+        };
+        if !known {
+            i_prot.skip(field.field_type)?;
+        }
+        i_prot.read_field_end()?;
+    }
+    i_prot.read_struct_end()
+}
+
+pub(crate) fn read_list<T, F>(
+    i_prot: &mut dyn TInputProtocol,
+    mut read_element: F,
+) -> thrift::Result<Vec<T>>
+where
+    F: FnMut(&mut dyn TInputProtocol) -> thrift::Result<T>,
+{
+    let list = i_prot.read_list_begin()?;
+    let capacity = (list.size.max(0) as usize).min(LIST_PREALLOCATION_MAX);
+    let mut elements = Vec::with_capacity(capacity);
+    for _ in 0..list.size {
+        elements.push(read_element(i_prot)?);
+    }
+    i_prot.read_list_end()?;
+    Ok(elements)
+}
+
+/// Body being decoded, shared between a protocol and the `BoundedInputProtocol` wrapping it
+#[derive(Clone)]
+pub(crate) struct SharedBody<'a>(Rc<Cell<&'a [u8]>>);
+
+impl<'a> Read for SharedBody<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut body = self.0.get();
+        let read = body.read(buf)?;
+        self.0.set(body);
+        Ok(read)
+    }
+}
+
+/// How a protocol encodes the length of strings and bytes
+#[derive(Clone, Copy)]
+pub(crate) enum LengthEncoding {
+    I32,
+    Varint,
+}
+
+/// Protocol reading strings and bytes with a length checked against what is left in the body.
+/// Lengths come from untrusted input, and thrift protocols allocate them before reading.
+pub(crate) struct BoundedInputProtocol<'a, P: TInputProtocol> {
+    protocol: P,
+    body: SharedBody<'a>,
+    length_encoding: LengthEncoding,
+}
+
+impl<'a, P: TInputProtocol> BoundedInputProtocol<'a, P> {
+    pub(crate) fn new<F>(body: &'a [u8], length_encoding: LengthEncoding, new_protocol: F) -> Self
+    where
+        F: FnOnce(SharedBody<'a>) -> P,
+    {
+        let body = SharedBody(Rc::new(Cell::new(body)));
+        BoundedInputProtocol {
+            protocol: new_protocol(body.clone()),
+            body,
+            length_encoding,
+        }
+    }
+
+    fn read_varint(&mut self) -> thrift::Result<i64> {
+        let mut value = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = self.protocol.read_byte()?;
+            value |= i64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(thrift::Error::Protocol(thrift::ProtocolError::new(
+            thrift::ProtocolErrorKind::InvalidData,
+            "length varint is too long",
+        )))
+    }
+}
+
+impl<'a, P: TInputProtocol> TInputProtocol for BoundedInputProtocol<'a, P> {
+    fn read_message_begin(&mut self) -> thrift::Result<TMessageIdentifier> {
+        self.protocol.read_message_begin()
+    }
+    fn read_message_end(&mut self) -> thrift::Result<()> {
+        self.protocol.read_message_end()
+    }
+    fn read_struct_begin(&mut self) -> thrift::Result<Option<TStructIdentifier>> {
+        self.protocol.read_struct_begin()
+    }
+    fn read_struct_end(&mut self) -> thrift::Result<()> {
+        self.protocol.read_struct_end()
+    }
+    fn read_field_begin(&mut self) -> thrift::Result<TFieldIdentifier> {
+        self.protocol.read_field_begin()
+    }
+    fn read_field_end(&mut self) -> thrift::Result<()> {
+        self.protocol.read_field_end()
+    }
+    fn read_bool(&mut self) -> thrift::Result<bool> {
+        self.protocol.read_bool()
+    }
+    fn read_bytes(&mut self) -> thrift::Result<Vec<u8>> {
+        let len = match self.length_encoding {
+            LengthEncoding::I32 => i64::from(self.protocol.read_i32()?),
+            LengthEncoding::Varint => self.read_varint()?,
+        };
+        let body = self.body.0.get();
+        if len < 0 {
+            return Err(thrift::Error::Protocol(thrift::ProtocolError::new(
+                thrift::ProtocolErrorKind::NegativeSize,
+                format!("negative length {}", len),
+            )));
+        }
+        if len as usize > body.len() {
+            return Err(thrift::Error::Protocol(thrift::ProtocolError::new(
+                thrift::ProtocolErrorKind::SizeLimit,
+                format!(
+                    "length {} is larger than the {} bytes left",
+                    len,
+                    body.len()
+                ),
+            )));
+        }
+        let (bytes, rest) = body.split_at(len as usize);
+        self.body.0.set(rest);
+        Ok(bytes.to_vec())
+    }
+    fn read_i8(&mut self) -> thrift::Result<i8> {
+        self.protocol.read_i8()
+    }
+    fn read_i16(&mut self) -> thrift::Result<i16> {
+        self.protocol.read_i16()
+    }
+    fn read_i32(&mut self) -> thrift::Result<i32> {
+        self.protocol.read_i32()
+    }
+    fn read_i64(&mut self) -> thrift::Result<i64> {
+        self.protocol.read_i64()
+    }
+    fn read_double(&mut self) -> thrift::Result<f64> {
+        self.protocol.read_double()
+    }
+    fn read_string(&mut self) -> thrift::Result<String> {
+        String::from_utf8(self.read_bytes()?).map_err(From::from)
+    }
+    fn read_list_begin(&mut self) -> thrift::Result<TListIdentifier> {
+        self.protocol.read_list_begin()
+    }
+    fn read_list_end(&mut self) -> thrift::Result<()> {
+        self.protocol.read_list_end()
+    }
+    fn read_set_begin(&mut self) -> thrift::Result<TSetIdentifier> {
+        self.protocol.read_set_begin()
+    }
+    fn read_set_end(&mut self) -> thrift::Result<()> {
+        self.protocol.read_set_end()
+    }
+    fn read_map_begin(&mut self) -> thrift::Result<TMapIdentifier> {
+        self.protocol.read_map_begin()
+    }
+    fn read_map_end(&mut self) -> thrift::Result<()> {
+        self.protocol.read_map_end()
+    }
+    fn read_byte(&mut self) -> thrift::Result<u8> {
+        self.protocol.read_byte()
+    }
+}
+
+pub(crate) fn id_to_hex(id: i64) -> String {
+    format!("{:016x}", id as u64)
+}
+
+fn read_endpoint(i_prot: &mut dyn TInputProtocol) -> thrift::Result<Endpoint> {
+    let mut endpoint = Endpoint::default();
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::I32) => {
+                endpoint.ipv4 = match i_prot.read_i32()? {
+                    0 => None,
+                    ipv4 => Some(Ipv4Addr::from(ipv4 as u32).to_string()),
+                }
+            }
+            (2, TType::I16) => {
+                endpoint.port = match i_prot.read_i16()? {
+                    0 => None,
+                    port => Some(i32::from(port as u16)),
+                }
+            }
+            (3, TType::String) => {
+                endpoint.service_name = Some(i_prot.read_string()?).filter(|name| !name.is_empty())
+            }
+            (4, TType::String) => {
+                let bytes = i_prot.read_bytes()?;
+                if bytes.len() == 16 {
+                    let mut ipv6 = [0; 16];
+                    ipv6.copy_from_slice(&bytes);
+                    endpoint.ipv6 = Some(Ipv6Addr::from(ipv6).to_string());
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(endpoint)
+}
+
+fn read_annotation(i_prot: &mut dyn TInputProtocol) -> thrift::Result<Annotation> {
+    let mut annotation = Annotation {
+        timestamp: 0,
+        value: String::new(),
+        endpoint: None,
+    };
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::I64) => annotation.timestamp = i_prot.read_i64()?,
+            (2, TType::String) => annotation.value = i_prot.read_string()?,
+            (3, TType::Struct) => annotation.endpoint = Some(read_endpoint(i_prot)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(annotation)
+}
+
+fn binary_value_to_string(value: &[u8], annotation_type: i32) -> String {
+    let mut be_bytes = [0; 8];
+    let size = value.len().min(8);
+    be_bytes[8 - size..].copy_from_slice(&value[..size]);
+    let as_i64 = i64::from_be_bytes(be_bytes);
+    match annotation_type {
+        ANNOTATION_TYPE_BOOL => (value.first().cloned().unwrap_or(0) != 0).to_string(),
+        ANNOTATION_TYPE_BYTES => base64::encode(value),
+        ANNOTATION_TYPE_I16 => (as_i64 as i16).to_string(),
+        ANNOTATION_TYPE_I32 => (as_i64 as i32).to_string(),
+        ANNOTATION_TYPE_I64 => as_i64.to_string(),
+        ANNOTATION_TYPE_DOUBLE => f64::from_bits(as_i64 as u64).to_string(),
+        _ => String::from_utf8_lossy(value).to_string(),
+    }
+}
+
+fn read_binary_annotation(i_prot: &mut dyn TInputProtocol) -> thrift::Result<BinaryTag> {
+    let mut key = String::new();
+    let mut value = vec![];
+    let mut annotation_type = 6;
+    let mut endpoint = None;
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::String) => key = i_prot.read_string()?,
+            (2, TType::String) => value = i_prot.read_bytes()?,
+            (3, TType::I32) => annotation_type = i_prot.read_i32()?,
+            (4, TType::Struct) => endpoint = Some(read_endpoint(i_prot)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(BinaryTag {
+        key,
+        value: binary_value_to_string(&value, annotation_type),
+        endpoint,
+    })
+}
+
+fn read_span(i_prot: &mut dyn TInputProtocol) -> thrift::Result<Span> {
+    let mut trace_id = 0;
+    let mut trace_id_high = 0;
+    let mut span = Span {
+        trace_id: String::new(),
+        id: String::new(),
+        parent_id: None,
+        name: None,
+        kind: None,
+        duration: None,
+        timestamp: None,
+        debug: false,
+        shared: false,
+        local_endpoint: None,
+        remote_endpoint: None,
+        annotations: vec![],
+        tags: HashMap::new(),
+        binary_annotations: vec![],
+    };
+    read_struct(i_prot, |i_prot, id, field_type| {
+        match (id, field_type) {
+            (1, TType::I64) => trace_id = i_prot.read_i64()?,
+            (3, TType::String) => span.name = Some(i_prot.read_string()?),
+            (4, TType::I64) => span.id = id_to_hex(i_prot.read_i64()?),
+            (5, TType::I64) => span.parent_id = Some(id_to_hex(i_prot.read_i64()?)),
+            (6, TType::List) => span.annotations = read_list(i_prot, read_annotation)?,
+            (8, TType::List) => {
+                span.binary_annotations = read_list(i_prot, read_binary_annotation)?
+            }
+            (9, TType::Bool) => span.debug = i_prot.read_bool()?,
+            (10, TType::I64) => span.timestamp = Some(i_prot.read_i64()?),
+            (11, TType::I64) => span.duration = Some(i_prot.read_i64()?),
+            (12, TType::I64) => trace_id_high = i_prot.read_i64()?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    span.trace_id = match trace_id_high {
+        0 => id_to_hex(trace_id),
+        high => format!("{}{}", id_to_hex(high), id_to_hex(trace_id)),
+    };
+    Ok(span)
+}
+
+/// Decode a list of Zipkin v1 Thrift spans encoded with TBinaryProtocol
+pub fn decode(body: &[u8]) -> thrift::Result<Vec<Span>> {
+    let mut i_prot = BoundedInputProtocol::new(body, LengthEncoding::I32, |body| {
+        TBinaryInputProtocol::new(body, true)
+    });
+    read_list(&mut i_prot, read_span)
+}
+
+#[cfg(test)]
+mod tests {
+    use thrift::protocol::{
+        TBinaryOutputProtocol, TFieldIdentifier, TListIdentifier, TOutputProtocol,
+        TStructIdentifier,
+    };
+
+    use super::*;
+
+    fn write_field<F>(o_prot: &mut dyn TOutputProtocol, id: i16, field_type: TType, write: F)
+    where
+        F: FnOnce(&mut dyn TOutputProtocol),
+    {
+        o_prot
+            .write_field_begin(&TFieldIdentifier::new::<_, String, _>(None, field_type, id))
+            .unwrap();
+        write(o_prot);
+        o_prot.write_field_end().unwrap();
+    }
+
+    fn write_endpoint(o_prot: &mut dyn TOutputProtocol, service_name: &str) {
+        o_prot
+            .write_struct_begin(&TStructIdentifier::new("Endpoint"))
+            .unwrap();
+        write_field(o_prot, 1, TType::I32, |o| o.write_i32(0x7f00_0001).unwrap());
+        write_field(o_prot, 2, TType::I16, |o| o.write_i16(8080).unwrap());
+        write_field(o_prot, 3, TType::String, |o| {
+            o.write_string(service_name).unwrap()
+        });
+        o_prot.write_field_stop().unwrap();
+        o_prot.write_struct_end().unwrap();
+    }
+
+    fn encode_test_span() -> Vec<u8> {
+        let mut body = vec![];
+        {
+            let mut o_prot = TBinaryOutputProtocol::new(&mut body, true);
+            let o_prot: &mut dyn TOutputProtocol = &mut o_prot;
+            o_prot
+                .write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                .unwrap();
+            o_prot
+                .write_struct_begin(&TStructIdentifier::new("Span"))
+                .unwrap();
+            write_field(o_prot, 1, TType::I64, |o| o.write_i64(0x5af7).unwrap());
+            write_field(o_prot, 3, TType::String, |o| {
+                o.write_string("get /api").unwrap()
+            });
+            write_field(o_prot, 4, TType::I64, |o| o.write_i64(0x352b).unwrap());
+            write_field(o_prot, 6, TType::List, |o| {
+                o.write_list_begin(&TListIdentifier::new(TType::Struct, 2))
+                    .unwrap();
+                for (timestamp, value) in &[(1_000_000, "sr"), (1_000_500, "ss")] {
+                    o.write_struct_begin(&TStructIdentifier::new("Annotation"))
+                        .unwrap();
+                    write_field(o, 1, TType::I64, |o| o.write_i64(*timestamp).unwrap());
+                    write_field(o, 2, TType::String, |o| o.write_string(value).unwrap());
+                    write_field(o, 3, TType::Struct, |o| write_endpoint(o, "backend"));
+                    o.write_field_stop().unwrap();
+                    o.write_struct_end().unwrap();
+                }
+                o.write_list_end().unwrap();
+            });
+            write_field(o_prot, 8, TType::List, |o| {
+                o.write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                    .unwrap();
+                o.write_struct_begin(&TStructIdentifier::new("BinaryAnnotation"))
+                    .unwrap();
+                write_field(o, 1, TType::String, |o| {
+                    o.write_string("http.status_code").unwrap()
+                });
+                write_field(o, 2, TType::String, |o| {
+                    o.write_bytes(&200i32.to_be_bytes()).unwrap()
+                });
+                write_field(o, 3, TType::I32, |o| {
+                    o.write_i32(ANNOTATION_TYPE_I32).unwrap()
+                });
+                o.write_field_stop().unwrap();
+                o.write_struct_end().unwrap();
+                o.write_list_end().unwrap();
+            });
+            // unknown fields are skipped
+            write_field(o_prot, 42, TType::String, |o| {
+                o.write_string("ignored").unwrap()
+            });
+            o_prot.write_field_stop().unwrap();
+            o_prot.write_struct_end().unwrap();
+            o_prot.write_list_end().unwrap();
+        }
+        body
+    }
+
+    #[test]
+    fn can_decode_thrift_spans() {
+        let spans = decode(&encode_test_span()).unwrap();
+        assert_eq!(spans.len(), 1);
+        let span = spans[0].clone();
+        assert_eq!(span.trace_id, "0000000000005af7");
+        assert_eq!(span.id, "000000000000352b");
+        assert_eq!(span.name, Some("get /api".to_string()));
+        assert_eq!(span.annotations.len(), 2);
+        let endpoint = span.annotations[0].endpoint.clone().unwrap();
+        assert_eq!(endpoint.service_name, Some("backend".to_string()));
+        assert_eq!(endpoint.ipv4, Some("127.0.0.1".to_string()));
+        assert_eq!(endpoint.port, Some(8080));
+        assert_eq!(span.binary_annotations[0].value, "200");
+        assert!(span.is_v1());
+
+        let span = span.into_v2().remove(0);
+        assert_eq!(span.duration, Some(500));
+        assert_eq!(span.tags.get("http.status_code"), Some(&"200".to_string()));
+    }
+
+    #[test]
+    fn should_refuse_list_larger_than_body() {
+        let mut body = vec![];
+        {
+            let mut o_prot = TBinaryOutputProtocol::new(&mut body, true);
+            o_prot
+                .write_list_begin(&TListIdentifier::new(TType::Struct, i32::MAX))
+                .unwrap();
+        }
+        assert!(decode(&body).is_err());
+    }
+
+    #[test]
+    fn should_refuse_string_larger_than_body() {
+        let mut body = vec![];
+        {
+            let mut o_prot = TBinaryOutputProtocol::new(&mut body, true);
+            o_prot
+                .write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                .unwrap();
+            write_field(&mut o_prot, 3, TType::String, |o| {
+                o.write_i32(i32::MAX).unwrap()
+            });
+        }
+        assert!(decode(&body).is_err());
+    }
+
+    #[test]
+    fn should_refuse_negative_string_length() {
+        let body = [12, 0, 0, 0, 1, 11, 0, 3, 0xff, 0xff, 0xff, 0xff];
+        assert!(decode(&body).is_err());
+    }
+
+    #[test]
+    fn should_refuse_negative_length_of_skipped_field() {
+        let body = [12, 0, 0, 0, 1, 11, 0, 42, 0xff, 0xff, 0xff, 0xff];
+        assert!(decode(&body).is_err());
+    }
+
+    #[test]
+    fn can_convert_binary_annotation_values() {
+        assert_eq!(binary_value_to_string(&[1], ANNOTATION_TYPE_BOOL), "true");
+        assert_eq!(
+            binary_value_to_string(&(-2i16).to_be_bytes(), ANNOTATION_TYPE_I16),
+            "-2"
+        );
+        assert_eq!(
+            binary_value_to_string(&1.5f64.to_bits().to_be_bytes(), ANNOTATION_TYPE_DOUBLE),
+            "1.5"
+        );
+        assert_eq!(binary_value_to_string(b"abc", 6), "abc");
+    }
+}
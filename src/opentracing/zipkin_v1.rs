@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+
+use super::span::{Annotation, BinaryTag, Endpoint, Kind, Span};
+
+// Zipkin v1 core annotations
+// https://zipkin.io/pages/data_model.html
+const CLIENT_SEND: &str = "cs";
+const CLIENT_RECV: &str = "cr";
+const SERVER_SEND: &str = "ss";
+const SERVER_RECV: &str = "sr";
+const MESSAGE_SEND: &str = "ms";
+const MESSAGE_RECV: &str = "mr";
+
+// Zipkin v1 address binary annotations
+const CLIENT_ADDR: &str = "ca";
+const SERVER_ADDR: &str = "sa";
+const MESSAGE_ADDR: &str = "ma";
+
+#[derive(Default)]
+struct CoreAnnotations {
+    cs: Option<Annotation>,
+    cr: Option<Annotation>,
+    ss: Option<Annotation>,
+    sr: Option<Annotation>,
+    ms: Option<Annotation>,
+    mr: Option<Annotation>,
+}
+
+fn non_empty_endpoint(endpoint: Option<Endpoint>) -> Option<Endpoint> {
+    endpoint.and_then(|ep| {
+        let ep = Endpoint {
+            service_name: ep.service_name.filter(|name| !name.is_empty()),
+            ..ep
+        };
+        if ep.service_name.is_none() && ep.ipv4.is_none() && ep.ipv6.is_none() {
+            None
+        } else {
+            Some(ep)
+        }
+    })
+}
+
+impl Span {
+    /// A span is considered in Zipkin v1 format if it has no kind but carries
+    /// core or binary annotations
+    pub fn is_v1(&self) -> bool {
+        self.kind.is_none()
+            && (!self.binary_annotations.is_empty()
+                || self.annotations.iter().any(|annotation| {
+                    matches!(
+                        annotation.value.as_ref(),
+                        CLIENT_SEND
+                            | CLIENT_RECV
+                            | SERVER_SEND
+                            | SERVER_RECV
+                            | MESSAGE_SEND
+                            | MESSAGE_RECV
+                    )
+                }))
+    }
+
+    /// Convert a Zipkin v1 span to the v2 model: core annotations give the kind,
+    /// timestamp, duration and local endpoint, binary annotations give the remote
+    /// endpoint and tags. A v1 span shared between client and server becomes a client
+    /// span and a shared server span. A span already in v2 format is returned with its
+    /// binary annotations merged in its tags.
+    pub fn into_v2(self) -> Vec<Span> {
+        if !self.is_v1() {
+            return vec![self.with_binary_annotations_as_tags()];
+        }
+        let has_annotation = |values: &[&str]| {
+            self.annotations
+                .iter()
+                .any(|annotation| values.contains(&annotation.value.as_str()))
+        };
+        if !has_annotation(&[CLIENT_SEND, CLIENT_RECV])
+            || !has_annotation(&[SERVER_RECV, SERVER_SEND])
+        {
+            return vec![self.v1_into_v2()];
+        }
+
+        let (client, server) = self.split_shared();
+        let mut client = client.v1_into_v2();
+        let mut server = server.v1_into_v2();
+        if client.remote_endpoint.is_none() {
+            client.remote_endpoint = server.local_endpoint.clone();
+        }
+        if server.remote_endpoint.is_none() {
+            server.remote_endpoint = client.local_endpoint.clone();
+        }
+        vec![client, server]
+    }
+
+    /// Split a v1 span with both client and server annotations in its client side,
+    /// owning the explicit timestamp and duration, and its server side. Other
+    /// annotations go to the server side when logged by the server.
+    fn split_shared(self) -> (Span, Span) {
+        let server_service = self
+            .annotations
+            .iter()
+            .find(|annotation| annotation.value == SERVER_RECV || annotation.value == SERVER_SEND)
+            .and_then(|annotation| annotation.endpoint.as_ref())
+            .and_then(|endpoint| endpoint.service_name.clone());
+        let by_server = |endpoint: &Option<Endpoint>| {
+            server_service.is_some()
+                && endpoint.as_ref().and_then(|ep| ep.service_name.as_ref())
+                    == server_service.as_ref()
+        };
+
+        let (server_annotations, client_annotations): (Vec<Annotation>, Vec<Annotation>) = self
+            .annotations
+            .into_iter()
+            .partition(|annotation| match annotation.value.as_ref() {
+                SERVER_RECV | SERVER_SEND => true,
+                CLIENT_SEND | CLIENT_RECV => false,
+                _ => by_server(&annotation.endpoint),
+            });
+        let (server_binary_annotations, client_binary_annotations): (
+            Vec<BinaryTag>,
+            Vec<BinaryTag>,
+        ) = self
+            .binary_annotations
+            .into_iter()
+            .partition(|binary_annotation| match binary_annotation.key.as_ref() {
+                CLIENT_ADDR => true,
+                SERVER_ADDR => false,
+                _ => by_server(&binary_annotation.endpoint),
+            });
+
+        let server = Span {
+            trace_id: self.trace_id.clone(),
+            id: self.id.clone(),
+            parent_id: self.parent_id.clone(),
+            name: self.name.clone(),
+            kind: None,
+            duration: None,
+            timestamp: None,
+            debug: self.debug,
+            shared: true,
+            local_endpoint: None,
+            remote_endpoint: None,
+            annotations: server_annotations,
+            tags: HashMap::new(),
+            binary_annotations: server_binary_annotations,
+        };
+        let client = Span {
+            annotations: client_annotations,
+            binary_annotations: client_binary_annotations,
+            ..self
+        };
+        (client, server)
+    }
+
+    fn v1_into_v2(self) -> Span {
+        let mut core = CoreAnnotations::default();
+        let mut annotations = vec![];
+        for annotation in self.annotations {
+            match annotation.value.as_ref() {
+                CLIENT_SEND => core.cs = Some(annotation),
+                CLIENT_RECV => core.cr = Some(annotation),
+                SERVER_SEND => core.ss = Some(annotation),
+                SERVER_RECV => core.sr = Some(annotation),
+                MESSAGE_SEND => core.ms = Some(annotation),
+                MESSAGE_RECV => core.mr = Some(annotation),
+                _ => annotations.push(Annotation {
+                    endpoint: None,
+                    ..annotation
+                }),
+            }
+        }
+
+        let (kind, start, end, local_endpoint, shared) = if core.cs.is_some() || core.cr.is_some() {
+            (
+                Some(Kind::CLIENT),
+                core.cs.clone(),
+                core.cr.clone(),
+                core.cs
+                    .clone()
+                    .or_else(|| core.cr.clone())
+                    .and_then(|a| a.endpoint),
+                false,
+            )
+        } else if core.sr.is_some() || core.ss.is_some() {
+            (
+                Some(Kind::SERVER),
+                core.sr.clone(),
+                core.ss.clone(),
+                core.sr
+                    .clone()
+                    .or_else(|| core.ss.clone())
+                    .and_then(|a| a.endpoint),
+                // a server span without timestamp was started by its client
+                self.timestamp.is_none(),
+            )
+        } else if core.ms.is_some() {
+            (
+                Some(Kind::PRODUCER),
+                core.ms.clone(),
+                None,
+                core.ms.clone().and_then(|a| a.endpoint),
+                false,
+            )
+        } else if core.mr.is_some() {
+            (
+                Some(Kind::CONSUMER),
+                core.mr.clone(),
+                None,
+                core.mr.clone().and_then(|a| a.endpoint),
+                false,
+            )
+        } else {
+            (None, None, None, None, false)
+        };
+
+        let mut remote_endpoint = None;
+        let mut local_endpoint = non_empty_endpoint(local_endpoint);
+
+        let mut tags = self.tags;
+        for binary_annotation in self.binary_annotations {
+            match (binary_annotation.key.as_ref(), &kind) {
+                (SERVER_ADDR, Some(Kind::SERVER)) | (CLIENT_ADDR, Some(Kind::CLIENT)) => {
+                    if local_endpoint.is_none() {
+                        local_endpoint = non_empty_endpoint(binary_annotation.endpoint);
+                    }
+                }
+                (SERVER_ADDR, _) | (CLIENT_ADDR, Some(Kind::SERVER)) => {
+                    remote_endpoint = binary_annotation.endpoint;
+                }
+                (MESSAGE_ADDR, Some(Kind::PRODUCER)) | (MESSAGE_ADDR, Some(Kind::CONSUMER)) => {
+                    remote_endpoint = binary_annotation.endpoint;
+                }
+                (CLIENT_ADDR, _) | (MESSAGE_ADDR, _) => (),
+                (key, _) => {
+                    if local_endpoint.is_none() {
+                        local_endpoint = non_empty_endpoint(binary_annotation.endpoint);
+                    }
+                    tags.insert(key.to_string(), binary_annotation.value);
+                }
+            }
+        }
+
+        let start_ts = start.map(|a| a.timestamp);
+        let timestamp = self.timestamp.or(start_ts);
+        let duration = self.duration.or_else(|| match (start_ts, end) {
+            (Some(start_ts), Some(end)) if end.timestamp >= start_ts => {
+                Some(end.timestamp - start_ts)
+            }
+            _ => None,
+        });
+
+        Span {
+            trace_id: self.trace_id,
+            id: self.id,
+            parent_id: self.parent_id,
+            name: self
+                .name
+                .filter(|name| !name.is_empty() && name != "unknown"),
+            kind,
+            duration,
+            timestamp,
+            debug: self.debug,
+            shared: self.shared || shared,
+            local_endpoint,
+            remote_endpoint: non_empty_endpoint(remote_endpoint),
+            annotations,
+            tags,
+            binary_annotations: vec![],
+        }
+    }
+
+    fn with_binary_annotations_as_tags(self) -> Span {
+        if self.binary_annotations.is_empty() {
+            return self;
+        }
+        let mut tags: HashMap<String, String> = self
+            .binary_annotations
+            .iter()
+            .map(|BinaryTag { key, value, .. }| (key.clone(), value.clone()))
+            .collect();
+        tags.extend(self.tags);
+        Span {
+            tags,
+            binary_annotations: vec![],
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+
+    #[test]
+    fn can_convert_client_span_from_v1() {
+        let v1 = r#"{
+  "traceId": "5af7183fb1d4cf5f",
+  "name": "get /api",
+  "id": "352bff9a74ca9ad2",
+  "parentId": "6b221d5bc9e6496c",
+  "annotations": [
+    {"timestamp": 1472470996199000, "value": "cs", "endpoint": {"serviceName": "frontend", "ipv4": "127.0.0.1"}},
+    {"timestamp": 1472470996238000, "value": "ws", "endpoint": {"serviceName": "frontend", "ipv4": "127.0.0.1"}},
+    {"timestamp": 1472470996403000, "value": "cr", "endpoint": {"serviceName": "frontend", "ipv4": "127.0.0.1"}}
+  ],
+  "binaryAnnotations": [
+    {"key": "http.path", "value": "/api", "endpoint": {"serviceName": "frontend", "ipv4": "127.0.0.1"}},
+    {"key": "error", "value": true, "endpoint": {"serviceName": "frontend", "ipv4": "127.0.0.1"}},
+    {"key": "sa", "value": true, "endpoint": {"serviceName": "backend", "ipv4": "192.168.99.101", "port": 9000}}
+  ]
+}"#;
+        let span: Span = serde_json::from_str(v1).unwrap();
+        assert!(span.is_v1());
+
+        let mut spans = span.into_v2();
+        assert_eq!(spans.len(), 1);
+        let span = spans.remove(0);
+        assert!(!span.is_v1());
+        match span.kind {
+            Some(Kind::CLIENT) => (),
+            _ => panic!("span should be a client span"),
+        }
+        assert_eq!(span.timestamp, Some(1_472_470_996_199_000));
+        assert_eq!(span.duration, Some(204_000));
+        assert_eq!(
+            span.local_endpoint.and_then(|ep| ep.service_name),
+            Some("frontend".to_string())
+        );
+        assert_eq!(
+            span.remote_endpoint.map(|ep| (ep.service_name, ep.port)),
+            Some((Some("backend".to_string()), Some(9000)))
+        );
+        assert_eq!(span.annotations.len(), 1);
+        assert_eq!(span.annotations[0].value, "ws");
+        assert_eq!(span.tags.get("http.path"), Some(&"/api".to_string()));
+        assert_eq!(span.tags.get("error"), Some(&"true".to_string()));
+        assert!(span.binary_annotations.is_empty());
+    }
+
+    #[test]
+    fn can_convert_server_span_from_v1() {
+        let v1 = r#"{
+  "traceId": "5af7183fb1d4cf5f",
+  "name": "get /api",
+  "id": "352bff9a74ca9ad2",
+  "annotations": [
+    {"timestamp": 1472470996250000, "value": "sr", "endpoint": {"serviceName": "backend", "ipv4": "192.168.99.101"}},
+    {"timestamp": 1472470996350000, "value": "ss", "endpoint": {"serviceName": "backend", "ipv4": "192.168.99.101"}}
+  ],
+  "binaryAnnotations": [
+    {"key": "ca", "value": true, "endpoint": {"serviceName": "frontend", "ipv4": "127.0.0.1"}},
+    {"key": "test.class", "value": "ApiTest", "endpoint": {"serviceName": "backend", "ipv4": "192.168.99.101"}}
+  ]
+}"#;
+        let span: Span = serde_json::from_str(v1).unwrap();
+        let span = span.into_v2().remove(0);
+        match span.kind {
+            Some(Kind::SERVER) => (),
+            _ => panic!("span should be a server span"),
+        }
+        assert!(span.shared);
+        assert_eq!(span.duration, Some(100_000));
+        assert_eq!(
+            span.local_endpoint.and_then(|ep| ep.service_name),
+            Some("backend".to_string())
+        );
+        assert_eq!(
+            span.remote_endpoint.and_then(|ep| ep.service_name),
+            Some("frontend".to_string())
+        );
+        assert_eq!(span.tags.get("test.class"), Some(&"ApiTest".to_string()));
+    }
+
+    #[test]
+    fn can_split_shared_span_from_v1() {
+        let v1 = r#"{
+  "traceId": "5af7183fb1d4cf5f",
+  "name": "get /api",
+  "id": "352bff9a74ca9ad2",
+  "parentId": "6b221d5bc9e6496c",
+  "timestamp": 1472470996199000,
+  "duration": 207000,
+  "annotations": [
+    {"timestamp": 1472470996199000, "value": "cs", "endpoint": {"serviceName": "frontend", "ipv4": "127.0.0.1"}},
+    {"timestamp": 1472470996250000, "value": "sr", "endpoint": {"serviceName": "backend", "ipv4": "192.168.99.101"}},
+    {"timestamp": 1472470996300000, "value": "cache miss", "endpoint": {"serviceName": "backend", "ipv4": "192.168.99.101"}},
+    {"timestamp": 1472470996350000, "value": "ss", "endpoint": {"serviceName": "backend", "ipv4": "192.168.99.101"}},
+    {"timestamp": 1472470996406000, "value": "cr", "endpoint": {"serviceName": "frontend", "ipv4": "127.0.0.1"}}
+  ],
+  "binaryAnnotations": [
+    {"key": "http.path", "value": "/api", "endpoint": {"serviceName": "frontend", "ipv4": "127.0.0.1"}},
+    {"key": "db.instance", "value": "users", "endpoint": {"serviceName": "backend", "ipv4": "192.168.99.101"}}
+  ]
+}"#;
+        let span: Span = serde_json::from_str(v1).unwrap();
+        let spans = span.into_v2();
+        assert_eq!(spans.len(), 2);
+
+        let client = &spans[0];
+        match client.kind {
+            Some(Kind::CLIENT) => (),
+            _ => panic!("first span should be the client side"),
+        }
+        assert!(!client.shared);
+        assert_eq!(client.timestamp, Some(1_472_470_996_199_000));
+        assert_eq!(client.duration, Some(207_000));
+        assert_eq!(
+            client.local_endpoint.clone().and_then(|ep| ep.service_name),
+            Some("frontend".to_string())
+        );
+        assert_eq!(
+            client
+                .remote_endpoint
+                .clone()
+                .and_then(|ep| ep.service_name),
+            Some("backend".to_string())
+        );
+        assert!(client.annotations.is_empty());
+        assert_eq!(client.tags.get("http.path"), Some(&"/api".to_string()));
+        assert_eq!(client.tags.get("db.instance"), None);
+
+        let server = &spans[1];
+        match server.kind {
+            Some(Kind::SERVER) => (),
+            _ => panic!("second span should be the server side"),
+        }
+        assert!(server.shared);
+        assert_eq!(server.id, client.id);
+        assert_eq!(server.parent_id, client.parent_id);
+        assert_eq!(server.timestamp, Some(1_472_470_996_250_000));
+        assert_eq!(server.duration, Some(100_000));
+        assert_eq!(
+            server.local_endpoint.clone().and_then(|ep| ep.service_name),
+            Some("backend".to_string())
+        );
+        assert_eq!(
+            server
+                .remote_endpoint
+                .clone()
+                .and_then(|ep| ep.service_name),
+            Some("frontend".to_string())
+        );
+        assert_eq!(server.annotations.len(), 1);
+        assert_eq!(server.annotations[0].value, "cache miss");
+        assert_eq!(server.tags.get("db.instance"), Some(&"users".to_string()));
+        assert_eq!(server.tags.get("http.path"), None);
+    }
+
+    #[test]
+    fn keeps_v2_span_as_is() {
+        let v2 = r#"{
+  "traceId": "5af7183fb1d4cf5f",
+  "name": "get /api",
+  "id": "352bff9a74ca9ad2",
+  "kind": "CLIENT",
+  "timestamp": 1472470996199000,
+  "duration": 207000,
+  "localEndpoint": {"serviceName": "frontend"},
+  "tags": {"http.path": "/api"}
+}"#;
+        let span: Span = serde_json::from_str(v2).unwrap();
+        assert!(!span.is_v1());
+        let span = span.into_v2().remove(0);
+        assert_eq!(span.duration, Some(207_000));
+        assert_eq!(span.tags.len(), 1);
+    }
+}
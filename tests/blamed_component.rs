@@ -0,0 +1,82 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::report::Report;
+use ikrelln::engine::test_result::TestResult;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn should_blame_service_of_deepest_failed_span() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().to_string();
+    let service_name = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(vec![
+            SpanBuilder::test(&trace_id, "test_class", "failure")
+                .trace(&trace_id)
+                .build(),
+            SpanBuilder::new("checkout")
+                .child_of(&trace_id, &trace_id)
+                .id("checkout")
+                .tag("error", "true")
+                .build(),
+            SpanBuilder::new("get_stock")
+                .child_of(&trace_id, "checkout")
+                .id("get_stock")
+                .tag("http.status_code", "503")
+                .remote_service(&service_name)
+                .build(),
+        ])
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_REPORT_SAVED_MILLISECONDS,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?traceId={}", trace_id),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+    let blamed_component = data_tr[0].blamed_component.clone().unwrap();
+    assert_eq!(blamed_component.service, Some(service_name.clone()));
+    assert_eq!(blamed_component.span_name, Some("get_stock".to_string()));
+
+    let req_report = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/reports/failures/{}", service_name),
+        )
+        .finish()
+        .unwrap();
+    let response_report = srv.execute(req_report.send()).unwrap();
+    assert!(response_report.status().is_success());
+    let data_report: Report =
+        serde_json::from_slice(&srv.execute(response_report.body()).unwrap()).unwrap();
+    assert_eq!(data_report.group, "failures".to_string());
+    assert!(data_report.categories.unwrap().contains_key("get_stock"));
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
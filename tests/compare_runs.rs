@@ -0,0 +1,126 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::engine::compare::{ComparedSide, Comparison};
+use ikrelln::opentracing::tags::IkrellnTags;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn should_compare_two_runs() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let suite = uuid::Uuid::new_v4().to_string();
+    let base_run = uuid::Uuid::new_v4().to_string();
+    let target_run = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(vec![
+            SpanBuilder::test("compared_test", "failing", "success")
+                .tag(IkrellnTags::Suite, &suite)
+                .tag(IkrellnTags::RunId, &base_run)
+                .duration(10_000)
+                .timestamp(50_000)
+                .build(),
+            SpanBuilder::test("compared_test", "fixed", "failure")
+                .tag(IkrellnTags::Suite, &suite)
+                .tag(IkrellnTags::RunId, &base_run)
+                .duration(10_000)
+                .timestamp(50_000)
+                .build(),
+            SpanBuilder::test("compared_test", "slower", "success")
+                .tag(IkrellnTags::Suite, &suite)
+                .tag(IkrellnTags::RunId, &base_run)
+                .duration(10_000)
+                .timestamp(50_000)
+                .build(),
+            SpanBuilder::test("compared_test", "removed", "success")
+                .tag(IkrellnTags::Suite, &suite)
+                .tag(IkrellnTags::RunId, &base_run)
+                .duration(10_000)
+                .timestamp(50_000)
+                .build(),
+            SpanBuilder::test("compared_test", "failing", "failure")
+                .tag(IkrellnTags::Suite, &suite)
+                .tag(IkrellnTags::RunId, &target_run)
+                .duration(10_000)
+                .timestamp(50_000)
+                .build(),
+            SpanBuilder::test("compared_test", "fixed", "success")
+                .tag(IkrellnTags::Suite, &suite)
+                .tag(IkrellnTags::RunId, &target_run)
+                .duration(10_000)
+                .timestamp(50_000)
+                .build(),
+            SpanBuilder::test("compared_test", "slower", "success")
+                .tag(IkrellnTags::Suite, &suite)
+                .tag(IkrellnTags::RunId, &target_run)
+                .duration(40_000)
+                .timestamp(50_000)
+                .build(),
+            SpanBuilder::test("compared_test", "added", "success")
+                .tag(IkrellnTags::Suite, &suite)
+                .tag(IkrellnTags::RunId, &target_run)
+                .duration(10_000)
+                .timestamp(50_000)
+                .build(),
+        ])
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_compare = srv
+        .client(
+            http::Method::GET,
+            &format!(
+                "/api/v1/runs/compare?baseRun={}&targetRun={}",
+                base_run, target_run
+            ),
+        )
+        .finish()
+        .unwrap();
+    let response_compare = srv.execute(req_compare.send()).unwrap();
+    assert!(response_compare.status().is_success());
+    let data: Comparison =
+        serde_json::from_slice(&srv.execute(response_compare.body()).unwrap()).unwrap();
+    assert_eq!(data.base, ComparedSide::Run(base_run));
+    assert_eq!(data.categories.len(), 1);
+    let changes = &data.categories[&suite];
+    assert_eq!(changes.newly_failing.len(), 1);
+    assert_eq!(changes.newly_failing[0].path[1], "failing");
+    assert_eq!(changes.newly_passing.len(), 1);
+    assert_eq!(changes.newly_passing[0].path[1], "fixed");
+    assert_eq!(changes.appeared.len(), 1);
+    assert_eq!(changes.appeared[0].path[1], "added");
+    assert_eq!(changes.disappeared.len(), 1);
+    assert_eq!(changes.disappeared[0].path[1], "removed");
+    assert_eq!(changes.duration_changes.len(), 1);
+    assert_eq!(changes.duration_changes[0].path[1], "slower");
+    assert_eq!(changes.duration_changes[0].base_duration, 10_000);
+
+    let req_invalid = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/runs/compare?baseRun={}", target_run),
+        )
+        .finish()
+        .unwrap();
+    let response_invalid = srv.execute(req_invalid.send()).unwrap();
+    assert_eq!(response_invalid.status(), http::StatusCode::BAD_REQUEST);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,91 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::engine::baseline::{DurationBaseline, DurationRegression};
+use ikrelln::engine::test_result::TestResult;
+use ikrelln::opentracing::Span;
+
+use helpers::SpanBuilder;
+
+fn get<T>(srv: &mut actix_web::test::TestServer, path: &str) -> T
+where
+    T: serde::de::DeserializeOwned,
+{
+    let req = srv.client(http::Method::GET, path).finish().unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap()
+}
+
+#[test]
+fn should_detect_duration_regression() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let class = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(
+            (0..6)
+                .map(|i| {
+                    SpanBuilder::test("slow_test", &class, "success")
+                        .duration(10_000 + i)
+                        .timestamp(50 + i)
+                        .build()
+                })
+                .collect::<Vec<Span>>(),
+        )
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let slow_trace_id = uuid::Uuid::new_v4().to_string();
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(vec![SpanBuilder::test("slow_test", &class, "success")
+            .trace(&slow_trace_id)
+            .duration(40_000)
+            .timestamp(100)
+            .build()])
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let test_results: Vec<TestResult> = get(
+        &mut srv,
+        &format!("/api/v1/testresults?traceId={}", slow_trace_id),
+    );
+    assert_eq!(test_results.len(), 1);
+    let test_id = test_results[0].test_id.clone();
+
+    let regressions: Vec<DurationRegression> = get(
+        &mut srv,
+        &format!("/api/v1/tests/regressions?testId={}", test_id),
+    );
+    assert_eq!(regressions.len(), 1);
+    assert_eq!(regressions[0].trace_id, slow_trace_id);
+    assert_eq!(regressions[0].baseline.nb_results, 6);
+    assert!(regressions[0].ratio > 3.0);
+
+    let baseline: DurationBaseline = get(&mut srv, &format!("/api/v1/tests/{}/baseline", test_id));
+    assert_eq!(baseline.nb_results, 7);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
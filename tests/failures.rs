@@ -0,0 +1,90 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::engine::failure::FailureCluster;
+use ikrelln::opentracing::Span;
+
+use helpers::SpanBuilder;
+
+fn failed_test_spans(class: &str, error_kind: &str, port: i64, timestamp: i64) -> Vec<Span> {
+    let test = SpanBuilder::test("failed_test", class, "failure")
+        .duration(25_000)
+        .timestamp(timestamp)
+        .build();
+    let call = SpanBuilder::new("db_call")
+        .child_of(&test.trace_id, &test.id)
+        .duration(10_000)
+        .timestamp(timestamp + 5_000)
+        .tag("error", "true")
+        .tag("error.kind", error_kind)
+        .annotation(
+            timestamp + 15_000,
+            &format!(
+                "{{\"event\": \"error\", \"message\": \"connection refused on port {}\"}}",
+                port
+            ),
+        )
+        .build();
+    vec![test, call]
+}
+
+#[test]
+fn should_cluster_failures_by_signature() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let error_kind = uuid::Uuid::new_v4().to_string();
+    let class_a = uuid::Uuid::new_v4().to_string();
+    let class_b = uuid::Uuid::new_v4().to_string();
+
+    let mut spans = failed_test_spans(&class_a, &error_kind, 5432, 50_000);
+    spans.extend(failed_test_spans(&class_b, &error_kind, 5433, 60_000));
+    spans.extend(failed_test_spans(&class_a, &error_kind, 5434, 70_000));
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(spans)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_failures = srv
+        .client(http::Method::GET, "/api/v1/failures")
+        .finish()
+        .unwrap();
+    let response_failures = srv.execute(req_failures.send()).unwrap();
+    assert!(response_failures.status().is_success());
+    let data: Vec<FailureCluster> =
+        serde_json::from_slice(&srv.execute(response_failures.body()).unwrap()).unwrap();
+    let cluster = data
+        .iter()
+        .find(|cluster| cluster.kind.as_ref() == Some(&error_kind))
+        .expect("failures should be clustered");
+    assert_eq!(
+        cluster.signature,
+        format!("{}: connection refused on port <n>", error_kind)
+    );
+    assert_eq!(cluster.nb_failures, 3);
+    assert_eq!(cluster.first_seen, 50_000);
+    assert_eq!(cluster.last_seen, 70_000);
+    assert_eq!(cluster.tests.len(), 2);
+    assert_eq!(cluster.tests[0].path[1], class_a);
+    assert_eq!(cluster.tests[0].nb_failures, 2);
+    assert_eq!(
+        cluster.message,
+        Some("connection refused on port 5434".to_string())
+    );
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,77 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::engine::flaky::FlakyTest;
+use ikrelln::opentracing::tags::IkrellnTags;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn should_detect_flaky_test_in_same_build() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let class = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(vec![
+            SpanBuilder::test("flaky_test", &class, "success")
+                .tag(IkrellnTags::BuildId, "41")
+                .timestamp(50)
+                .build(),
+            SpanBuilder::test("flaky_test", &class, "success")
+                .tag(IkrellnTags::BuildId, "42")
+                .timestamp(60)
+                .build(),
+            SpanBuilder::test("flaky_test", &class, "failure")
+                .tag(IkrellnTags::BuildId, "42")
+                .timestamp(70)
+                .build(),
+        ])
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_flaky = srv
+        .client(http::Method::GET, "/api/v1/tests/flaky")
+        .finish()
+        .unwrap();
+    let response_flaky = srv.execute(req_flaky.send()).unwrap();
+    assert!(response_flaky.status().is_success());
+    let data: Vec<FlakyTest> =
+        serde_json::from_slice(&srv.execute(response_flaky.body()).unwrap()).unwrap();
+    let flaky_test = data
+        .iter()
+        .find(|flaky_test| flaky_test.path == vec!["test_suite".to_string(), class.clone()])
+        .expect("test should be flaky");
+    assert_eq!(flaky_test.nb_results, 3);
+    assert_eq!(flaky_test.builds, vec!["42".to_string()]);
+
+    let req_test = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/tests/{}", flaky_test.test_id),
+        )
+        .finish()
+        .unwrap();
+    let response_test = srv.execute(req_test.send()).unwrap();
+    assert!(response_test.status().is_success());
+    let data: serde_json::Value =
+        serde_json::from_slice(&srv.execute(response_test.body()).unwrap()).unwrap();
+    assert!(data["flaky"].as_f64().unwrap() > 0.0);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -5,22 +5,32 @@ extern crate chrono;
 extern crate fern;
 extern crate log;
 extern crate serde_json;
+extern crate uuid;
 
 extern crate ikrelln;
 
 use std as TheStd;
+use std::collections::HashMap;
 
 use actix_web::test::TestServer;
 
 use ikrelln::api::http_application;
+use ikrelln::opentracing::span::{Annotation, Endpoint, Kind};
+use ikrelln::opentracing::tags::IkrellnTags;
+use ikrelln::opentracing::Span;
 
 pub const DELAY_SPAN_SAVED_MILLISECONDS: u64 = 200;
-pub const DELAY_RESULT_SAVED_MILLISECONDS: u64 = 200;
-pub const DELAY_REPORT_SAVED_MILLISECONDS: u64 = 500;
+pub const DELAY_RESULT_SAVED_MILLISECONDS: u64 = 1000;
+pub const DELAY_REPORT_SAVED_MILLISECONDS: u64 = 1500;
 pub const DELAY_SCRIPT_SAVED_MILLISECONDS: u64 = 100;
 pub const DELAY_FINISH: u64 = 500;
 
 pub fn setup_server() -> TestServer {
+    // without a database configured, tests run against the in-memory storage
+    if std::env::var("DATABASE_URL").is_err() {
+        std::env::set_var("DATABASE_URL", "memory://");
+    }
+    ikrelln::migrate().expect("failed to migrate the DB");
     TestServer::with_factory(http_application)
 }
 
@@ -46,3 +56,109 @@ pub fn setup_logger() {
             .unwrap();
     }
 }
+
+/// Span sent by tests, by default the root client span of a new trace
+pub struct SpanBuilder(Span);
+
+impl SpanBuilder {
+    pub fn new(name: &str) -> Self {
+        let trace_id = uuid::Uuid::new_v4().to_string();
+        SpanBuilder(Span {
+            trace_id: trace_id.clone(),
+            id: trace_id,
+            parent_id: None,
+            name: Some(name.to_string()),
+            kind: Some(Kind::CLIENT),
+            duration: Some(25),
+            timestamp: Some(50),
+            debug: false,
+            shared: false,
+            local_endpoint: None,
+            remote_endpoint: None,
+            annotations: vec![],
+            tags: HashMap::new(),
+            binary_annotations: vec![],
+        })
+    }
+
+    /// Root span of a test of `test_suite`
+    pub fn test(name: &str, class: &str, result: &str) -> Self {
+        SpanBuilder::new(name)
+            .tag(IkrellnTags::Suite, "test_suite")
+            .tag(IkrellnTags::Class, class)
+            .tag(IkrellnTags::Result, result)
+    }
+
+    /// Make this the root span of `trace_id`
+    pub fn trace(mut self, trace_id: &str) -> Self {
+        self.0.trace_id = trace_id.to_string();
+        self.0.id = trace_id.to_string();
+        self
+    }
+
+    /// Make this a new span of the trace of `parent_id`
+    pub fn child_of(mut self, trace_id: &str, parent_id: &str) -> Self {
+        self.0.trace_id = trace_id.to_string();
+        self.0.id = uuid::Uuid::new_v4().to_string();
+        self.0.parent_id = Some(parent_id.to_string());
+        self
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.0.id = id.to_string();
+        self
+    }
+
+    pub fn kind(mut self, kind: Kind) -> Self {
+        self.0.kind = Some(kind);
+        self
+    }
+
+    pub fn duration(mut self, duration: i64) -> Self {
+        self.0.duration = Some(duration);
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.0.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn service(mut self, service_name: &str) -> Self {
+        self.0.local_endpoint = Some(Endpoint {
+            service_name: Some(service_name.to_string()),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn remote_service(mut self, service_name: &str) -> Self {
+        self.0.remote_endpoint = Some(Endpoint {
+            service_name: Some(service_name.to_string()),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn tag<T>(mut self, tag: T, value: &str) -> Self
+    where
+        &'static str: From<T>,
+    {
+        let tag: &str = tag.into();
+        self.0.tags.insert(tag.to_string(), value.to_string());
+        self
+    }
+
+    pub fn annotation(mut self, timestamp: i64, value: &str) -> Self {
+        self.0.annotations.push(Annotation {
+            timestamp,
+            value: value.to_string(),
+            endpoint: None,
+        });
+        self
+    }
+
+    pub fn build(self) -> Span {
+        self.0
+    }
+}
@@ -0,0 +1,105 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::test_result::{StepType, TestResult, TestStatus};
+use ikrelln::opentracing::Span;
+
+#[test]
+fn should_create_test_results_with_steps_from_cucumber_report() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let feature = uuid::Uuid::new_v4().to_string();
+    let environment = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(
+            http::Method::POST,
+            &format!("/api/v1/import/cucumber?environment={}", environment),
+        )
+        .content_type("application/json")
+        .body(format!(
+            r#"[{{
+  "uri": "features/login.feature",
+  "name": "{feature}",
+  "elements": [{{
+    "name": "Successful login",
+    "type": "scenario",
+    "steps": [
+      {{ "keyword": "Given ", "name": "a user", "result": {{ "status": "passed", "duration": 1000000 }} }},
+      {{ "keyword": "When ", "name": "the user logs in", "result": {{ "status": "passed", "duration": 2000000 }} }}
+    ]
+  }}]
+}}]"#,
+            feature = feature
+        ))
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: IngestResponse =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap();
+    assert_eq!(data.nb_events, 3);
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?environment={}", &environment),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+    assert_eq!(
+        data_tr[0].path,
+        vec![feature, "features/login.feature".to_string()]
+    );
+    assert_eq!(data_tr[0].name, "Successful login");
+    assert_eq!(data_tr[0].status, TestStatus::Success);
+    assert_eq!(data_tr[0].duration, 3_000);
+    assert_eq!(
+        data_tr[0]
+            .steps
+            .iter()
+            .map(|step| step.step_type.clone())
+            .collect::<Vec<StepType>>(),
+        vec![StepType::Setup, StepType::Action]
+    );
+
+    let req_trace = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/trace/{}", &data_tr[0].trace_id),
+        )
+        .finish()
+        .unwrap();
+    let response_trace = srv.execute(req_trace.send()).unwrap();
+    assert!(response_trace.status().is_success());
+    let data_trace: Vec<Span> =
+        serde_json::from_slice(&srv.execute(response_trace.body()).unwrap()).unwrap();
+    assert_eq!(data_trace.len(), 3);
+    assert_eq!(
+        data_trace
+            .iter()
+            .filter(|span| span.tags.contains_key("test.step_type"))
+            .count(),
+        2
+    );
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
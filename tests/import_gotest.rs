@@ -0,0 +1,66 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::test_result::{TestResult, TestStatus};
+
+#[test]
+fn should_create_test_results_from_go_test_events() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let package = uuid::Uuid::new_v4().to_string();
+    let environment = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(
+            http::Method::POST,
+            &format!("/api/v1/import/gotest?environment={}", environment),
+        )
+        .content_type("application/json")
+        .body(format!(
+            r#"{{"Action":"run","Package":"{package}","Test":"TestSave"}}
+{{"Action":"run","Package":"{package}","Test":"TestSave/empty"}}
+{{"Action":"pass","Package":"{package}","Test":"TestSave/empty","Elapsed":0.1}}
+{{"Action":"pass","Package":"{package}","Test":"TestSave","Elapsed":0.5}}
+{{"Action":"pass","Package":"{package}","Elapsed":0.5}}"#,
+            package = package
+        ))
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: IngestResponse =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap();
+    assert_eq!(data.nb_events, 2);
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?environment={}", &environment),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+    assert_eq!(data_tr[0].path, vec![package, "TestSave".to_string()]);
+    assert_eq!(data_tr[0].name, "TestSave");
+    assert_eq!(data_tr[0].status, TestStatus::Success);
+    assert_eq!(data_tr[0].duration, 500_000);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,88 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::test_result::{TestResult, TestStatus};
+
+#[test]
+fn should_create_test_results_from_junit_report() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let suite = uuid::Uuid::new_v4().to_string();
+    let environment = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(
+            http::Method::POST,
+            &format!("/api/v1/import/junit?environment={}", environment),
+        )
+        .content_type("application/xml")
+        .body(format!(
+            r#"<testsuite name="{}" timestamp="2019-05-06T10:20:30">
+  <testcase classname="JunitTest" name="passes" time="0.5"/>
+  <testcase classname="JunitTest" name="fails" time="1.0">
+    <error message="NullPointerException"/>
+  </testcase>
+</testsuite>"#,
+            suite
+        ))
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: IngestResponse =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap();
+    assert_eq!(data.nb_events, 2);
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?environment={}", &environment),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let mut data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    data_tr.sort_by_key(|tr| tr.date);
+    assert_eq!(data_tr.len(), 2);
+    assert_eq!(
+        data_tr[0].path,
+        vec![suite.clone(), "JunitTest".to_string()]
+    );
+    assert_eq!(data_tr[0].name, "passes");
+    assert_eq!(data_tr[0].status, TestStatus::Success);
+    assert_eq!(data_tr[1].name, "fails");
+    assert_eq!(data_tr[1].status, TestStatus::Failure);
+    assert_eq!(data_tr[1].duration, 1_000_000);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
+
+#[test]
+fn should_reject_invalid_junit_report() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/import/junit")
+        .content_type("application/xml")
+        .body("<testsuite><testcase></testsuite>")
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,67 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::test_result::{TestResult, TestStatus};
+
+#[test]
+fn should_create_test_results_from_libtest_events() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let crate_name = uuid::Uuid::new_v4().to_string();
+    let environment = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(
+            http::Method::POST,
+            &format!(
+                "/api/v1/import/libtest?crate={}&environment={}",
+                crate_name, environment
+            ),
+        )
+        .content_type("application/json")
+        .body(
+            r#"{ "type": "suite", "event": "started", "test_count": 1 }
+{ "type": "test", "event": "started", "name": "db::tests::can_save" }
+{ "type": "test", "name": "db::tests::can_save", "event": "failed", "exec_time": 0.5, "stdout": "panicked" }
+{ "type": "suite", "event": "failed", "passed": 0, "failed": 1, "ignored": 0, "measured": 0, "filtered_out": 0, "exec_time": 0.5 }"#,
+        )
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: IngestResponse =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap();
+    assert_eq!(data.nb_events, 1);
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?environment={}", &environment),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+    assert_eq!(data_tr[0].path, vec![crate_name, "db::tests".to_string()]);
+    assert_eq!(data_tr[0].name, "can_save");
+    assert_eq!(data_tr[0].status, TestStatus::Failure);
+    assert_eq!(data_tr[0].duration, 500_000);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,68 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::ingestor::Ingest;
+use ikrelln::opentracing::span::Kind;
+use ikrelln::opentracing::Span;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn can_receive_a_large_trace_in_one_batch() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().to_string();
+    let span = |kind: Kind| {
+        SpanBuilder::new("get")
+            .kind(kind)
+            .service("service")
+            .tag("http.method", "GET")
+    };
+    let mut spans: Vec<Span> = (0..2000)
+        .map(|_| span(Kind::CLIENT).child_of(&trace_id, &trace_id).build())
+        .collect();
+    spans.push(span(Kind::SERVER).trace(&trace_id).build());
+    // both sides of a shared span have the same ID
+    spans.push(span(Kind::CLIENT).trace(&trace_id).build());
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(spans)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: IngestResponse =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap();
+    assert_eq!(data.nb_events, 2002);
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS * 2,
+    ));
+
+    let req_ingest = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/ingest/{}", data.ingest_id.0),
+        )
+        .finish()
+        .unwrap();
+    let response_ingest = srv.execute(req_ingest.send()).unwrap();
+    assert!(response_ingest.status().is_success());
+    let data_ingest: Ingest =
+        serde_json::from_slice(&srv.execute(response_ingest.body()).unwrap()).unwrap();
+    assert!(data_ingest.processed_at.is_some());
+    assert_eq!(data_ingest.nb_stored, 2002);
+    assert_eq!(data_ingest.nb_failed, 0);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
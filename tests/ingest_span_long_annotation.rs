@@ -0,0 +1,51 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::opentracing::Span;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn should_keep_long_annotation() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().to_string();
+    let stack = "at ikrelln::engine::test_result::TestResult::try_from\n".repeat(50);
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(vec![SpanBuilder::new(&trace_id)
+            .trace(&trace_id)
+            .annotation(60, &stack)
+            .build()])
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_SPAN_SAVED_MILLISECONDS,
+    ));
+
+    let req_trace = srv
+        .client(http::Method::GET, &format!("/api/v1/trace/{}", &trace_id))
+        .finish()
+        .unwrap();
+    let response_trace = srv.execute(req_trace.send()).unwrap();
+    assert!(response_trace.status().is_success());
+    let data_trace: Vec<Span> =
+        serde_json::from_slice(&srv.execute(response_trace.body()).unwrap()).unwrap();
+    assert_eq!(data_trace.len(), 1);
+    assert_eq!(data_trace[0].annotations.len(), 1);
+    assert_eq!(data_trace[0].annotations[0].value, stack);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
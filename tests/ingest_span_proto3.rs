@@ -0,0 +1,100 @@
+extern crate actix_web;
+extern crate prost;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::collections::HashMap;
+use std::{thread, time};
+
+use actix_web::*;
+use prost::Message;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::test_result::TestResult;
+use ikrelln::opentracing::zipkin_proto3;
+
+#[test]
+fn should_create_test_result_from_protobuf_spans() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().as_bytes()[..8].to_vec();
+    let class = uuid::Uuid::new_v4().to_string();
+
+    let mut tags = HashMap::new();
+    tags.insert("test.class".to_string(), class.clone());
+    tags.insert("test.result".to_string(), "success".to_string());
+    let spans = zipkin_proto3::ListOfSpans {
+        spans: vec![zipkin_proto3::Span {
+            trace_id: trace_id.clone(),
+            parent_id: vec![],
+            id: trace_id.clone(),
+            kind: zipkin_proto3::Kind::Server as i32,
+            name: "test_proto3".to_string(),
+            timestamp: 1_000_000,
+            duration: 1000,
+            local_endpoint: Some(zipkin_proto3::Endpoint {
+                service_name: "test-suite".to_string(),
+                ..Default::default()
+            }),
+            remote_endpoint: None,
+            annotations: vec![],
+            tags,
+            debug: false,
+            shared: false,
+        }],
+    };
+    let mut body = vec![];
+    spans.encode(&mut body).unwrap();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v2/spans")
+        .content_type("application/x-protobuf")
+        .body(body)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: Result<IngestResponse, _> =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap());
+    assert!(data.is_ok());
+    assert_eq!(data.unwrap().nb_events, 1);
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let hex_trace_id: String = trace_id.iter().map(|b| format!("{:02x}", b)).collect();
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?traceId={}", &hex_trace_id),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+    assert_eq!(data_tr[0].path, vec!["test-suite".to_string(), class]);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
+
+#[test]
+fn should_reject_invalid_protobuf_payload() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .content_type("application/x-protobuf")
+        .body(vec![0x0a, 0xff, 0xff])
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,156 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate thrift;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+use thrift::protocol::{
+    TBinaryOutputProtocol, TFieldIdentifier, TListIdentifier, TOutputProtocol, TStructIdentifier,
+    TType,
+};
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::opentracing::Span;
+
+fn encode_span(id: i64, name: &str) -> Vec<u8> {
+    let mut body = vec![];
+    {
+        let mut o_prot = TBinaryOutputProtocol::new(&mut body, true);
+        o_prot
+            .write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+            .unwrap();
+        o_prot
+            .write_struct_begin(&TStructIdentifier::new("Span"))
+            .unwrap();
+        for (field_id, value) in &[(1, id), (4, id), (10, 1_000_000), (11, 1000)] {
+            o_prot
+                .write_field_begin(&TFieldIdentifier::new::<_, String, _>(
+                    None,
+                    TType::I64,
+                    *field_id,
+                ))
+                .unwrap();
+            o_prot.write_i64(*value).unwrap();
+            o_prot.write_field_end().unwrap();
+        }
+        o_prot
+            .write_field_begin(&TFieldIdentifier::new::<_, String, _>(
+                None,
+                TType::String,
+                3,
+            ))
+            .unwrap();
+        o_prot.write_string(name).unwrap();
+        o_prot.write_field_end().unwrap();
+        o_prot.write_field_stop().unwrap();
+        o_prot.write_struct_end().unwrap();
+        o_prot.write_list_end().unwrap();
+    }
+    body
+}
+
+#[test]
+fn can_receive_thrift_span() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let mut id_bytes = [0; 8];
+    id_bytes.copy_from_slice(&uuid::Uuid::new_v4().as_bytes()[..8]);
+    let id = i64::from_be_bytes(id_bytes);
+    let name = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .content_type("application/x-thrift")
+        .body(encode_span(id, &name))
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: IngestResponse =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap();
+    assert_eq!(data.nb_events, 1);
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_SPAN_SAVED_MILLISECONDS,
+    ));
+
+    let req_trace = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/trace/{:016x}", id as u64),
+        )
+        .finish()
+        .unwrap();
+    let response_trace = srv.execute(req_trace.send()).unwrap();
+    assert!(response_trace.status().is_success());
+    let data_trace: Vec<Span> =
+        serde_json::from_slice(&srv.execute(response_trace.body()).unwrap()).unwrap();
+    assert_eq!(data_trace.len(), 1);
+    assert_eq!(data_trace[0].name, Some(name));
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
+
+#[test]
+fn should_reject_thrift_list_larger_than_body() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let mut body = vec![];
+    {
+        let mut o_prot = TBinaryOutputProtocol::new(&mut body, true);
+        o_prot
+            .write_list_begin(&TListIdentifier::new(TType::Struct, i32::MAX))
+            .unwrap();
+    }
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .content_type("application/x-thrift")
+        .body(body)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
+
+#[test]
+fn should_reject_thrift_string_length_negative_or_larger_than_body() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    for length in &[-1, i32::MAX] {
+        let mut body = vec![];
+        {
+            let mut o_prot = TBinaryOutputProtocol::new(&mut body, true);
+            o_prot
+                .write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+                .unwrap();
+            o_prot
+                .write_struct_begin(&TStructIdentifier::new("Span"))
+                .unwrap();
+            o_prot
+                .write_field_begin(&TFieldIdentifier::new::<_, String, _>(
+                    None,
+                    TType::String,
+                    3,
+                ))
+                .unwrap();
+            o_prot.write_i32(*length).unwrap();
+        }
+
+        let req = srv
+            .client(http::Method::POST, "/api/v1/spans")
+            .content_type("application/x-thrift")
+            .body(body)
+            .unwrap();
+        let response = srv.execute(req.send()).unwrap();
+        assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+    }
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
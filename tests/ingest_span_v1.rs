@@ -0,0 +1,158 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::test_result::TestResult;
+use ikrelln::opentracing::span::Kind;
+use ikrelln::opentracing::Span;
+
+#[test]
+fn should_create_test_result_from_v1_spans() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().to_simple().to_string()[..16].to_string();
+    let child_id = uuid::Uuid::new_v4().to_simple().to_string()[..16].to_string();
+
+    let spans: serde_json::Value = serde_json::from_str(&format!(
+        r#"[
+  {{
+    "traceId": "{trace_id}",
+    "id": "{child_id}",
+    "parentId": "{trace_id}",
+    "name": "get /api",
+    "annotations": [
+      {{"timestamp": 1000100, "value": "cs", "endpoint": {{"serviceName": "test-runner"}}}},
+      {{"timestamp": 1000300, "value": "sr", "endpoint": {{"serviceName": "backend"}}}},
+      {{"timestamp": 1000700, "value": "ss", "endpoint": {{"serviceName": "backend"}}}},
+      {{"timestamp": 1000900, "value": "cr", "endpoint": {{"serviceName": "test-runner"}}}}
+    ],
+    "binaryAnnotations": [
+      {{"key": "http.status_code", "value": 200, "endpoint": {{"serviceName": "test-runner"}}}},
+      {{"key": "db.instance", "value": "users", "endpoint": {{"serviceName": "backend"}}}},
+      {{"key": "sa", "value": true, "endpoint": {{"serviceName": "backend", "port": 8080}}}}
+    ]
+  }},
+  {{
+    "traceId": "{trace_id}",
+    "id": "{trace_id}",
+    "name": "test_v1",
+    "timestamp": 1000000,
+    "duration": 1000,
+    "annotations": [],
+    "binaryAnnotations": [
+      {{"key": "lc", "value": "junit", "endpoint": {{"serviceName": "test-runner"}}}},
+      {{"key": "test.class", "value": "V1Test", "endpoint": {{"serviceName": "test-runner"}}}},
+      {{"key": "test.result", "value": "success", "endpoint": {{"serviceName": "test-runner"}}}}
+    ]
+  }}
+]"#,
+        trace_id = trace_id,
+        child_id = child_id
+    ))
+    .unwrap();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(spans)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: Result<IngestResponse, _> =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap());
+    assert!(data.is_ok());
+    assert_eq!(data.unwrap().nb_events, 2);
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_trace = srv
+        .client(http::Method::GET, &format!("/api/v1/trace/{}", &trace_id))
+        .finish()
+        .unwrap();
+    let response_trace = srv.execute(req_trace.send()).unwrap();
+    assert!(response_trace.status().is_success());
+    let data_trace: Vec<Span> =
+        serde_json::from_slice(&srv.execute(response_trace.body()).unwrap()).unwrap();
+    assert_eq!(data_trace.len(), 3);
+    let client_span = data_trace
+        .iter()
+        .find(|span| span.id == child_id && !span.shared)
+        .expect("client span should be saved");
+    match client_span.kind {
+        Some(Kind::CLIENT) => (),
+        _ => panic!("client span should have been converted to v2"),
+    }
+    assert_eq!(client_span.duration, Some(800));
+    assert_eq!(
+        client_span
+            .remote_endpoint
+            .clone()
+            .and_then(|ep| ep.service_name),
+        Some("backend".to_string())
+    );
+    assert_eq!(
+        client_span.tags.get("http.status_code"),
+        Some(&"200".to_string())
+    );
+    assert_eq!(client_span.tags.get("db.instance"), None);
+
+    // the server side of the shared span is kept with the same ID
+    let server_span = data_trace
+        .iter()
+        .find(|span| span.id == child_id && span.shared)
+        .expect("server span should be saved");
+    match server_span.kind {
+        Some(Kind::SERVER) => (),
+        _ => panic!("server span should have been split from the client span"),
+    }
+    assert_eq!(server_span.parent_id, Some(trace_id.clone()));
+    assert_eq!(server_span.timestamp, Some(1_000_300));
+    assert_eq!(server_span.duration, Some(400));
+    assert_eq!(
+        server_span
+            .local_endpoint
+            .clone()
+            .and_then(|ep| ep.service_name),
+        Some("backend".to_string())
+    );
+    assert_eq!(
+        server_span
+            .remote_endpoint
+            .clone()
+            .and_then(|ep| ep.service_name),
+        Some("test-runner".to_string())
+    );
+    assert_eq!(
+        server_span.tags.get("db.instance"),
+        Some(&"users".to_string())
+    );
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?traceId={}", &trace_id),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+    assert_eq!(
+        data_tr[0].path,
+        vec!["test-runner".to_string(), "V1Test".to_string()]
+    );
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,71 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::ingestor::Ingest;
+
+#[test]
+fn should_track_ingest_until_processed() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let req_unknown = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/ingest/{}", uuid::Uuid::new_v4()),
+        )
+        .finish()
+        .unwrap();
+    let response_unknown = srv.execute(req_unknown.send()).unwrap();
+    assert_eq!(response_unknown.status(), http::StatusCode::NOT_FOUND);
+
+    let crate_name = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(
+            http::Method::POST,
+            &format!("/api/v1/import/libtest?crate={}", crate_name),
+        )
+        .content_type("application/json")
+        .body(
+            r#"{ "type": "test", "name": "db::tests::can_save", "event": "ok", "exec_time": 0.5 }
+{ "type": "test", "name": "db::tests::can_load", "event": "ok", "exec_time": 0.5 }"#,
+        )
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: IngestResponse =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap();
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_ingest = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/ingest/{}", data.ingest_id.0),
+        )
+        .finish()
+        .unwrap();
+    let response_ingest = srv.execute(req_ingest.send()).unwrap();
+    assert!(response_ingest.status().is_success());
+    let data_ingest: Ingest =
+        serde_json::from_slice(&srv.execute(response_ingest.body()).unwrap()).unwrap();
+    assert_eq!(data_ingest.ingest_id.0, data.ingest_id.0);
+    assert!(data_ingest.processed_at.is_some());
+    assert_eq!(data_ingest.nb_received, 2);
+    assert_eq!(data_ingest.nb_stored, 2);
+    assert_eq!(data_ingest.nb_failed, 0);
+    assert_eq!(data_ingest.nb_test_results, 2);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
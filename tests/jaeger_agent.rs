@@ -0,0 +1,140 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate thrift;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::net::UdpSocket;
+use std::sync::Once;
+use std::{thread, time};
+
+use actix_web::test::TestServer;
+use actix_web::*;
+use thrift::protocol::{
+    TCompactOutputProtocol, TFieldIdentifier, TListIdentifier, TMessageIdentifier, TMessageType,
+    TOutputProtocol, TStructIdentifier, TType,
+};
+
+use ikrelln::opentracing::Span;
+
+static START_AGENT: Once = Once::new();
+
+fn write_field<F>(o_prot: &mut dyn TOutputProtocol, id: i16, field_type: TType, write: F)
+where
+    F: FnOnce(&mut dyn TOutputProtocol),
+{
+    o_prot
+        .write_field_begin(&TFieldIdentifier::new::<_, String, _>(None, field_type, id))
+        .unwrap();
+    write(o_prot);
+    o_prot.write_field_end().unwrap();
+}
+
+/// `emitBatch` call with a batch whose spans are written by `write_spans`
+fn emit_batch<F>(write_spans: F) -> Vec<u8>
+where
+    F: FnOnce(&mut dyn TOutputProtocol),
+{
+    let mut body = vec![];
+    {
+        let mut o_prot = TCompactOutputProtocol::new(&mut body);
+        let o_prot: &mut dyn TOutputProtocol = &mut o_prot;
+        o_prot
+            .write_message_begin(&TMessageIdentifier::new(
+                "emitBatch",
+                TMessageType::OneWay,
+                1,
+            ))
+            .unwrap();
+        o_prot
+            .write_struct_begin(&TStructIdentifier::new("emitBatch_args"))
+            .unwrap();
+        write_field(o_prot, 1, TType::Struct, |o| {
+            o.write_struct_begin(&TStructIdentifier::new("Batch"))
+                .unwrap();
+            write_field(o, 2, TType::List, write_spans);
+            o.write_field_stop().unwrap();
+            o.write_struct_end().unwrap();
+        });
+        o_prot.write_field_stop().unwrap();
+        o_prot.write_struct_end().unwrap();
+        o_prot.write_message_end().unwrap();
+    }
+    body
+}
+
+#[test]
+fn can_receive_jaeger_batch_over_udp() {
+    helpers::setup_logger();
+    if std::env::var("DATABASE_URL").is_err() {
+        std::env::set_var("DATABASE_URL", "memory://");
+    }
+    ikrelln::migrate().expect("failed to migrate the DB");
+
+    let agent_port = UdpSocket::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut srv = TestServer::with_factory(move || {
+        // the agent must be started from within the actix system of the server
+        START_AGENT.call_once(|| ikrelln::api::jaeger_agent::serve("127.0.0.1", agent_port));
+        ikrelln::api::http_application()
+    });
+    let mut id_bytes = [0; 8];
+    id_bytes.copy_from_slice(&uuid::Uuid::new_v4().as_bytes()[..8]);
+    let id = i64::from_be_bytes(id_bytes);
+    let name = uuid::Uuid::new_v4().to_string();
+
+    // make sure the server and the agent are started
+    let req = srv
+        .client(http::Method::GET, "/healthcheck")
+        .finish()
+        .unwrap();
+    srv.execute(req.send()).unwrap();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    // a batch declaring more spans than it contains must not take the agent down
+    let invalid = emit_batch(|o| {
+        o.write_list_begin(&TListIdentifier::new(TType::Struct, i32::MAX))
+            .unwrap();
+    });
+    socket.send_to(&invalid, ("127.0.0.1", agent_port)).unwrap();
+    let valid = emit_batch(|o| {
+        o.write_list_begin(&TListIdentifier::new(TType::Struct, 1))
+            .unwrap();
+        o.write_struct_begin(&TStructIdentifier::new("Span"))
+            .unwrap();
+        write_field(o, 1, TType::I64, |o| o.write_i64(id).unwrap());
+        write_field(o, 3, TType::I64, |o| o.write_i64(id).unwrap());
+        write_field(o, 5, TType::String, |o| o.write_string(&name).unwrap());
+        write_field(o, 8, TType::I64, |o| o.write_i64(1_000_000).unwrap());
+        write_field(o, 9, TType::I64, |o| o.write_i64(1500).unwrap());
+        o.write_field_stop().unwrap();
+        o.write_struct_end().unwrap();
+        o.write_list_end().unwrap();
+    });
+    socket.send_to(&valid, ("127.0.0.1", agent_port)).unwrap();
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_trace = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/trace/{:016x}", id as u64),
+        )
+        .finish()
+        .unwrap();
+    let response_trace = srv.execute(req_trace.send()).unwrap();
+    assert!(response_trace.status().is_success());
+    let data_trace: Vec<Span> =
+        serde_json::from_slice(&srv.execute(response_trace.body()).unwrap()).unwrap();
+    assert_eq!(data_trace.len(), 1);
+    assert_eq!(data_trace[0].name, Some(name));
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
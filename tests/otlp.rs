@@ -0,0 +1,84 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::engine::test_result::{TestResult, TestStatus};
+
+#[test]
+fn should_create_test_result_from_otlp_json() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().to_simple().to_string();
+    let span_id = trace_id[..16].to_string();
+    let suite = uuid::Uuid::new_v4().to_string();
+
+    let request: serde_json::Value = serde_json::from_str(&format!(
+        r#"{{
+  "resourceSpans": [{{
+    "resource": {{"attributes": [
+      {{"key": "service.name", "value": {{"stringValue": "test-runner"}}}},
+      {{"key": "deployment.environment", "value": {{"stringValue": "ci"}}}}
+    ]}},
+    "scopeSpans": [{{
+      "spans": [{{
+        "traceId": "{trace_id}",
+        "spanId": "{span_id}",
+        "name": "test_login",
+        "kind": 1,
+        "startTimeUnixNano": "1000000000",
+        "endTimeUnixNano": "1002000000",
+        "attributes": [
+          {{"key": "test.suite.name", "value": {{"stringValue": "{suite}"}}}},
+          {{"key": "code.namespace", "value": {{"stringValue": "tests.test_auth"}}}},
+          {{"key": "test.case.result.status", "value": {{"stringValue": "fail"}}}}
+        ],
+        "status": {{"code": 2, "message": "assertion failed"}}
+      }}]
+    }}]
+  }}]
+}}"#,
+        trace_id = trace_id,
+        span_id = span_id,
+        suite = suite
+    ))
+    .unwrap();
+
+    let req = srv
+        .client(http::Method::POST, "/v1/traces")
+        .json(request)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?traceId={}", &trace_id),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+    assert_eq!(data_tr[0].path, vec![suite, "tests.test_auth".to_string()]);
+    assert_eq!(data_tr[0].name, "test_login");
+    assert_eq!(data_tr[0].status, TestStatus::Failure);
+    assert_eq!(data_tr[0].duration, 2000);
+    assert_eq!(data_tr[0].environment, Some("ci".to_string()));
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,68 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::test_result::TestResult;
+use ikrelln::opentracing::Span;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn should_parse_whole_trace_of_more_than_1000_spans() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().to_string();
+    // children come first by timestamp, the root span is after the first 1000 spans
+    let mut spans: Vec<Span> = (0..1500)
+        .map(|_| {
+            SpanBuilder::new("child")
+                .child_of(&trace_id, &trace_id)
+                .timestamp(40)
+                .build()
+        })
+        .collect();
+    spans.push(
+        SpanBuilder::test("span_name", "test_class", "success")
+            .trace(&trace_id)
+            .build(),
+    );
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(spans)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: IngestResponse =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap();
+    assert_eq!(data.nb_events, 1501);
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS * 2,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?traceId={}", &trace_id),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+    assert_eq!(data_tr[0].nb_spans, 1501);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,86 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::api::span::IngestResponse;
+use ikrelln::engine::test_result::TestResult;
+use ikrelln::opentracing::Span;
+
+use helpers::SpanBuilder;
+
+fn send_spans(srv: &mut actix_web::test::TestServer, spans: Vec<Span>) {
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(spans)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+    let data: IngestResponse =
+        serde_json::from_slice(&srv.execute(response.body()).unwrap()).unwrap();
+    assert_eq!(data.nb_events, 1);
+}
+
+fn get_test_results(srv: &mut actix_web::test::TestServer, trace_id: &str) -> Vec<TestResult> {
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?traceId={}", trace_id),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap()
+}
+
+#[test]
+fn should_update_test_result_with_late_spans() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().to_string();
+
+    send_spans(
+        &mut srv,
+        vec![SpanBuilder::test("span_name", "test_class", "success")
+            .trace(&trace_id)
+            .build()],
+    );
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let test_results = get_test_results(&mut srv, &trace_id);
+    assert_eq!(test_results.len(), 1);
+    assert_eq!(test_results[0].nb_spans, 1);
+
+    let service = uuid::Uuid::new_v4().to_string();
+    send_spans(
+        &mut srv,
+        vec![SpanBuilder::new("late_span")
+            .child_of(&trace_id, &trace_id)
+            .remote_service(&service)
+            .duration(10)
+            .timestamp(60)
+            .build()],
+    );
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let test_results = get_test_results(&mut srv, &trace_id);
+    assert_eq!(test_results.len(), 1);
+    assert_eq!(test_results[0].nb_spans, 2);
+    assert_eq!(test_results[0].components_called.get(&service), Some(&1));
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
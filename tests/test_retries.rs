@@ -0,0 +1,87 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::engine::flaky::FlakyTest;
+use ikrelln::engine::test_result::{TestResult, TestStatus};
+use ikrelln::opentracing::tags::IkrellnTags;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn should_link_attempts_of_a_test_in_a_run() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let class = uuid::Uuid::new_v4().to_string();
+    let run_id = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(vec![
+            SpanBuilder::test("retried_test", &class, "success")
+                .tag(IkrellnTags::RunId, "previous-run")
+                .tag(IkrellnTags::Attempt, "1")
+                .timestamp(40_000)
+                .build(),
+            SpanBuilder::test("retried_test", &class, "failure")
+                .tag(IkrellnTags::RunId, &run_id)
+                .tag(IkrellnTags::Attempt, "1")
+                .timestamp(50_000)
+                .build(),
+            SpanBuilder::test("retried_test", &class, "success")
+                .tag(IkrellnTags::RunId, &run_id)
+                .tag(IkrellnTags::Attempt, "2")
+                .timestamp(60_000)
+                .build(),
+        ])
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?runId={}", run_id),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 2);
+    assert_eq!(data_tr[0].attempt, 2);
+    assert_eq!(data_tr[0].status, TestStatus::PassedOnRetry);
+    assert_eq!(data_tr[1].attempt, 1);
+    assert_eq!(data_tr[1].status, TestStatus::Failure);
+    assert_eq!(data_tr[0].test_id, data_tr[1].test_id);
+
+    let req_flaky = srv
+        .client(http::Method::GET, "/api/v1/tests/flaky")
+        .finish()
+        .unwrap();
+    let response_flaky = srv.execute(req_flaky.send()).unwrap();
+    assert!(response_flaky.status().is_success());
+    let data: Vec<FlakyTest> =
+        serde_json::from_slice(&srv.execute(response_flaky.body()).unwrap()).unwrap();
+    let flaky_test = data
+        .iter()
+        .find(|flaky_test| flaky_test.test_id == data_tr[0].test_id)
+        .expect("test should be flaky");
+    assert_eq!(flaky_test.nb_results, 2);
+    assert_eq!(flaky_test.nb_passed_on_retry, 1);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
@@ -0,0 +1,107 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::engine::run::TestRun;
+use ikrelln::engine::test_result::TestStatus;
+use ikrelln::opentracing::tags::IkrellnTags;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn should_group_results_of_a_run() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let branch = uuid::Uuid::new_v4().to_string();
+    let class_a = uuid::Uuid::new_v4().to_string();
+    let class_b = uuid::Uuid::new_v4().to_string();
+    let previous_run_id = uuid::Uuid::new_v4().to_string();
+    let run_id = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(vec![
+            SpanBuilder::test("test_in_run", &class_a, "success")
+                .tag(IkrellnTags::RunId, &previous_run_id)
+                .tag(IkrellnTags::Branch, &branch)
+                .tag(IkrellnTags::Commit, &previous_run_id)
+                .timestamp(1_000_000)
+                .duration(10_000)
+                .build(),
+            SpanBuilder::test("test_in_run", &class_b, "success")
+                .tag(IkrellnTags::RunId, &previous_run_id)
+                .tag(IkrellnTags::Branch, &branch)
+                .tag(IkrellnTags::Commit, &previous_run_id)
+                .timestamp(1_010_000)
+                .duration(20_000)
+                .build(),
+            SpanBuilder::test("test_in_run", &class_a, "failure")
+                .tag(IkrellnTags::RunId, &run_id)
+                .tag(IkrellnTags::Branch, &branch)
+                .tag(IkrellnTags::Commit, &run_id)
+                .timestamp(2_000_000)
+                .duration(10_000)
+                .build(),
+            SpanBuilder::test("test_in_run", &class_b, "success")
+                .tag(IkrellnTags::RunId, &run_id)
+                .tag(IkrellnTags::Branch, &branch)
+                .tag(IkrellnTags::Commit, &run_id)
+                .timestamp(2_010_000)
+                .duration(30_000)
+                .build(),
+        ])
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_runs = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/runs?branch={}", branch),
+        )
+        .finish()
+        .unwrap();
+    let response_runs = srv.execute(req_runs.send()).unwrap();
+    assert!(response_runs.status().is_success());
+    let data_runs: Vec<TestRun> =
+        serde_json::from_slice(&srv.execute(response_runs.body()).unwrap()).unwrap();
+    assert_eq!(data_runs.len(), 2);
+    assert_eq!(data_runs[0].id, run_id);
+    assert_eq!(data_runs[0].commit, Some(run_id.clone()));
+    assert_eq!(data_runs[0].start, 2_000_000);
+    assert_eq!(data_runs[0].duration, 40_000);
+    assert_eq!(data_runs[1].id, previous_run_id);
+
+    let req_run = srv
+        .client(http::Method::GET, &format!("/api/v1/runs/{}", run_id))
+        .finish()
+        .unwrap();
+    let response_run = srv.execute(req_run.send()).unwrap();
+    assert!(response_run.status().is_success());
+    let data_run: TestRun =
+        serde_json::from_slice(&srv.execute(response_run.body()).unwrap()).unwrap();
+    let summary = data_run.summary.unwrap();
+    assert_eq!(summary[&TestStatus::Success], 1);
+    assert_eq!(summary[&TestStatus::Failure], 1);
+    assert_eq!(data_run.previous_run_id, Some(previous_run_id));
+    let slowest_tests = data_run.slowest_tests.unwrap();
+    assert_eq!(slowest_tests.len(), 2);
+    assert_eq!(slowest_tests[0].path[1], class_b);
+    let new_failures = data_run.new_failures.unwrap();
+    assert_eq!(new_failures.len(), 1);
+    assert_eq!(new_failures[0].path[1], class_a);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
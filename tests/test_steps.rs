@@ -0,0 +1,73 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::engine::test_result::{StepType, TestResult, TestStatus};
+use ikrelln::opentracing::tags::IkrellnTags;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn should_save_test_steps_with_test_result() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().to_string();
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(vec![
+            SpanBuilder::test(&trace_id, "test_class", "failure")
+                .trace(&trace_id)
+                .build(),
+            SpanBuilder::new("setup")
+                .child_of(&trace_id, &trace_id)
+                .id("setup")
+                .tag(IkrellnTags::StepType, "setup")
+                .build(),
+            SpanBuilder::new("assert")
+                .child_of(&trace_id, &trace_id)
+                .id("assert")
+                .tag(IkrellnTags::StepType, "assert")
+                .tag(IkrellnTags::StepStatus, "failure")
+                .tag(IkrellnTags::StepParameters, "expected 1")
+                .build(),
+        ])
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?traceId={}", trace_id),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+    let steps = &data_tr[0].steps;
+    assert_eq!(steps.len(), 2);
+    let failed_step = steps
+        .iter()
+        .find(|step| step.status == TestStatus::Failure)
+        .unwrap();
+    assert_eq!(failed_step.step_type, StepType::Assert);
+    assert_eq!(failed_step.parameters, Some("expected 1".to_string()));
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
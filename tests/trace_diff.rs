@@ -0,0 +1,124 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::engine::test_result::TestResult;
+use ikrelln::engine::trace_diff::{SpanChange, TraceDiff};
+use ikrelln::opentracing::tags::IkrellnTags;
+use ikrelln::opentracing::Span;
+
+use helpers::SpanBuilder;
+
+fn execution(
+    class: &str,
+    result: &str,
+    status_code: &str,
+    timestamp: i64,
+    environment: Option<&str>,
+) -> Vec<Span> {
+    let call = |name: &str, trace_id: &str, timestamp: i64| {
+        SpanBuilder::new(name)
+            .child_of(trace_id, trace_id)
+            .service("shop")
+            .duration(25_000)
+            .timestamp(timestamp)
+    };
+    let mut test = SpanBuilder::test("test_checkout", class, result)
+        .service("shop")
+        .duration(25_000)
+        .timestamp(timestamp);
+    if let Some(environment) = environment {
+        test = test.tag(IkrellnTags::Environment, environment);
+    }
+    let test = test.build();
+    let trace_id = test.trace_id.clone();
+    let mut spans = vec![
+        test,
+        call("get_stock", &trace_id, timestamp + 1_000)
+            .tag("http.status_code", status_code)
+            .build(),
+    ];
+    if result == "failure" {
+        spans.push(call("retry_stock", &trace_id, timestamp + 2_000).build());
+    }
+    spans
+}
+
+#[test]
+fn should_diff_traces_of_last_success_and_failure() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let class = uuid::Uuid::new_v4().to_string();
+    let failure = execution(&class, "failure", "503", 100_000, None);
+    let failure_trace_id = failure[0].trace_id.clone();
+    let mut spans = execution(&class, "success", "200", 50_000, None);
+    let success_trace_id = spans[0].trace_id.clone();
+    spans.extend(failure);
+    // later success of another environment, not compared to the failure
+    spans.extend(execution(&class, "success", "201", 75_000, Some("staging")));
+
+    let req = srv
+        .client(http::Method::POST, "/api/v1/spans")
+        .json(spans)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_RESULT_SAVED_MILLISECONDS,
+    ));
+
+    let req_tr = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/testresults?traceId={}", failure_trace_id),
+        )
+        .finish()
+        .unwrap();
+    let response_tr = srv.execute(req_tr.send()).unwrap();
+    assert!(response_tr.status().is_success());
+    let data_tr: Vec<TestResult> =
+        serde_json::from_slice(&srv.execute(response_tr.body()).unwrap()).unwrap();
+    assert_eq!(data_tr.len(), 1);
+
+    let req_diff = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v1/tests/{}/tracediff", data_tr[0].test_id),
+        )
+        .finish()
+        .unwrap();
+    let response_diff = srv.execute(req_diff.send()).unwrap();
+    assert!(response_diff.status().is_success());
+    let data: TraceDiff =
+        serde_json::from_slice(&srv.execute(response_diff.body()).unwrap()).unwrap();
+    assert_eq!(data.success_trace_id, success_trace_id);
+    assert_eq!(data.failure_trace_id, failure_trace_id);
+    assert_eq!(data.spans.len(), 2);
+    assert_eq!(
+        data.spans[0].path,
+        vec!["shop:test_checkout", "shop:get_stock"]
+    );
+    assert_eq!(data.spans[0].change, SpanChange::Changed);
+    assert_eq!(data.spans[0].changed_tags[0].tag, "http.status_code");
+    assert_eq!(
+        data.spans[0].changed_tags[0].success,
+        Some("200".to_string())
+    );
+    assert_eq!(
+        data.spans[0].changed_tags[0].failure,
+        Some("503".to_string())
+    );
+    assert_eq!(data.spans[1].path[1], "shop:retry_stock");
+    assert_eq!(data.spans[1].change, SpanChange::Added);
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}
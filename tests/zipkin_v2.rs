@@ -0,0 +1,124 @@
+extern crate actix_web;
+extern crate serde_json;
+extern crate uuid;
+
+extern crate ikrelln;
+
+mod helpers;
+
+use std::{thread, time};
+
+use actix_web::*;
+
+use ikrelln::opentracing::Span;
+
+use helpers::SpanBuilder;
+
+#[test]
+fn can_query_traces_with_v2_api() {
+    helpers::setup_logger();
+    let mut srv = helpers::setup_server();
+
+    let trace_id = uuid::Uuid::new_v4().to_string();
+    let service_name = uuid::Uuid::new_v4().to_string();
+    let remote_service_name = uuid::Uuid::new_v4().to_string();
+    let status_code = uuid::Uuid::new_v4().to_string();
+
+    let span = || {
+        SpanBuilder::new("get /v2")
+            .service(&service_name)
+            .timestamp(1_500_000_000_123_456)
+    };
+    let spans = vec![
+        span().trace(&trace_id).build(),
+        span()
+            .child_of(&trace_id, &trace_id)
+            .remote_service(&remote_service_name)
+            .tag("http.status_code", &status_code)
+            .build(),
+    ];
+
+    let req = srv
+        .client(http::Method::POST, "/api/v2/spans")
+        .json(spans)
+        .unwrap();
+    let response = srv.execute(req.send()).unwrap();
+    assert!(response.status().is_success());
+
+    thread::sleep(time::Duration::from_millis(
+        helpers::DELAY_SPAN_SAVED_MILLISECONDS,
+    ));
+
+    let req_trace = srv
+        .client(http::Method::GET, &format!("/api/v2/trace/{}", &trace_id))
+        .finish()
+        .unwrap();
+    let response_trace = srv.execute(req_trace.send()).unwrap();
+    assert!(response_trace.status().is_success());
+    let data_trace: Vec<Span> =
+        serde_json::from_slice(&srv.execute(response_trace.body()).unwrap()).unwrap();
+    assert_eq!(data_trace.len(), 2);
+    assert!(data_trace
+        .iter()
+        .all(|span| span.timestamp == Some(1_500_000_000_123_456)));
+
+    let req_remote = srv
+        .client(
+            http::Method::GET,
+            &format!("/api/v2/remoteServices?serviceName={}", &service_name),
+        )
+        .finish()
+        .unwrap();
+    let response_remote = srv.execute(req_remote.send()).unwrap();
+    assert!(response_remote.status().is_success());
+    let data_remote: Vec<String> =
+        serde_json::from_slice(&srv.execute(response_remote.body()).unwrap()).unwrap();
+    assert_eq!(data_remote, vec![remote_service_name.clone()]);
+
+    let req_traces = srv
+        .client(
+            http::Method::GET,
+            &format!(
+                "/api/v2/traces?serviceName={}&remoteServiceName={}&annotationQuery=http.status_code%3D{}&endTs=1500000001000&lookback=10000",
+                &service_name, &remote_service_name, &status_code
+            ),
+        )
+        .finish()
+        .unwrap();
+    let response_traces = srv.execute(req_traces.send()).unwrap();
+    assert!(response_traces.status().is_success());
+    let data_traces: Vec<Vec<Span>> =
+        serde_json::from_slice(&srv.execute(response_traces.body()).unwrap()).unwrap();
+    assert_eq!(data_traces.len(), 1);
+    assert_eq!(data_traces[0].len(), 2);
+
+    let req_no_traces = srv
+        .client(
+            http::Method::GET,
+            &format!(
+                "/api/v2/traces?serviceName={}&annotationQuery=http.status_code%3Dnot-{}&endTs=1500000001000&lookback=10000",
+                &service_name, &status_code
+            ),
+        )
+        .finish()
+        .unwrap();
+    let response_no_traces = srv.execute(req_no_traces.send()).unwrap();
+    assert!(response_no_traces.status().is_success());
+    let data_no_traces: Vec<Vec<Span>> =
+        serde_json::from_slice(&srv.execute(response_no_traces.body()).unwrap()).unwrap();
+    assert_eq!(data_no_traces.len(), 0);
+
+    let req_values = srv
+        .client(
+            http::Method::GET,
+            "/api/v2/autocompleteValues?key=http.status_code",
+        )
+        .finish()
+        .unwrap();
+    let response_values = srv.execute(req_values.send()).unwrap();
+    assert!(response_values.status().is_success());
+    let data_values: Vec<String> =
+        serde_json::from_slice(&srv.execute(response_values.body()).unwrap()).unwrap();
+    assert!(data_values.contains(&status_code));
+    thread::sleep(time::Duration::from_millis(helpers::DELAY_FINISH));
+}